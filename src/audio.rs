@@ -0,0 +1,338 @@
+//! Audio sources and sinks.
+//!
+//! [`AudioSource`] and [`AudioSink`] abstract where frames come from and go to, so application
+//! code like meters, recorders or players can be written once and run both with
+//! [`I2sTransfer`](crate::transfer::I2sTransfer) on target and with in-memory buffers, or the
+//! `sim` module of the `trace` feature, in host tests. Frames are anything implementing
+//! [`FromRawFrame`](crate::transfer::FromRawFrame) or [`ToRawFrame`](crate::transfer::ToRawFrame)
+//! for transfers, and any type for buffers.
+//!
+//! Like `I2sTransfer`, sources and sinks are non-blocking. [`AudioSource::frames()`] gives a
+//! blocking iterator.
+//!
+//! ```
+//! # use stm32_i2s_v12x::audio::*;
+//! /// Peak level of the next `count` frames.
+//! fn peak<S: AudioSource<(i16, i16)>>(source: &mut S, count: usize) -> Result<u16, S::Error> {
+//!     let mut peak = 0;
+//!     for frame in source.frames().take(count) {
+//!         let (left, right) = frame?;
+//!         peak = peak.max(left.unsigned_abs()).max(right.unsigned_abs());
+//!     }
+//!     Ok(peak)
+//! }
+//!
+//! let mut source = BufferSource::new(&[(1, -2), (-300, 4)]);
+//! assert_eq!(peak(&mut source, 2), Ok(300));
+//! // the buffer is exhausted
+//! assert_eq!(peak(&mut source, 2), Err(EndOfBuffer));
+//! ```
+use core::marker::PhantomData;
+
+/// A non-blocking source of audio frames.
+pub trait AudioSource<F> {
+    /// Error reported when no more frame can be read.
+    type Error;
+
+    /// Read a frame.
+    fn read_frame(&mut self) -> nb::Result<F, Self::Error>;
+
+    /// Blocking iterator over frames of the source.
+    ///
+    /// The iterator ends after yielding the first error, even a recoverable one like a master
+    /// receiver overrun. Call `frames()` again to keep reading after such errors.
+    fn frames(&mut self) -> Frames<'_, Self, F>
+    where
+        Self: Sized,
+    {
+        Frames::new(self)
+    }
+}
+
+/// A non-blocking sink of audio frames.
+pub trait AudioSink<F> {
+    /// Error reported when no more frame can be written.
+    type Error;
+
+    /// Write a frame.
+    fn write_frame(&mut self, frame: F) -> nb::Result<(), Self::Error>;
+
+    /// Write (blocking) all frames from an iterator, stopping at the first error.
+    ///
+    /// A frame is written again each time the sink would block, so frames need to be cloned.
+    fn write_all_frames<T>(&mut self, frames: T) -> Result<(), Self::Error>
+    where
+        T: IntoIterator<Item = F>,
+        F: Clone,
+    {
+        for frame in frames {
+            nb::block!(self.write_frame(frame.clone()))?;
+        }
+        Ok(())
+    }
+}
+
+impl<F, S: AudioSource<F>> AudioSource<F> for &mut S {
+    type Error = S::Error;
+
+    fn read_frame(&mut self) -> nb::Result<F, S::Error> {
+        (**self).read_frame()
+    }
+}
+
+impl<F, S: AudioSink<F>> AudioSink<F> for &mut S {
+    type Error = S::Error;
+
+    fn write_frame(&mut self, frame: F) -> nb::Result<(), S::Error> {
+        (**self).write_frame(frame)
+    }
+}
+
+/// Blocking iterator over frames of an [`AudioSource`], ending at the first error.
+///
+/// This `struct` is created by [`AudioSource::frames()`].
+pub struct Frames<'a, S, F> {
+    source: &'a mut S,
+    errored: bool,
+    _frame: PhantomData<F>,
+}
+
+impl<'a, S, F> Frames<'a, S, F> {
+    pub(crate) fn new(source: &'a mut S) -> Self {
+        Self {
+            source,
+            errored: false,
+            _frame: PhantomData,
+        }
+    }
+}
+
+impl<'a, S: AudioSource<F>, F> Iterator for Frames<'a, S, F> {
+    type Item = Result<F, S::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.errored {
+            return None;
+        }
+        let frame = nb::block!(self.source.read_frame());
+        self.errored = frame.is_err();
+        Some(frame)
+    }
+}
+
+/// Error of in-memory sources and sinks, when a buffer is exhausted or full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EndOfBuffer;
+
+/// An [`AudioSource`] reading frames from a slice.
+#[derive(Debug, Clone)]
+pub struct BufferSource<'a, F> {
+    frames: &'a [F],
+    position: usize,
+}
+
+impl<'a, F> BufferSource<'a, F> {
+    /// Create a source reading `frames` from the start.
+    pub fn new(frames: &'a [F]) -> Self {
+        Self {
+            frames,
+            position: 0,
+        }
+    }
+
+    /// Number of frames read.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Frames not read yet.
+    pub fn remaining(&self) -> &'a [F] {
+        &self.frames[self.position..]
+    }
+}
+
+impl<'a, F: Copy> AudioSource<F> for BufferSource<'a, F> {
+    type Error = EndOfBuffer;
+
+    fn read_frame(&mut self) -> nb::Result<F, EndOfBuffer> {
+        let frame = self.frames.get(self.position).ok_or(EndOfBuffer)?;
+        self.position += 1;
+        Ok(*frame)
+    }
+}
+
+/// An [`AudioSink`] writing frames into a slice.
+#[derive(Debug)]
+pub struct BufferSink<'a, F> {
+    frames: &'a mut [F],
+    position: usize,
+}
+
+impl<'a, F> BufferSink<'a, F> {
+    /// Create a sink filling `frames` from the start.
+    pub fn new(frames: &'a mut [F]) -> Self {
+        Self {
+            frames,
+            position: 0,
+        }
+    }
+
+    /// Frames written so far.
+    pub fn written(&self) -> &[F] {
+        &self.frames[..self.position]
+    }
+
+    /// `true` when no more frame can be written.
+    pub fn is_full(&self) -> bool {
+        self.position == self.frames.len()
+    }
+}
+
+impl<'a, F> AudioSink<F> for BufferSink<'a, F> {
+    type Error = EndOfBuffer;
+
+    fn write_frame(&mut self, frame: F) -> nb::Result<(), EndOfBuffer> {
+        let slot = self.frames.get_mut(self.position).ok_or(EndOfBuffer)?;
+        *slot = frame;
+        self.position += 1;
+        Ok(())
+    }
+}
+
+/// A growable sink, never full.
+#[cfg(any(test, feature = "trace"))]
+impl<F> AudioSink<F> for std::vec::Vec<F> {
+    type Error = core::convert::Infallible;
+
+    fn write_frame(&mut self, frame: F) -> nb::Result<(), Self::Error> {
+        self.push(frame);
+        Ok(())
+    }
+}
+
+/// A queue of frames, exhausted when empty.
+#[cfg(any(test, feature = "trace"))]
+impl<F> AudioSource<F> for std::collections::VecDeque<F> {
+    type Error = EndOfBuffer;
+
+    fn read_frame(&mut self) -> nb::Result<F, EndOfBuffer> {
+        Ok(self.pop_front().ok_or(EndOfBuffer)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{BusConfig, SimI2sA, SimI2sB, SimLoopback, VirtualBus};
+    use crate::transfer::*;
+    use std::collections::VecDeque;
+    use std::vec::Vec;
+
+    /// Application code: scale frames from a source into a sink.
+    fn gain<I, O>(input: &mut I, output: &mut O, count: usize)
+    where
+        I: AudioSource<(i16, i16)>,
+        O: AudioSink<(i16, i16)>,
+        I::Error: core::fmt::Debug,
+        O::Error: core::fmt::Debug,
+    {
+        for frame in input.frames().take(count) {
+            let (left, right) = frame.unwrap();
+            nb::block!(output.write_frame((left / 2, right / 2))).unwrap();
+        }
+    }
+
+    /// Source with an overrun every third read, like a master receiver read too late.
+    struct Overruns(u32);
+
+    impl AudioSource<u32> for Overruns {
+        type Error = I2sTransferError;
+
+        fn read_frame(&mut self) -> nb::Result<u32, I2sTransferError> {
+            self.0 += 1;
+            match self.0 % 3 {
+                0 => Err(nb::Error::Other(I2sTransferError::Overrun)),
+                _ => Ok(self.0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_buffers() {
+        let input = [(2, -2), (4, -4), (6, -6)];
+        let mut source = BufferSource::new(&input);
+        let mut output = [(0, 0); 2];
+        let mut sink = BufferSink::new(&mut output);
+        gain(&mut source, &mut sink, 2);
+        assert!(sink.is_full());
+        assert_eq!(sink.write_frame((0, 0)), Err(nb::Error::Other(EndOfBuffer)));
+        assert_eq!(output, [(1, -1), (2, -2)]);
+        assert_eq!(source.remaining(), &[(6, -6)]);
+        let frames: Vec<_> = source.frames().collect();
+        assert_eq!(frames, [Ok((6, -6)), Err(EndOfBuffer)]);
+
+        let mut queue: VecDeque<_> = input.iter().copied().collect();
+        let mut recorded = Vec::new();
+        gain(&mut queue, &mut recorded, 3);
+        assert_eq!(recorded, [(1, -1), (2, -2), (3, -3)]);
+        recorded.write_all_frames(input.iter().copied()).unwrap();
+        assert_eq!(recorded.len(), 6);
+        // frames only need to be `Clone`
+        let blocks = [Vec::from([1i16, 2]), Vec::from([3])];
+        let mut written = Vec::new();
+        written.write_all_frames(blocks.iter().cloned()).unwrap();
+        assert_eq!(written, blocks);
+    }
+
+    #[test]
+    fn test_transfers() {
+        // the same application code runs on transfers
+        let bus = VirtualBus::new(BusConfig::new());
+        bus.install();
+        let mut transmitter = I2sTransferConfig::new_master().i2s_transfer(SimI2sA::new(&bus));
+        let mut receiver = I2sTransferConfig::new_slave()
+            .receive()
+            .i2s_transfer(SimI2sB::new(&bus));
+        let mut recorded = Vec::new();
+        let mut next = 2;
+        while recorded.len() < 8 {
+            if transmitter.write_frame((next, -next)).is_ok() {
+                next += 2;
+            }
+            if let Ok(frame) = AudioSource::<(i16, i16)>::read_frame(&mut receiver) {
+                nb::block!(recorded.write_frame((frame.0 / 2, frame.1 / 2))).unwrap();
+            }
+        }
+        assert!(recorded.windows(2).all(|w| w[1].0 == w[0].0 + 1));
+
+        // a master receiver yields frames until an overrun
+        let bus = VirtualBus::new(BusConfig::new());
+        bus.install();
+        let mut receiver = I2sTransferConfig::new_master()
+            .receive()
+            .i2s_transfer(SimI2sA::new(&bus));
+        let frames: Vec<_> = receiver.frames::<(i16, i16)>().take(4).collect();
+        assert_eq!(frames, [Ok((0, 0)); 4]);
+        // iteration stops at the first error, even a recoverable overrun
+        let mut source = Overruns(0);
+        let frames: Vec<_> = source.frames().collect();
+        assert_eq!(frames, [Ok(1), Ok(2), Err(I2sTransferError::Overrun)]);
+        let frames: Vec<_> = source.frames().collect();
+        assert_eq!(frames, [Ok(4), Ok(5), Err(I2sTransferError::Overrun)]);
+
+        let bus = VirtualBus::new(BusConfig::new().start_offset(9));
+        bus.install();
+        let mut loopback = SimLoopback::new(&bus, Msb, Data16Channel32);
+        let input: Vec<(i16, i16)> = (1..=20).map(|i| (i * 100, -i * 100)).collect();
+        loopback.write_all_frames(input.iter().copied()).unwrap();
+        let mut output = [(0, 0); 4];
+        gain(&mut loopback, &mut BufferSink::new(&mut output), 4);
+        // the receiver synchronizes on the first frames
+        let first = output[0].0 * 2 / 100;
+        assert!((1..=4).contains(&first));
+        assert!(output
+            .iter()
+            .zip(first..)
+            .all(|(f, i)| *f == (i * 50, -i * 50)));
+    }
+}
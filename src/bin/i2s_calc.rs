@@ -0,0 +1,191 @@
+//! Host tool for I2S clock and register calculations.
+//!
+//! This uses the same calculations as the drivers, so what is printed here is what the firmware
+//! will do.
+//!
+//! ```text
+//! i2s-calc rates <I2S_CLOCK> [--standard STD] [--format FMT] [--mclk] [RATE...]
+//! i2s-calc decode <I2SCFGR> <I2SPR> [I2S_CLOCK]
+//! ```
+use std::env;
+use std::process::exit;
+
+use stm32_i2s_v12x::clock;
+use stm32_i2s_v12x::driver::{decode_registers, DataFormat, I2sStandard, SlaveOrMaster};
+
+const USAGE: &str = "\
+Usage:
+  i2s-calc rates <I2S_CLOCK> [--standard STD] [--format FMT] [--mclk] [RATE...]
+  i2s-calc decode <I2SCFGR> <I2SPR> [I2S_CLOCK]
+
+Options:
+  --standard STD  philips (default), msb, lsb, pcm-short or pcm-long
+  --format FMT    16 (default), 16/32, 24 or 32
+  --mclk          master clock output enabled
+
+Frequencies are in Hz, up to 1 GHz for the i2s clock and 1 MHz for rates. Register values
+are hexadecimal.";
+
+fn fail(msg: &str) -> ! {
+    eprintln!("error: {}\n\n{}", msg, USAGE);
+    exit(2)
+}
+
+/// Highest accepted I2S clock. This is far above what devices support, but low enough to avoid
+/// overflows in calculations.
+const MAX_I2S_CLOCK: u32 = 1_000_000_000;
+
+/// Highest accepted sampling rate.
+const MAX_RATE: u32 = 1_000_000;
+
+fn check_freq(s: &str, max: u32) -> Result<u32, String> {
+    let freq = s
+        .replace('_', "")
+        .parse()
+        .map_err(|_| format!("invalid frequency '{}'", s))?;
+    if freq == 0 || freq > max {
+        return Err(format!("frequency '{}' out of range, 1 to {} Hz", s, max));
+    }
+    Ok(freq)
+}
+
+fn parse_freq(s: &str, max: u32) -> u32 {
+    check_freq(s, max).unwrap_or_else(|msg| fail(&msg))
+}
+
+fn parse_hex(s: &str) -> u32 {
+    let digits = s.trim_start_matches("0x").trim_start_matches("0X");
+    u32::from_str_radix(digits, 16)
+        .unwrap_or_else(|_| fail(&format!("invalid register value '{}'", s)))
+}
+
+fn parse_standard(s: &str) -> I2sStandard {
+    match s {
+        "philips" => I2sStandard::Philips,
+        "msb" => I2sStandard::Msb,
+        "lsb" => I2sStandard::Lsb,
+        "pcm-short" => I2sStandard::PcmShortSync,
+        "pcm-long" => I2sStandard::PcmLongSync,
+        _ => fail(&format!("unknown standard '{}'", s)),
+    }
+}
+
+fn parse_format(s: &str) -> DataFormat {
+    match s {
+        "16" | "16/16" => DataFormat::Data16Channel16,
+        "16/32" => DataFormat::Data16Channel32,
+        "24" | "24/32" => DataFormat::Data24Channel32,
+        "32" | "32/32" => DataFormat::Data32Channel32,
+        _ => fail(&format!("unknown data format '{}'", s)),
+    }
+}
+
+fn print_prescaler(
+    i2s_clock: u32,
+    odd: bool,
+    div: u8,
+    mclk: bool,
+    std: I2sStandard,
+    fmt: DataFormat,
+) {
+    let exact =
+        i2s_clock as f64 / (clock::coef(mclk, std, fmt) as f64 * clock::division(odd, div) as f64);
+    print!(" odd={} div={:<3} Fs={:>12.3} Hz", odd as u8, div, exact);
+    if mclk {
+        print!(" MCLK={:>9} Hz", clock::mclk_frequency(i2s_clock, odd, div));
+    }
+    print!(
+        " SCK={:>9} Hz",
+        clock::sck_frequency(i2s_clock, odd, div, mclk, std, fmt)
+    );
+}
+
+fn rates(args: &[String]) {
+    let mut i2s_clock = None;
+    let mut std = I2sStandard::Philips;
+    let mut fmt = DataFormat::Data16Channel16;
+    let mut mclk = false;
+    let mut rates = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--standard" => {
+                std = parse_standard(args.next().unwrap_or_else(|| fail("missing standard")))
+            }
+            "--format" => fmt = parse_format(args.next().unwrap_or_else(|| fail("missing format"))),
+            "--mclk" => mclk = true,
+            s if i2s_clock.is_none() => i2s_clock = Some(parse_freq(s, MAX_I2S_CLOCK)),
+            s => rates.push(parse_freq(s, MAX_RATE)),
+        }
+    }
+    let i2s_clock = i2s_clock.unwrap_or_else(|| fail("missing i2s clock"));
+    if rates.is_empty() {
+        rates.extend_from_slice(&clock::STANDARD_RATES);
+    }
+    println!(
+        "i2s clock {} Hz, {:?}, {:?}, master clock {}",
+        i2s_clock,
+        std,
+        fmt,
+        if mclk { "on" } else { "off" }
+    );
+    for freq in rates {
+        let nearest = clock::nearest_rate(i2s_clock, freq, mclk, std, fmt);
+        print!("{:>7} Hz:", freq);
+        print_prescaler(i2s_clock, nearest.odd, nearest.div, mclk, std, fmt);
+        println!(" error={:+} ppm", nearest.ppm);
+    }
+}
+
+fn decode(args: &[String]) {
+    if args.len() < 2 || args.len() > 3 {
+        fail("decode takes two register values and an optional i2s clock");
+    }
+    let config = decode_registers(parse_hex(&args[0]), parse_hex(&args[1]));
+    println!("{}", config);
+    if let Some(i2s_clock) = args.get(2).map(|s| parse_freq(s, MAX_I2S_CLOCK)) {
+        if config.role == SlaveOrMaster::Slave {
+            println!("slave: clocks are generated by another device");
+        } else if clock::division(config.odd, config.div) < 4 {
+            println!("invalid prescaler: division factor must be at least 4");
+        } else {
+            // with reserved data length, channel length is 32 bits
+            let fmt = config.data_format.unwrap_or(DataFormat::Data32Channel32);
+            print_prescaler(
+                i2s_clock,
+                config.odd,
+                config.div,
+                config.master_clock,
+                config.standard,
+                fmt,
+            );
+            println!();
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|s| s.as_str()) {
+        Some("rates") => rates(&args[1..]),
+        Some("decode") => decode(&args[1..]),
+        Some("-h") | Some("--help") => println!("{}", USAGE),
+        _ => fail("missing or unknown command"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_freq() {
+        assert_eq!(check_freq("48_000", MAX_RATE), Ok(48_000));
+        assert_eq!(check_freq("1000000", MAX_RATE), Ok(1_000_000));
+        assert!(check_freq("0", MAX_RATE).is_err());
+        assert!(check_freq("1_000_001", MAX_RATE).is_err());
+        assert!(check_freq("4_000_000_000", MAX_I2S_CLOCK).is_err());
+        assert!(check_freq("-1", MAX_I2S_CLOCK).is_err());
+        assert!(check_freq("48k", MAX_RATE).is_err());
+    }
+}
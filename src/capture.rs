@@ -0,0 +1,468 @@
+//! Export and import of I2S line captures.
+//!
+//! Line states produced by the [`waveform`](crate::waveform) model can be written in VCD (Value
+//! Change Dump) format, to be displayed with tools like GTKWave. Logic analyzer captures can be
+//! read from VCD or CSV files, then decoded into frames:
+//! ```
+//! # use stm32_i2s_v12x::capture::*;
+//! # use stm32_i2s_v12x::waveform::*;
+//! # use stm32_i2s_v12x::driver::ClockPolarity;
+//! # use stm32_i2s_v12x::marker::*;
+//! let csv = "Time [s],CK,WS,SD\n0.0,0,0,0\n";
+//! let lines = read_csv(csv, Signals::default().names("CK", "WS", "SD")).unwrap();
+//! let waveform = Waveform::for_transfer::<Philips, Data16Channel16>(ClockPolarity::IdleLow);
+//! for frame in waveform.decode(lines) {
+//!     let (left, right): (i16, i16) = frame.to_transfer::<Philips, Data16Channel16, _>();
+//! }
+//! ```
+//!
+//! Everything works on in-memory text and [`core::fmt::Write`], so it doesn't require `std`.
+use core::fmt;
+
+use crate::clock;
+use crate::waveform::{LineState, Waveform};
+
+/// Line of an I2S bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Line {
+    /// Serial clock.
+    Ck,
+    /// Word select.
+    Ws,
+    /// Serial data.
+    Sd,
+}
+
+/// Error when reading a capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CaptureError {
+    /// The signal of a line was not found.
+    MissingSignal(Line),
+    /// Unexpected content at a given line of the file (starting at 1).
+    Syntax(usize),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::MissingSignal(line) => write!(f, "no signal for {:?} line", line),
+            CaptureError::Syntax(line) => write!(f, "syntax error at line {}", line),
+        }
+    }
+}
+
+/// Names of signals in a capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signals<'n> {
+    /// Name of the serial clock signal.
+    pub ck: &'n str,
+    /// Name of the word select signal.
+    pub ws: &'n str,
+    /// Name of the serial data signal.
+    pub sd: &'n str,
+}
+
+impl Default for Signals<'static> {
+    /// Signals named `ck`, `ws` and `sd`, as written by [`write_vcd`].
+    fn default() -> Self {
+        Signals {
+            ck: "ck",
+            ws: "ws",
+            sd: "sd",
+        }
+    }
+}
+
+impl<'n> Signals<'n> {
+    /// Set signal names.
+    pub fn names<'m>(self, ck: &'m str, ws: &'m str, sd: &'m str) -> Signals<'m> {
+        Signals { ck, ws, sd }
+    }
+}
+
+/// Write line states in VCD format.
+///
+/// `sample_rate` is the sampling frequency, used to compute timings. When `master_clock` is
+/// `true`, a `mck` signal is also written, at 256 times the sampling frequency (128 times with
+/// PCM standards).
+pub fn write_vcd<W, I>(
+    out: &mut W,
+    waveform: &Waveform,
+    sample_rate: u32,
+    master_clock: bool,
+    lines: I,
+) -> fmt::Result
+where
+    W: fmt::Write,
+    I: IntoIterator<Item = LineState>,
+{
+    let std = waveform.standard();
+    let fmt = waveform.data_format();
+    let bits_per_frame = clock::bits_per_frame(std, fmt);
+    // number of steps per line state, a step being a half period of the fastest clock
+    let (steps_per_line, clock_freq) = match master_clock {
+        true => {
+            let mclk_ratio = clock::coef(true, std, fmt);
+            (mclk_ratio / bits_per_frame, mclk_ratio * sample_rate)
+        }
+        false => (1, bits_per_frame * sample_rate),
+    };
+    let time = |step: u64| step as u128 * 1_000_000_000_000 / (2 * clock_freq as u128);
+
+    writeln!(out, "$timescale 1 ps $end")?;
+    writeln!(out, "$scope module i2s $end")?;
+    writeln!(out, "$var wire 1 c ck $end")?;
+    writeln!(out, "$var wire 1 w ws $end")?;
+    writeln!(out, "$var wire 1 d sd $end")?;
+    if master_clock {
+        writeln!(out, "$var wire 1 m mck $end")?;
+    }
+    writeln!(out, "$upscope $end")?;
+    writeln!(out, "$enddefinitions $end")?;
+
+    let mut previous: Option<LineState> = None;
+    let mut step = 0;
+    for line in lines {
+        for sub_step in 0..steps_per_line {
+            let changed = |f: fn(&LineState) -> bool| previous.map(|p| f(&p)) != Some(f(&line));
+            let changes = [
+                (sub_step == 0 && changed(|l| l.ck), line.ck, 'c'),
+                (sub_step == 0 && changed(|l| l.ws), line.ws, 'w'),
+                (sub_step == 0 && changed(|l| l.sd), line.sd, 'd'),
+                (master_clock, step % 2 != 0, 'm'),
+            ];
+            if changes.iter().any(|change| change.0) {
+                writeln!(out, "#{}", time(step))?;
+            }
+            for &(_, value, id) in changes.iter().filter(|change| change.0) {
+                writeln!(out, "{}{}", value as u8, id)?;
+            }
+            step += 1;
+        }
+        previous = Some(line);
+    }
+    writeln!(out, "#{}", time(step))
+}
+
+/// Tokens of a text, with their line number.
+#[derive(Debug, Clone)]
+struct Tokens<'a> {
+    rest: &'a str,
+    line: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(text: &'a str) -> Self {
+        Tokens {
+            rest: text,
+            line: 1,
+        }
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.rest.find(|c: char| !c.is_whitespace())?;
+        self.line += self.rest[..start].matches('\n').count();
+        let rest = &self.rest[start..];
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        self.rest = &rest[end..];
+        Some((self.line, &rest[..end]))
+    }
+}
+
+/// Skip tokens until `$end`.
+fn skip_section(tokens: &mut Tokens<'_>) -> Result<(), CaptureError> {
+    let line = tokens.line;
+    match tokens.any(|(_, token)| token == "$end") {
+        true => Ok(()),
+        false => Err(CaptureError::Syntax(line)),
+    }
+}
+
+/// Outcome of processing a VCD body token.
+enum Step {
+    /// A line state is complete.
+    State(LineState),
+    /// The token didn't complete a line state.
+    Pending,
+    /// End of the file.
+    End,
+}
+
+/// Line states read from a VCD file, created by [`read_vcd`].
+///
+/// A line state is produced for each timestamp where one of the signals changed.
+#[derive(Debug, Clone)]
+pub struct VcdReader<'a> {
+    tokens: Tokens<'a>,
+    ids: [&'a str; 3],
+    state: LineState,
+    /// A timestamp was read.
+    started: bool,
+    /// Signals changed since the last produced line state. The first state is always produced.
+    changed: bool,
+}
+
+impl<'a> VcdReader<'a> {
+    /// Process the next body token.
+    fn step(&mut self) -> Result<Step, CaptureError> {
+        let (line, token) = match self.tokens.next() {
+            Some(token) => token,
+            None if self.started && core::mem::replace(&mut self.changed, false) => {
+                return Ok(Step::State(self.state))
+            }
+            None => return Ok(Step::End),
+        };
+        let mut chars = token.chars();
+        match chars.next() {
+            Some('#') => {
+                if token[1..].parse::<u64>().is_err() {
+                    return Err(CaptureError::Syntax(line));
+                }
+                let started = core::mem::replace(&mut self.started, true);
+                if started && core::mem::replace(&mut self.changed, false) {
+                    return Ok(Step::State(self.state));
+                }
+            }
+            Some(value @ ('0' | '1' | 'x' | 'X' | 'z' | 'Z')) if token.len() > 1 => {
+                let id = chars.as_str();
+                let level = value == '1';
+                let lines = [&mut self.state.ck, &mut self.state.ws, &mut self.state.sd];
+                for (signal_id, state) in self.ids.iter().zip(IntoIterator::into_iter(lines)) {
+                    if *signal_id == id {
+                        self.changed |= *state != level;
+                        *state = level;
+                    }
+                }
+            }
+            // vector and real values, followed by an identifier
+            Some('b' | 'B' | 'r' | 'R') => {
+                self.tokens.next().ok_or(CaptureError::Syntax(line))?;
+            }
+            Some('$') => match token {
+                "$dumpvars" | "$dumpall" | "$dumpon" | "$dumpoff" | "$end" => {}
+                _ => skip_section(&mut self.tokens)?,
+            },
+            _ => return Err(CaptureError::Syntax(line)),
+        }
+        Ok(Step::Pending)
+    }
+}
+
+impl<'a> Iterator for VcdReader<'a> {
+    type Item = LineState;
+
+    fn next(&mut self) -> Option<LineState> {
+        loop {
+            match self.step() {
+                Ok(Step::State(state)) => return Some(state),
+                Ok(Step::Pending) => {}
+                Ok(Step::End) | Err(_) => return None,
+            }
+        }
+    }
+}
+
+/// Read a VCD file.
+///
+/// Signals are found by their reference name, whatever their scope. The whole file is checked
+/// before returning, so reading line states can't fail.
+pub fn read_vcd<'a>(text: &'a str, signals: Signals<'_>) -> Result<VcdReader<'a>, CaptureError> {
+    let mut tokens = Tokens::new(text);
+    let mut ids: [Option<&'a str>; 3] = [None; 3];
+    let names = [signals.ck, signals.ws, signals.sd];
+    loop {
+        let (line, token) = tokens.next().ok_or(CaptureError::Syntax(tokens.line))?;
+        match token {
+            "$var" => {
+                // $var type size identifier reference [index] $end
+                let mut fields = [""; 4];
+                for field in fields.iter_mut() {
+                    *field = tokens.next().ok_or(CaptureError::Syntax(line))?.1;
+                }
+                for (id, name) in ids.iter_mut().zip(names.iter()) {
+                    if fields[3] == *name {
+                        *id = Some(fields[2]);
+                    }
+                }
+                if fields[3] != "$end" {
+                    skip_section(&mut tokens)?;
+                }
+            }
+            "$enddefinitions" => {
+                skip_section(&mut tokens)?;
+                break;
+            }
+            _ if token.starts_with('$') => skip_section(&mut tokens)?,
+            _ => return Err(CaptureError::Syntax(line)),
+        }
+    }
+    let lines = [Line::Ck, Line::Ws, Line::Sd];
+    let mut found = [""; 3];
+    for ((id, found), line) in ids.iter().zip(found.iter_mut()).zip(lines.iter()) {
+        *found = id.ok_or(CaptureError::MissingSignal(*line))?;
+    }
+    let reader = VcdReader {
+        tokens,
+        ids: found,
+        state: LineState::default(),
+        started: false,
+        changed: true,
+    };
+    // check the body
+    let mut check = reader.clone();
+    loop {
+        if let Step::End = check.step()? {
+            break;
+        }
+    }
+    Ok(reader)
+}
+
+/// Line states read from a CSV file, created by [`read_csv`].
+///
+/// A line state is produced for each row.
+#[derive(Debug, Clone)]
+pub struct CsvReader<'a> {
+    rows: core::str::Lines<'a>,
+    columns: [usize; 3],
+}
+
+/// Parse a CSV row into a line state.
+fn parse_row(row: &str, columns: &[usize; 3]) -> Option<LineState> {
+    let mut levels = [false; 3];
+    for (level, &column) in levels.iter_mut().zip(columns.iter()) {
+        let field = row.split(',').nth(column)?.trim().trim_matches('"');
+        *level = field.parse::<f32>().ok()? != 0.0;
+    }
+    Some(LineState {
+        ck: levels[0],
+        ws: levels[1],
+        sd: levels[2],
+    })
+}
+
+impl<'a> Iterator for CsvReader<'a> {
+    type Item = LineState;
+
+    fn next(&mut self) -> Option<LineState> {
+        let row = self.rows.find(|row| !row.trim().is_empty())?;
+        parse_row(row, &self.columns)
+    }
+}
+
+/// Read a CSV file, as exported by logic analyzer software.
+///
+/// The first row must contain column names. Other columns, like time, are ignored. Values are
+/// numbers, non-zero meaning high level. The whole file is checked before returning, so reading
+/// line states can't fail.
+pub fn read_csv<'a>(text: &'a str, signals: Signals<'_>) -> Result<CsvReader<'a>, CaptureError> {
+    let mut rows = text.lines();
+    let header = rows.next().ok_or(CaptureError::Syntax(1))?;
+    let names = [signals.ck, signals.ws, signals.sd];
+    let lines = [Line::Ck, Line::Ws, Line::Sd];
+    let mut columns = [0; 3];
+    for ((column, name), line) in columns.iter_mut().zip(names.iter()).zip(lines.iter()) {
+        *column = header
+            .split(',')
+            .position(|field| field.trim().trim_matches('"') == *name)
+            .ok_or(CaptureError::MissingSignal(*line))?;
+    }
+    for (index, row) in rows.clone().enumerate() {
+        if !row.trim().is_empty() && parse_row(row, &columns).is_none() {
+            return Err(CaptureError::Syntax(index + 2));
+        }
+    }
+    Ok(CsvReader { rows, columns })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::{ClockPolarity, DataFormat, I2sStandard};
+    use crate::waveform::Frame;
+    use std::string::String;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_vcd_round_trip() {
+        let waveform = Waveform::new(
+            I2sStandard::Philips,
+            DataFormat::Data24Channel32,
+            ClockPolarity::IdleLow,
+        );
+        let frames = [
+            Frame {
+                left: 0x123456,
+                right: -2,
+            },
+            Frame {
+                left: -0x800000,
+                right: 0x7fffff,
+            },
+        ];
+        for &master_clock in [false, true].iter() {
+            let mut vcd = String::new();
+            let lines = waveform.encode(frames.iter().copied());
+            write_vcd(&mut vcd, &waveform, 48000, master_clock, lines).unwrap();
+            assert!(vcd.contains("$var wire 1 m mck $end") == master_clock);
+            // one SCK half period at 48 kHz with 64 bits per frame
+            assert!(vcd.contains("\n#162760\n"));
+            let reader = read_vcd(&vcd, Signals::default()).unwrap();
+            let decoded: Vec<_> = waveform.decode(reader).collect();
+            assert_eq!(decoded, frames);
+        }
+    }
+
+    #[test]
+    fn test_read_vcd() {
+        let vcd = "$date today $end\n$timescale 1 ns $end\n$scope module top $end\n\
+                   $var wire 1 ! SCK $end\n$var wire 1 \" LRCK $end\n\
+                   $var wire 8 # bus [7:0] $end\n$var wire 1 $ SD $end\n\
+                   $upscope $end\n$enddefinitions $end\n\
+                   #0\n$dumpvars\n0!\n1\"\nx$\nb0 #\n$end\n#10\n1!\n1$\nb101 #\n#20\nb1 #\n#30\n0!\n";
+        let signals = Signals::default().names("SCK", "LRCK", "SD");
+        let lines: Vec<_> = read_vcd(vcd, signals).unwrap().collect();
+        let expected = [
+            (false, true, false),
+            (true, true, true),
+            (false, true, true),
+        ];
+        let lines: Vec<_> = lines.iter().map(|l| (l.ck, l.ws, l.sd)).collect();
+        assert_eq!(lines, expected);
+
+        let missing = Signals::default().names("SCK", "WS", "SD");
+        assert_eq!(
+            read_vcd(vcd, missing).unwrap_err(),
+            CaptureError::MissingSignal(Line::Ws)
+        );
+        let broken = vcd.replace("#20", "?20");
+        assert_eq!(
+            read_vcd(&broken, signals).unwrap_err(),
+            CaptureError::Syntax(21)
+        );
+    }
+
+    #[test]
+    fn test_read_csv() {
+        let csv = "Time [s], \"D0\", \"D1\", \"D2\"\n0.0, 0, 1, 0\n\n1e-6, 1, 1, 1\n";
+        let signals = Signals::default().names("D0", "D1", "D2");
+        let lines: Vec<_> = read_csv(csv, signals).unwrap().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(!lines[0].ck && lines[0].ws && !lines[0].sd);
+        assert!(lines[1].ck && lines[1].ws && lines[1].sd);
+        assert_eq!(
+            read_csv(csv, Signals::default()).unwrap_err(),
+            CaptureError::MissingSignal(Line::Ck)
+        );
+        let broken = csv.replace("1e-6, 1,", "1e-6, high,");
+        assert_eq!(
+            read_csv(&broken, signals).unwrap_err(),
+            CaptureError::Syntax(4)
+        );
+    }
+}
@@ -0,0 +1,387 @@
+//! Channel mapping.
+//!
+//! With `Philips`, `Msb` and `Lsb` standards, frames have a left and a right channel. A
+//! [`ChannelMap`] converts between those stereo frames and the frames of the application, for
+//! example to play a mono voice on both channels, or to swap channels:
+//!  - [`Duplicate`]: a mono sample on both channels, reading the left channel,
+//!  - [`LeftOnly`] and [`RightOnly`]: a mono sample on one channel, the other being silent,
+//!  - [`Swap`]: stereo frames with left and right channels swapped,
+//!  - [`Downmix`]: the average of both channels, duplicated when writing,
+//!  - [`Routing`]: a stereo to stereo mapping selected at runtime.
+//!
+//! [`Mapped`] applies a mapping to any [`AudioSink`] or [`AudioSource`] of stereo frames, like an
+//! `I2sTransfer`. Fixed mappings are zero-sized types, so they cost nothing besides the
+//! conversion itself.
+//!
+//! ```
+//! # use stm32_i2s_v12x::audio::*;
+//! # use stm32_i2s_v12x::channel::*;
+//! let mut output = [(0, 0); 2];
+//! // an I2sTransfer in a real application
+//! let mut sink = Mapped::new(BufferSink::new(&mut output), Duplicate);
+//! sink.write_frame(1i16).unwrap();
+//! sink.write_frame(-2).unwrap();
+//! drop(sink);
+//! assert_eq!(output, [(1, 1), (-2, -2)]);
+//! ```
+//!
+//! This crate has no DMA or interrupt streaming types: with those, the driver transfers buffers
+//! of interleaved samples (`L, R, L, R...`), that can be converted with
+//! [`ChannelMap::to_interleaved()`] and [`ChannelMap::to_frames()`].
+use core::marker::PhantomData;
+
+use crate::audio::{AudioSink, AudioSource};
+use crate::sample::{Q15, Q31};
+
+/// Sample types supported by channel mappings.
+pub trait ChannelSample: Copy + Default {
+    /// Average of two samples, rounded toward negative infinity for integers.
+    fn average(a: Self, b: Self) -> Self;
+}
+
+macro_rules! impl_channel_sample {
+    ($(($type:ty, $wide:ty)),*) => {
+        $(
+            impl ChannelSample for $type {
+                #[inline]
+                fn average(a: Self, b: Self) -> Self {
+                    ((a as $wide + b as $wide) >> 1) as $type
+                }
+            }
+        )*
+    };
+}
+
+impl_channel_sample!((i16, i32), (i32, i64));
+
+impl ChannelSample for f32 {
+    #[inline]
+    fn average(a: Self, b: Self) -> Self {
+        (a + b) * 0.5
+    }
+}
+
+impl ChannelSample for Q15 {
+    #[inline]
+    fn average(a: Self, b: Self) -> Self {
+        Q15(i16::average(a.0, b.0))
+    }
+}
+
+impl ChannelSample for Q31 {
+    #[inline]
+    fn average(a: Self, b: Self) -> Self {
+        Q31(i32::average(a.0, b.0))
+    }
+}
+
+/// Conversion between application frames and `(left, right)` frames of samples `S`.
+pub trait ChannelMap<S: ChannelSample> {
+    /// Frame of the application.
+    type Frame: Copy;
+
+    /// Stereo frame to write for an application frame.
+    fn to_stereo(&self, frame: Self::Frame) -> (S, S);
+
+    /// Application frame for a stereo frame that was read.
+    fn to_frame(&self, frame: (S, S)) -> Self::Frame;
+
+    /// Convert application frames into interleaved samples (`L, R, L, R...`).
+    ///
+    /// # Panics
+    ///
+    /// `samples` must contain two samples per frame.
+    fn to_interleaved(&self, frames: &[Self::Frame], samples: &mut [S]) {
+        assert_eq!(samples.len(), frames.len() * 2);
+        for (frame, samples) in frames.iter().zip(samples.chunks_exact_mut(2)) {
+            let (left, right) = self.to_stereo(*frame);
+            samples[0] = left;
+            samples[1] = right;
+        }
+    }
+
+    /// Convert interleaved samples (`L, R, L, R...`) into application frames.
+    ///
+    /// # Panics
+    ///
+    /// `samples` must contain two samples per frame.
+    fn to_frames(&self, samples: &[S], frames: &mut [Self::Frame]) {
+        assert_eq!(samples.len(), frames.len() * 2);
+        for (frame, samples) in frames.iter_mut().zip(samples.chunks_exact(2)) {
+            *frame = self.to_frame((samples[0], samples[1]));
+        }
+    }
+}
+
+/// Mono samples written on both channels, and read from the left channel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Duplicate;
+
+impl<S: ChannelSample> ChannelMap<S> for Duplicate {
+    type Frame = S;
+
+    #[inline]
+    fn to_stereo(&self, sample: S) -> (S, S) {
+        (sample, sample)
+    }
+
+    #[inline]
+    fn to_frame(&self, (left, _): (S, S)) -> S {
+        left
+    }
+}
+
+/// Mono samples on the left channel, the right channel being silent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LeftOnly;
+
+impl<S: ChannelSample> ChannelMap<S> for LeftOnly {
+    type Frame = S;
+
+    #[inline]
+    fn to_stereo(&self, sample: S) -> (S, S) {
+        (sample, S::default())
+    }
+
+    #[inline]
+    fn to_frame(&self, (left, _): (S, S)) -> S {
+        left
+    }
+}
+
+/// Mono samples on the right channel, the left channel being silent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RightOnly;
+
+impl<S: ChannelSample> ChannelMap<S> for RightOnly {
+    type Frame = S;
+
+    #[inline]
+    fn to_stereo(&self, sample: S) -> (S, S) {
+        (S::default(), sample)
+    }
+
+    #[inline]
+    fn to_frame(&self, (_, right): (S, S)) -> S {
+        right
+    }
+}
+
+/// Stereo frames with left and right channels swapped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Swap;
+
+impl<S: ChannelSample> ChannelMap<S> for Swap {
+    type Frame = (S, S);
+
+    #[inline]
+    fn to_stereo(&self, (left, right): (S, S)) -> (S, S) {
+        (right, left)
+    }
+
+    #[inline]
+    fn to_frame(&self, (left, right): (S, S)) -> (S, S) {
+        (right, left)
+    }
+}
+
+/// Mono samples read as the average of both channels, and written on both channels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Downmix;
+
+impl<S: ChannelSample> ChannelMap<S> for Downmix {
+    type Frame = S;
+
+    #[inline]
+    fn to_stereo(&self, sample: S) -> (S, S) {
+        (sample, sample)
+    }
+
+    #[inline]
+    fn to_frame(&self, (left, right): (S, S)) -> S {
+        S::average(left, right)
+    }
+}
+
+/// Stereo to stereo mapping selected at runtime, applied the same way when writing and reading.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Routing {
+    /// Frames are unchanged.
+    #[default]
+    Straight,
+    /// Left and right channels are swapped.
+    Swap,
+    /// The left channel is duplicated on both channels.
+    Left,
+    /// The right channel is duplicated on both channels.
+    Right,
+    /// The average of both channels is duplicated on both channels.
+    Downmix,
+}
+
+impl Routing {
+    fn apply<S: ChannelSample>(&self, (left, right): (S, S)) -> (S, S) {
+        match self {
+            Routing::Straight => (left, right),
+            Routing::Swap => (right, left),
+            Routing::Left => (left, left),
+            Routing::Right => (right, right),
+            Routing::Downmix => {
+                let mono = S::average(left, right);
+                (mono, mono)
+            }
+        }
+    }
+}
+
+impl<S: ChannelSample> ChannelMap<S> for Routing {
+    type Frame = (S, S);
+
+    fn to_stereo(&self, frame: (S, S)) -> (S, S) {
+        self.apply(frame)
+    }
+
+    fn to_frame(&self, frame: (S, S)) -> (S, S) {
+        self.apply(frame)
+    }
+}
+
+/// An [`AudioSink`] or [`AudioSource`] of stereo frames, seen through a [`ChannelMap`].
+///
+/// `S` is the sample type of stereo frames, usually inferred.
+#[derive(Debug)]
+pub struct Mapped<T, M, S> {
+    inner: T,
+    map: M,
+    _sample: PhantomData<S>,
+}
+
+impl<T, M, S> Mapped<T, M, S> {
+    /// Apply `map` to `inner`, which can also be a mutable reference.
+    pub fn new(inner: T, map: M) -> Self {
+        Self {
+            inner,
+            map,
+            _sample: PhantomData,
+        }
+    }
+
+    /// Get a reference to the wrapped sink or source.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the wrapped sink or source.
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Change the mapping.
+    pub fn set_map(&mut self, map: M) {
+        self.map = map;
+    }
+
+    /// Give back the wrapped sink or source.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T, M, S> AudioSink<M::Frame> for Mapped<T, M, S>
+where
+    T: AudioSink<(S, S)>,
+    M: ChannelMap<S>,
+    S: ChannelSample,
+{
+    type Error = T::Error;
+
+    #[inline]
+    fn write_frame(&mut self, frame: M::Frame) -> nb::Result<(), T::Error> {
+        self.inner.write_frame(self.map.to_stereo(frame))
+    }
+}
+
+impl<T, M, S> AudioSource<M::Frame> for Mapped<T, M, S>
+where
+    T: AudioSource<(S, S)>,
+    M: ChannelMap<S>,
+    S: ChannelSample,
+{
+    type Error = T::Error;
+
+    #[inline]
+    fn read_frame(&mut self) -> nb::Result<M::Frame, T::Error> {
+        Ok(self.map.to_frame(self.inner.read_frame()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::BufferSource;
+    use crate::sim::{BusConfig, SimI2sA, SimI2sB, VirtualBus};
+    use crate::transfer::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_maps() {
+        let frame = (100i16, -301i16);
+        assert_eq!(Duplicate.to_stereo(7i16), (7, 7));
+        assert_eq!(Duplicate.to_frame(frame), 100);
+        assert_eq!(LeftOnly.to_stereo(Q15(7)), (Q15(7), Q15(0)));
+        assert_eq!(RightOnly.to_stereo(7.0f32), (0.0, 7.0));
+        assert_eq!(RightOnly.to_frame(frame), -301);
+        assert_eq!(Swap.to_stereo(frame), (-301, 100));
+        assert_eq!(Downmix.to_frame(frame), -101);
+        assert_eq!(Downmix.to_frame((i32::MAX, i32::MAX - 2)), i32::MAX - 1);
+        assert_eq!(
+            Downmix.to_frame((Q31(i32::MIN), Q31(i32::MIN))),
+            Q31(i32::MIN)
+        );
+        assert_eq!(Downmix.to_frame((0.5f32, 0.25)), 0.375);
+        assert_eq!(Routing::Right.to_stereo(frame), (-301, -301));
+        assert_eq!(Routing::Downmix.to_frame(frame), (-101, -101));
+
+        let mut samples = [0i16; 4];
+        LeftOnly.to_interleaved(&[1, 2], &mut samples);
+        assert_eq!(samples, [1, 0, 2, 0]);
+        let mut frames = [(0, 0); 2];
+        Routing::Swap.to_frames(&samples, &mut frames);
+        assert_eq!(frames, [(0, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn test_mapped() {
+        let stereo = [(1i32, 3i32), (-4, 2)];
+        let mut source = Mapped::new(BufferSource::new(&stereo), Downmix);
+        assert_eq!(source.read_frame(), Ok(2));
+        assert_eq!(source.read_frame(), Ok(-1));
+        assert!(source.read_frame().is_err());
+
+        // a mono voice on both channels of a transfer, read back swapped
+        let bus = VirtualBus::new(BusConfig::new());
+        bus.install();
+        let mut transmitter = I2sTransferConfig::new_master().i2s_transfer(SimI2sA::new(&bus));
+        let mut receiver = I2sTransferConfig::new_slave()
+            .receive()
+            .i2s_transfer(SimI2sB::new(&bus));
+        let mut voice = Mapped::new(&mut transmitter, RightOnly);
+        let mut swapped = Mapped::new(&mut receiver, Swap);
+        let mut received = Vec::new();
+        let mut next = 1;
+        while received.len() < 4 {
+            if voice.write_frame(next).is_ok() {
+                next += 1;
+            }
+            match swapped.read_frame() {
+                // padding sent by the master before synchronization
+                Ok((0, 0)) if received.is_empty() => {}
+                Ok(frame) => received.push(frame),
+                Err(_) => {}
+            }
+        }
+        let first = received[0].0;
+        let expected: Vec<(i16, i16)> = (first..first + 4).map(|i| (i, 0)).collect();
+        assert_eq!(received, expected);
+    }
+}
@@ -0,0 +1,544 @@
+//! Clock and prescaler calculations.
+//!
+//! Those are the calculations used by drivers to configure the prescaler and to report the
+//! effective sampling rate. They don't touch any hardware, so they can also be used on a host,
+//! for example to check what a clock tree can achieve before bringing up a board.
+//!
+//! # Calculation details
+//!
+//! ```text
+//! Fs = i2s_clock / [128 * nb_chan * ((2 * div) + odd)] when master clock is enabled
+//! Fs = i2s_clock / [(channel_length * nb_chan) * ((2 * div) + odd)] when master clock is disabled
+//! ```
+//! where:
+//!  - `nb_chan` is 2 with Philips, Msb and LSB standards and 1 with Pcm standards.
+//!  - `channel_length` is 16 or 32
+//!
+//! can be rewritten as `Fs = i2s_clock / (coef * division)` where:
+//!  - `coef` is a constant that depends on i2s standard, channel length and master clock (see
+//!    [`coef`])
+//!  - and where `division = (2 * div) + odd`
+//!
+//! note: `division = (2 * div) + odd = (div << 1) + odd`, in other word, from bits point of view,
+//! `division[8:1] = div[7:0]` and `division[0] = odd`.
+use crate::driver::{DataFormat, I2sStandard};
+
+/// rounding division
+pub(crate) fn div_round(n: u32, d: u32) -> u32 {
+    (n + (d >> 1)) / d
+}
+
+/// Number of audio channels in a frame for the given standard.
+fn nb_chan(std: I2sStandard) -> u32 {
+    match std {
+        I2sStandard::Philips | I2sStandard::Msb | I2sStandard::Lsb => 2,
+        I2sStandard::PcmShortSync | I2sStandard::PcmLongSync => 1,
+    }
+}
+
+/// Width in bits of a channel for the given data format.
+fn channel_length(data_format: DataFormat) -> u32 {
+    match data_format {
+        DataFormat::Data16Channel16 => 16,
+        _ => 32,
+    }
+}
+
+/// Get the number of bit clock cycles in a frame, that is the SCK/Fs ratio.
+pub fn bits_per_frame(std: I2sStandard, data_format: DataFormat) -> u32 {
+    channel_length(data_format) * nb_chan(std)
+}
+
+/// Get the coefficient linking the i2s clock to the sampling frequency, that is
+/// `Fs = i2s_clock / (coef * division)`.
+pub fn coef(mclk: bool, std: I2sStandard, data_format: DataFormat) -> u32 {
+    if mclk {
+        return 128 * nb_chan(std);
+    }
+    bits_per_frame(std, data_format)
+}
+
+/// Get the division factor, `(2 * div) + odd`, of a prescaler configuration.
+pub fn division(odd: bool, div: u8) -> u32 {
+    ((div as u32) << 1) + odd as u32
+}
+
+/// Get the prescaler configuration, `(odd, div)`, giving the sampling frequency nearest to
+/// `request_freq`.
+///
+/// The division factor is clamped to the range supported by the hardware, 4 to 511.
+pub fn request_prescaler(
+    i2s_clock: u32,
+    request_freq: u32,
+    mclk: bool,
+    std: I2sStandard,
+    data_format: DataFormat,
+) -> (bool, u8) {
+    let coef = coef(mclk, std, data_format);
+    let division = div_round(i2s_clock, coef * request_freq);
+    if division < 4 {
+        (false, 2)
+    } else if division > 511 {
+        (true, 255)
+    } else {
+        ((division & 1) == 1, (division >> 1) as u8)
+    }
+}
+
+/// Get the prescaler configuration, `(odd, div)`, giving exactly the sampling frequency `freq`.
+///
+/// Return `None` if `freq` can't be reached exactly with a division factor from 4 to 511.
+#[allow(clippy::manual_range_contains)]
+pub fn require_prescaler(
+    i2s_clock: u32,
+    freq: u32,
+    mclk: bool,
+    std: I2sStandard,
+    data_format: DataFormat,
+) -> Option<(bool, u8)> {
+    let coef = coef(mclk, std, data_format);
+    let division = i2s_clock / (coef * freq);
+    let rem = i2s_clock % (coef * freq);
+    if rem == 0 && division >= 4 && division <= 511 {
+        Some(((division & 1) == 1, (division >> 1) as u8))
+    } else {
+        None
+    }
+}
+
+/// Get the sampling frequency (rounded down) produced by a prescaler configuration.
+pub fn sample_rate(
+    i2s_clock: u32,
+    odd: bool,
+    div: u8,
+    mclk: bool,
+    std: I2sStandard,
+    data_format: DataFormat,
+) -> u32 {
+    i2s_clock / (coef(mclk, std, data_format) * division(odd, div))
+}
+
+/// Get the master clock frequency produced by a prescaler configuration.
+///
+/// This is meaningful only when the master clock is enabled, and is then `256 * Fs`, or
+/// `128 * Fs` with PCM standards.
+pub fn mclk_frequency(i2s_clock: u32, odd: bool, div: u8) -> u32 {
+    i2s_clock / division(odd, div)
+}
+
+/// Get the bit clock (SCK) frequency produced by a prescaler configuration.
+pub fn sck_frequency(
+    i2s_clock: u32,
+    odd: bool,
+    div: u8,
+    mclk: bool,
+    std: I2sStandard,
+    data_format: DataFormat,
+) -> u32 {
+    let bits_per_frame = bits_per_frame(std, data_format);
+    let coef = coef(mclk, std, data_format);
+    // SCK = Fs * bits_per_frame, computed without intermediate rounding of Fs
+    (i2s_clock as u64 * bits_per_frame as u64 / (coef as u64 * division(odd, div) as u64)) as u32
+}
+
+/// Get the deviation, in parts per million and rounded to nearest, of the exact sampling
+/// frequency produced by a prescaler configuration from `freq`.
+pub fn ppm_error(
+    i2s_clock: u32,
+    odd: bool,
+    div: u8,
+    mclk: bool,
+    std: I2sStandard,
+    data_format: DataFormat,
+    freq: u32,
+) -> i32 {
+    // (i2s_clock / (coef * division) - freq) / freq * 1e6
+    let den = coef(mclk, std, data_format) as i64 * division(odd, div) as i64 * freq as i64;
+    let num = (i2s_clock as i64 - den) * 1_000_000;
+    let half = den / 2;
+    let ppm = if num >= 0 {
+        (num + half) / den
+    } else {
+        (num - half) / den
+    };
+    ppm as i32
+}
+
+/// Find an i2s clock frequency giving exactly the sampling frequency `freq`, among frequencies
+/// accepted by `is_reachable`.
+///
+/// Candidates are tried from the lowest to the highest clock frequency. Return `(i2s_clock, odd,
+/// div)`, or `None` if no candidate is accepted.
+pub fn find_exact_i2s_clock(
+    freq: u32,
+    mclk: bool,
+    std: I2sStandard,
+    data_format: DataFormat,
+    mut is_reachable: impl FnMut(u32) -> bool,
+) -> Option<(u32, bool, u8)> {
+    let coef = coef(mclk, std, data_format) as u64;
+    (4..=511u32)
+        .map(|division| (coef * division as u64 * freq as u64, division))
+        .take_while(|&(i2s_clock, _)| i2s_clock <= u32::MAX as u64)
+        .find(|&(i2s_clock, _)| is_reachable(i2s_clock as u32))
+        .map(|(i2s_clock, division)| (i2s_clock as u32, (division & 1) == 1, (division >> 1) as u8))
+}
+
+/// Iterator over every sampling frequency reachable with a clock configuration.
+///
+/// This is created by [`sample_rates`].
+#[derive(Debug, Clone)]
+pub struct SampleRates {
+    i2s_clock: u32,
+    coef: u32,
+    division: u32,
+}
+
+impl Iterator for SampleRates {
+    /// `(odd, div, exact_rate)`
+    type Item = (bool, u8, f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.division > 511 {
+            return None;
+        }
+        let division = self.division;
+        self.division += 1;
+        let exact_rate = self.i2s_clock as f64 / (self.coef as f64 * division as f64);
+        Some(((division & 1) == 1, (division >> 1) as u8, exact_rate))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = 512usize.saturating_sub(self.division as usize);
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for SampleRates {}
+
+/// Get an iterator over every reachable sampling frequency, from the highest to the lowest.
+///
+/// Items are `(odd, div, exact_rate)`, where `exact_rate` is the sampling frequency in Hz without
+/// rounding.
+pub fn sample_rates(
+    i2s_clock: u32,
+    mclk: bool,
+    std: I2sStandard,
+    data_format: DataFormat,
+) -> SampleRates {
+    SampleRates {
+        i2s_clock,
+        coef: coef(mclk, std, data_format),
+        division: 4,
+    }
+}
+
+/// Commonly used audio sampling frequencies.
+pub const STANDARD_RATES: [u32; 10] = [
+    8_000, 11_025, 16_000, 22_050, 32_000, 44_100, 48_000, 88_200, 96_000, 192_000,
+];
+
+/// How close a sampling frequency can be reached.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NearestRate {
+    /// The wanted sampling frequency.
+    pub freq: u32,
+    /// Odd factor of the prescaler.
+    pub odd: bool,
+    /// Linear divider of the prescaler.
+    pub div: u8,
+    /// Sampling frequency in Hz effectively produced by the prescaler, without rounding.
+    pub exact_rate: f64,
+    /// Deviation from `freq` in parts per million.
+    pub ppm: i32,
+}
+
+/// Get how close `freq` can be reached.
+///
+/// The prescaler is chosen the same way as when a frequency is requested from a driver
+/// configuration, so the result is what a driver will effectively produce.
+pub fn nearest_rate(
+    i2s_clock: u32,
+    freq: u32,
+    mclk: bool,
+    std: I2sStandard,
+    data_format: DataFormat,
+) -> NearestRate {
+    let (odd, div) = request_prescaler(i2s_clock, freq, mclk, std, data_format);
+    let exact_rate =
+        i2s_clock as f64 / (coef(mclk, std, data_format) as f64 * division(odd, div) as f64);
+    NearestRate {
+        freq,
+        odd,
+        div,
+        exact_rate,
+        ppm: ppm_error(i2s_clock, odd, div, mclk, std, data_format, freq),
+    }
+}
+
+/// Get how close each of the [`STANDARD_RATES`] can be reached.
+pub fn nearest_standard_rates(
+    i2s_clock: u32,
+    mclk: bool,
+    std: I2sStandard,
+    data_format: DataFormat,
+) -> [NearestRate; 10] {
+    STANDARD_RATES.map(|freq| nearest_rate(i2s_clock, freq, mclk, std, data_format))
+}
+
+/// Get the [`STANDARD_RATES`] that can be reached with an error of at most `max_ppm` parts per
+/// million.
+pub fn standard_rates_within(
+    i2s_clock: u32,
+    mclk: bool,
+    std: I2sStandard,
+    data_format: DataFormat,
+    max_ppm: u32,
+) -> impl Iterator<Item = NearestRate> {
+    IntoIterator::into_iter(nearest_standard_rates(i2s_clock, mclk, std, data_format))
+        .filter(move |rate| rate.ppm.unsigned_abs() <= max_ppm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const I2S_CLOCKS: [u32; 6] = [
+        8_000_000,
+        16_000_000,
+        48_000_000,
+        61_440_000,
+        86_000_000,
+        180_633_600,
+    ];
+
+    const STANDARDS: [I2sStandard; 5] = [
+        I2sStandard::Philips,
+        I2sStandard::Msb,
+        I2sStandard::Lsb,
+        I2sStandard::PcmShortSync,
+        I2sStandard::PcmLongSync,
+    ];
+
+    const DATA_FORMATS: [DataFormat; 4] = [
+        DataFormat::Data16Channel16,
+        DataFormat::Data16Channel32,
+        DataFormat::Data24Channel32,
+        DataFormat::Data32Channel32,
+    ];
+
+    /// Call `f` with every combination of clock, standard, data format and master clock.
+    fn for_each_config(mut f: impl FnMut(u32, I2sStandard, DataFormat, bool)) {
+        for &i2s_clock in I2S_CLOCKS.iter() {
+            for &std in STANDARDS.iter() {
+                for &fmt in DATA_FORMATS.iter() {
+                    for &mclk in [false, true].iter() {
+                        f(i2s_clock, std, fmt, mclk);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Exact sampling frequency from the reference manual formula.
+    fn reference_rate(
+        i2s_clock: u32,
+        division: u32,
+        mclk: bool,
+        std: I2sStandard,
+        fmt: DataFormat,
+    ) -> f64 {
+        let nb_chan = match std {
+            I2sStandard::PcmShortSync | I2sStandard::PcmLongSync => 1.0,
+            _ => 2.0,
+        };
+        let channel_length = match fmt {
+            DataFormat::Data16Channel16 => 16.0,
+            _ => 32.0,
+        };
+        let bits = if mclk { 128.0 } else { channel_length };
+        i2s_clock as f64 / (bits * nb_chan * division as f64)
+    }
+
+    #[test]
+    fn test_div_round() {
+        let fracs = [(1, 2), (2, 2), (1, 3), (2, 3), (2, 4), (3, 5), (9, 2)];
+        for (n, d) in fracs {
+            let res = div_round(n, d);
+            let check = f32::round((n as f32) / (d as f32)) as u32;
+            assert_eq!(res, check);
+        }
+    }
+
+    #[test]
+    fn test_request_prescaler() {
+        for_each_config(|i2s_clock, std, fmt, mclk| {
+            let freqs = (1_000..=200_000)
+                .step_by(251)
+                .chain(STANDARD_RATES.iter().copied());
+            for freq in freqs {
+                let (odd, div) = request_prescaler(i2s_clock, freq, mclk, std, fmt);
+                let expected = (reference_rate(i2s_clock, 1, mclk, std, fmt) / freq as f64)
+                    .round()
+                    .clamp(4.0, 511.0);
+                assert_eq!(
+                    division(odd, div),
+                    expected as u32,
+                    "{} {}",
+                    i2s_clock,
+                    freq
+                );
+                assert_eq!(
+                    sample_rate(i2s_clock, odd, div, mclk, std, fmt),
+                    reference_rate(i2s_clock, division(odd, div), mclk, std, fmt) as u32
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn test_prescaler_round_trip() {
+        for_each_config(|i2s_clock, std, fmt, mclk| {
+            for factor in 4..=511 {
+                let (odd, div) = ((factor & 1) == 1, (factor >> 1) as u8);
+                let rate = sample_rate(i2s_clock, odd, div, mclk, std, fmt);
+                let exact = reference_rate(i2s_clock, factor, mclk, std, fmt);
+                if exact.fract() == 0.0 {
+                    assert_eq!(rate as f64, exact);
+                    assert_eq!(
+                        request_prescaler(i2s_clock, rate, mclk, std, fmt),
+                        (odd, div)
+                    );
+                    assert_eq!(
+                        require_prescaler(i2s_clock, rate, mclk, std, fmt),
+                        Some((odd, div))
+                    );
+                } else if let Some((odd, div)) = require_prescaler(i2s_clock, rate, mclk, std, fmt)
+                {
+                    // another division may reach the rounded down rate exactly
+                    assert_eq!(sample_rate(i2s_clock, odd, div, mclk, std, fmt), rate);
+                    assert!(division(odd, div) > factor);
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_prescaler_clamp() {
+        for &std in STANDARDS.iter() {
+            for &fmt in DATA_FORMATS.iter() {
+                for &mclk in [false, true].iter() {
+                    let coef = coef(mclk, std, fmt);
+                    for &division in [0, 1, 2, 3, 4, 5, 510, 511, 512, 513, 1000].iter() {
+                        let i2s_clock = coef * division * 1_000;
+                        let expected = match division {
+                            0..=3 => (false, 2),
+                            4..=511 => ((division & 1) == 1, (division >> 1) as u8),
+                            _ => (true, 255),
+                        };
+                        let request = request_prescaler(i2s_clock, 1_000, mclk, std, fmt);
+                        assert_eq!(request, expected);
+                        let require = require_prescaler(i2s_clock, 1_000, mclk, std, fmt);
+                        let in_range = (4..=511).contains(&division);
+                        assert_eq!(require, Some(expected).filter(|_| in_range));
+                    }
+                    // rounding to the nearest division happens before clamping
+                    let request = request_prescaler(coef * 3_499, 1_000, mclk, std, fmt);
+                    assert_eq!(request, (false, 2));
+                    let request = request_prescaler(coef * 511_499, 1_000, mclk, std, fmt);
+                    assert_eq!(request, (true, 255));
+                    assert_eq!(
+                        request_prescaler(coef * 3, 1_000, mclk, std, fmt),
+                        (false, 2)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_derived_clocks() {
+        // 86 MHz, 48 kHz requested, master clock enabled: division = 7
+        let (odd, div) = request_prescaler(
+            86_000_000,
+            48_000,
+            true,
+            I2sStandard::Philips,
+            DataFormat::Data24Channel32,
+        );
+        assert_eq!((odd, div), (true, 3));
+        assert_eq!(mclk_frequency(86_000_000, odd, div), 12_285_714);
+        let fmt = DataFormat::Data24Channel32;
+        let sck = sck_frequency(86_000_000, odd, div, true, I2sStandard::Philips, fmt);
+        assert_eq!(sck, 3_071_428);
+        let ppm = ppm_error(
+            86_000_000,
+            odd,
+            div,
+            true,
+            I2sStandard::Philips,
+            fmt,
+            48_000,
+        );
+        assert_eq!(ppm, -186);
+    }
+
+    #[test]
+    fn test_sample_rates() {
+        let std = I2sStandard::Msb;
+        let fmt = DataFormat::Data16Channel32;
+        let rates = sample_rates(61_440_000, false, std, fmt);
+        assert_eq!(rates.len(), 508);
+        let mut previous = f64::INFINITY;
+        for (odd, div, rate) in rates {
+            assert!(div >= 2);
+            assert!(rate < previous);
+            assert_eq!(
+                rate as u32,
+                sample_rate(61_440_000, odd, div, false, std, fmt)
+            );
+            previous = rate;
+        }
+        // 61.44 MHz / (64 * 20)
+        assert!(sample_rates(61_440_000, false, std, fmt).any(|r| r == (false, 10, 48_000.0)));
+    }
+
+    #[test]
+    fn test_standard_rates() {
+        let std = I2sStandard::Philips;
+        let fmt = DataFormat::Data16Channel16;
+        let nearest = nearest_standard_rates(86_000_000, true, std, fmt);
+        let r48k = nearest.iter().find(|r| r.freq == 48_000).unwrap();
+        assert_eq!((r48k.odd, r48k.div, r48k.ppm), (true, 3, -186));
+        // 32 kHz is 5 % off with 86 MHz clock, others are -186 ppm
+        let within = [8_000, 16_000, 48_000];
+        let rates = standard_rates_within(86_000_000, true, std, fmt, 200).map(|r| r.freq);
+        assert!(rates.eq(within.iter().copied()));
+    }
+
+    #[test]
+    fn test_find_exact_i2s_clock() {
+        let std = I2sStandard::Philips;
+        let fmt = DataFormat::Data16Channel16;
+        // PLLI2S with a 1 MHz input: the VCO runs at a multiple of 1 MHz from 100 to 432 MHz, and
+        // is divided by 2 to 7
+        let plli2s = |f: u32| {
+            (2..=7).any(|r| {
+                let vco = f as u64 * r;
+                vco % 1_000_000 == 0 && (100_000_000..=432_000_000).contains(&vco)
+            })
+        };
+        assert_eq!(find_exact_i2s_clock(44_100, false, std, fmt, plli2s), None);
+        assert_eq!(find_exact_i2s_clock(48_000, true, std, fmt, plli2s), None);
+        // 48 kHz * 32 * 25 = 38.4 MHz, from a 192 MHz VCO divided by 5
+        assert_eq!(
+            find_exact_i2s_clock(48_000, false, std, fmt, plli2s),
+            Some((38_400_000, true, 12))
+        );
+        let any = |_| true;
+        assert_eq!(
+            find_exact_i2s_clock(44_100, false, std, fmt, any),
+            Some((44_100 * 32 * 4, false, 2))
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Codec compatibility checks.
+//!
+//! The master clock (MCLK) generated by the peripheral is always `256 * Fs`, or `128 * Fs` with
+//! PCM standards, and the bit clock (SCK) is `channel_length * nb_chan * Fs`. Many codecs require
+//! other ratios, or only support some standards and data formats. [`CodecRequirements`] describes
+//! what a codec accepts, and [`CodecRequirements::check`] tells exactly which requirement a
+//! driver configuration doesn't meet.
+//!
+//! ```no_run
+//! # use stm32_i2s_v12x::codec::*;
+//! # use stm32_i2s_v12x::driver::*;
+//! const CODEC: CodecRequirements = CodecRequirements {
+//!     mclk_required: true,
+//!     mclk_ratios: &[256, 384, 512],
+//!     bclk_ratios: &[32, 64],
+//!     standards: &[I2sStandard::Philips, I2sStandard::Lsb],
+//!     data_formats: &[DataFormat::Data16Channel16, DataFormat::Data24Channel32],
+//! };
+//! let config = I2sDriverConfig::new_master()
+//!     .standard(Philips)
+//!     .master_clock(true)
+//!     .request_frequency(48_000);
+//! assert_eq!(CODEC.check(&config, 86_000_000), Ok(()));
+//! ```
+use core::fmt;
+
+use crate::clock;
+use crate::driver::{DataFormat, FrequencyError, I2sDriverConfig, I2sStandard, SlaveOrMaster};
+
+/// Description of what a codec accepts.
+///
+/// An empty slice means there is no restriction.
+#[derive(Debug, Clone, Copy)]
+pub struct CodecRequirements {
+    /// `true` if the codec needs a master clock.
+    pub mclk_required: bool,
+    /// Allowed MCLK/Fs ratios.
+    pub mclk_ratios: &'static [u32],
+    /// Allowed SCK/Fs ratios, that is, allowed numbers of bit clock cycles per frame.
+    pub bclk_ratios: &'static [u32],
+    /// Supported I2S standards.
+    pub standards: &'static [I2sStandard],
+    /// Supported data formats.
+    pub data_formats: &'static [DataFormat],
+}
+
+/// The requirement not met by a configuration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum CodecMismatch {
+    /// The I2S standard isn't supported.
+    Standard(I2sStandard),
+    /// The data format isn't supported.
+    DataFormat(DataFormat),
+    /// The codec needs a master clock but the master clock is disabled.
+    MasterClockDisabled,
+    /// The MCLK/Fs ratio isn't allowed.
+    MclkRatio(u32),
+    /// The SCK/Fs ratio isn't allowed.
+    BclkRatio(u32),
+    /// The sampling frequency can't be set.
+    Frequency(FrequencyError),
+}
+
+impl fmt::Display for CodecMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecMismatch::Standard(std) => write!(f, "standard {:?} not supported", std),
+            CodecMismatch::DataFormat(fmt) => write!(f, "data format {:?} not supported", fmt),
+            CodecMismatch::MasterClockDisabled => f.write_str("master clock required"),
+            CodecMismatch::MclkRatio(ratio) => write!(f, "MCLK/Fs ratio {} not allowed", ratio),
+            CodecMismatch::BclkRatio(ratio) => write!(f, "SCK/Fs ratio {} not allowed", ratio),
+            CodecMismatch::Frequency(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+fn allowed<T: PartialEq>(allowed: &[T], value: &T) -> bool {
+    allowed.is_empty() || allowed.contains(value)
+}
+
+impl CodecRequirements {
+    /// Check a driver configuration against the codec requirements.
+    ///
+    /// For a master configuration, `i2s_freq` is used to check that the requested sampling
+    /// frequency can be set, the same way a driver does. For a slave configuration, `i2s_freq` is
+    /// ignored and master clock requirements aren't checked since clocks are generated by another
+    /// device.
+    pub fn check<MS, DIR, STD>(
+        &self,
+        config: &I2sDriverConfig<MS, DIR, STD>,
+        i2s_freq: u32,
+    ) -> Result<(), CodecMismatch> {
+        let std = config.get_standard();
+        let data_format = config.get_data_format();
+        if !allowed(self.standards, &std) {
+            return Err(CodecMismatch::Standard(std));
+        }
+        if !allowed(self.data_formats, &data_format) {
+            return Err(CodecMismatch::DataFormat(data_format));
+        }
+        if config.get_role() == SlaveOrMaster::Slave {
+            let bclk_ratio = clock::bits_per_frame(std, data_format);
+            return match allowed(self.bclk_ratios, &bclk_ratio) {
+                true => Ok(()),
+                false => Err(CodecMismatch::BclkRatio(bclk_ratio)),
+            };
+        }
+        let mclk = config.get_master_clock();
+        if self.mclk_required && !mclk {
+            return Err(CodecMismatch::MasterClockDisabled);
+        }
+        config
+            .get_prescaler(i2s_freq)
+            .map_err(CodecMismatch::Frequency)?;
+        // Ratios don't depend on the prescaler: MCLK is `coef * Fs` and SCK is
+        // `bits_per_frame * Fs`.
+        if mclk {
+            let mclk_ratio = clock::coef(true, std, data_format);
+            if !allowed(self.mclk_ratios, &mclk_ratio) {
+                return Err(CodecMismatch::MclkRatio(mclk_ratio));
+            }
+        }
+        let bclk_ratio = clock::bits_per_frame(std, data_format);
+        if !allowed(self.bclk_ratios, &bclk_ratio) {
+            return Err(CodecMismatch::BclkRatio(bclk_ratio));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::{Msb, PcmShortSync, Philips};
+
+    const CODEC: CodecRequirements = CodecRequirements {
+        mclk_required: true,
+        mclk_ratios: &[256, 384, 512],
+        bclk_ratios: &[64],
+        standards: &[I2sStandard::Philips, I2sStandard::PcmShortSync],
+        data_formats: &[],
+    };
+
+    #[test]
+    fn test_check() {
+        let config = I2sDriverConfig::new_master()
+            .standard(Philips)
+            .data_format(DataFormat::Data24Channel32)
+            .master_clock(true)
+            .request_frequency(48_000);
+        assert_eq!(CODEC.check(&config, 86_000_000), Ok(()));
+        let res = CODEC.check(&config.master_clock(false), 86_000_000);
+        assert_eq!(res, Err(CodecMismatch::MasterClockDisabled));
+        let res = CODEC.check(&config.standard(Msb), 86_000_000);
+        assert_eq!(res, Err(CodecMismatch::Standard(I2sStandard::Msb)));
+        let res = CODEC.check(&config.data_format(DataFormat::Data16Channel16), 86_000_000);
+        assert_eq!(res, Err(CodecMismatch::BclkRatio(32)));
+        // PCM master clock is 128 * Fs
+        let res = CODEC.check(&config.standard(PcmShortSync), 86_000_000);
+        assert_eq!(res, Err(CodecMismatch::MclkRatio(128)));
+        let res = CODEC.check(&config.require_frequency(48_000), 86_000_000);
+        assert!(matches!(res, Err(CodecMismatch::Frequency(_))));
+        // clocks aren't checked for a slave
+        let res = CODEC.check(&config.to_slave(), 0);
+        assert_eq!(res, Ok(()));
+    }
+
+    #[test]
+    fn test_check_low_frequency() {
+        // the default prescaler gives a sampling frequency of 0 with a low i2s clock
+        let config = I2sDriverConfig::new_master()
+            .standard(Philips)
+            .data_format(DataFormat::Data24Channel32)
+            .master_clock(true);
+        assert_eq!(CODEC.check(&config, 1_000), Ok(()));
+        let config = config.data_format(DataFormat::Data16Channel16);
+        assert_eq!(
+            CODEC.check(&config, 1_000),
+            Err(CodecMismatch::BclkRatio(32))
+        );
+        let config = config.standard(PcmShortSync);
+        assert_eq!(
+            CODEC.check(&config, 1_000),
+            Err(CodecMismatch::MclkRatio(128))
+        );
+    }
+}
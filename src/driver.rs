@@ -64,6 +64,19 @@
 //! }
 //! ```
 //!
+//! Alternatively, `poll_events` decodes the status register into typed [`Event`]s, reading received
+//! data and clearing error flags as required by the hardware:
+//! ```ignore
+//! for event in driver.poll_events() {
+//!     match event {
+//!         Event::DataReceived(data, Some(Channel::Left)) => /* left channel audio data */,
+//!         Event::DataReceived(data, _) => /* right channel audio data */,
+//!         Event::Overrun => /* some data were lost */,
+//!         _ => {}
+//!     }
+//! }
+//! ```
+//!
 //! With [`DualI2sDriver`] you control 2 peripherals, a "main" SPI peripheral and an "ext" I2SEXT
 //! peripheral. Many operations are done on the "main" or "ext" parts. The following pseudocode
 //! example explains usage of [`DualI2sDriver`] configured for 16 bit audio data with main part
@@ -85,16 +98,18 @@
 //!     }
 //! }
 //! ```
+use core::fmt;
 use core::marker::PhantomData;
 
+use crate::clock::{self, NearestRate};
 use crate::pac::spi1::RegisterBlock;
-use crate::pac::spi1::{i2spr, sr};
-use crate::{DualI2sPeripheral, I2sPeripheral, WsPin};
+use crate::pac::spi1::{i2scfgr, i2spr, sr};
+use crate::{DualI2sPeripheral, I2sClockControl, I2sPeripheral, WsPin};
 
 pub use crate::marker::{self, *};
 
 /// The channel associated with a sample
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Channel {
     /// Left channel
     Left,
@@ -137,6 +152,13 @@ where
     }
 }
 
+impl<MS, DIR, STD> Status<MS, DIR, STD> {
+    /// Raw CHSIDE flag, `true` for the right channel, whatever the standard.
+    pub(crate) fn chside_bit(&self) -> bool {
+        self.value.chside().bit()
+    }
+}
+
 impl<DIR, STD> Status<Slave, DIR, STD> {
     /// Get the FRE flag. If `true`, a frame error occurred.
     ///
@@ -197,53 +219,221 @@ impl<STD> Status<Slave, Transmit, STD> {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum SlaveOrMaster {
+/// Event reported by `poll_events`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The Tx buffer is empty, the next data can be written. The channel is the one of the data
+    /// to write.
+    ReadyToWrite(Option<Channel>),
+    /// A data has been read from the Rx buffer, with the channel it was received on.
+    DataReceived(u16, Option<Channel>),
+    /// Data was received while the previous one was not read, the incoming data was lost.
+    Overrun,
+    /// Transmission started while no data was written.
+    Underrun,
+    /// The WS line changed at an unexpected moment.
+    FrameError,
+}
+
+/// Set of events returned by `poll_events`.
+///
+/// Channels of data events are `None` when the CHSIDE flag is not meaningful, that is with PCM
+/// standards and in master transmit mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Events {
+    data: Option<Event>,
+    overrun: bool,
+    underrun: bool,
+    frame_error: bool,
+}
+
+impl Events {
+    /// Get the data event, either `ReadyToWrite` or `DataReceived`.
+    pub fn data(&self) -> Option<Event> {
+        self.data
+    }
+
+    /// `true` if an overrun occurred.
+    pub fn overrun(&self) -> bool {
+        self.overrun
+    }
+
+    /// `true` if an underrun occurred.
+    pub fn underrun(&self) -> bool {
+        self.underrun
+    }
+
+    /// `true` if a frame error occurred.
+    pub fn frame_error(&self) -> bool {
+        self.frame_error
+    }
+
+    /// `true` if any error occurred.
+    pub fn has_error(&self) -> bool {
+        self.overrun || self.underrun || self.frame_error
+    }
+
+    /// `true` if there is no event.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_none() && !self.has_error()
+    }
+
+    /// Iterate over events, data event first.
+    pub fn iter(&self) -> EventsIter {
+        self.into_iter()
+    }
+}
+
+/// Iterator over [`Events`].
+pub type EventsIter = core::iter::Flatten<core::array::IntoIter<Option<Event>, 4>>;
+
+impl IntoIterator for Events {
+    type Item = Event;
+    type IntoIter = EventsIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let events = [
+            self.data,
+            Some(Event::Overrun).filter(|_| self.overrun),
+            Some(Event::Underrun).filter(|_| self.underrun),
+            Some(Event::FrameError).filter(|_| self.frame_error),
+        ];
+        IntoIterator::into_iter(events).flatten()
+    }
+}
+
+/// Read the status register and build the event set, performing flag clearing side effects.
+fn _poll_events(
+    registers: &RegisterBlock,
+    master: bool,
+    direction: TransmitOrReceive,
+    std: I2sStandard,
+) -> Events {
+    let sr = registers.sr.read();
+    let pcm = matches!(std, I2sStandard::PcmShortSync | I2sStandard::PcmLongSync);
+    let channel = match sr.chside().bit() {
+        false => Channel::Left,
+        true => Channel::Right,
+    };
+    let mut events = Events::default();
+    match direction {
+        TransmitOrReceive::Transmit => {
+            if sr.txe().bit() {
+                // CHSIDE is not reliable in master transmit
+                let channel = Some(channel).filter(|_| !pcm && !master);
+                events.data = Some(Event::ReadyToWrite(channel));
+            }
+            // cleared by reading status register
+            events.underrun = !master && sr.udr().bit();
+        }
+        TransmitOrReceive::Receive => {
+            if sr.rxne().bit() || sr.ovr().bit() {
+                let word = registers.dr.read().dr().bits();
+                if sr.rxne().bit() {
+                    let channel = Some(channel).filter(|_| !pcm);
+                    events.data = Some(Event::DataReceived(word, channel));
+                }
+            }
+            if sr.ovr().bit() {
+                // reading data register then status register clear the overrun flag
+                registers.sr.read();
+                events.overrun = true;
+            }
+        }
+    }
+    // cleared by reading status register
+    events.frame_error = !master && sr.fre().bit();
+    events
+}
+
+/// Role of an I2S device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlaveOrMaster {
+    /// The device receives clocks from another device.
     Slave,
+    /// The device generates clocks.
     Master,
 }
 
 /// Various ways to specify sampling frequency.
-#[derive(Debug, Clone, Copy)]
-enum Frequency {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Frequency {
+    /// Prescaler set directly with an odd factor and a divider.
     Prescaler(bool, u8),
+    /// Nearest reachable sampling frequency.
     Request(u32),
+    /// Exact sampling frequency, or panic.
     Require(u32),
+    /// Nearest reachable sampling frequency, or panic if the error in parts per million exceeds the
+    /// second value.
+    RequestWithin(u32, u32),
 }
 
-/// Those thing are not part of the public API but appear on public trait or trait bound.
-pub(crate) mod private {
-    #[derive(Debug, Clone, Copy)]
-    pub enum TransmitOrReceive {
-        Transmit,
-        Receive,
-    }
-
-    #[derive(Debug, Clone, Copy, Eq, PartialEq)]
-    /// I2s standard selection.
-    pub enum I2sStandard {
-        /// Philips I2S
-        Philips,
-        /// MSB Justified
-        Msb,
-        /// LSB Justified
-        Lsb,
-        /// PCM with short frame synchronisation.
-        PcmShortSync,
-        /// PCM with long frame synchronisation.
-        PcmLongSync,
+/// Error returned when a sampling frequency can't be reached within the required tolerance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrequencyError {
+    /// The best achievable sampling frequency.
+    pub best: NearestRate,
+    /// The maximum allowed error in parts per million, 0 when an exact frequency is required.
+    pub max_ppm: u32,
+}
+
+impl fmt::Display for FrequencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.max_ppm == 0 {
+            write!(f, "Cannot reach exactly the required frequency")?;
+        } else {
+            write!(
+                f,
+                "Cannot reach the requested frequency within {} ppm",
+                self.max_ppm
+            )?;
+        }
+        write!(
+            f,
+            " ({} Hz), best achievable is {} Hz ({} ppm)",
+            self.best.freq, self.best.exact_rate, self.best.ppm
+        )
     }
+}
 
+/// Communication direction of an I2S device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransmitOrReceive {
+    /// The device transmits data.
+    Transmit,
+    /// The device receives data.
+    Receive,
+}
+
+/// I2s standard selection.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum I2sStandard {
+    /// Philips I2S
+    Philips,
+    /// MSB Justified
+    Msb,
+    /// LSB Justified
+    Lsb,
+    /// PCM with short frame synchronisation.
+    PcmShortSync,
+    /// PCM with long frame synchronisation.
+    PcmLongSync,
+}
+
+/// Those thing are not part of the public API but appear on public trait or trait bound.
+pub(crate) mod private {
     /// This trait allow to have generic code for I2sCore.
     use super::RegisterBlock;
     pub trait I2sCoreRegisters {
         fn registers(&self) -> &RegisterBlock;
     }
 }
-pub(crate) use private::{I2sCoreRegisters, I2sStandard, TransmitOrReceive};
+pub(crate) use private::I2sCoreRegisters;
 
 /// Steady state clock polarity
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ClockPolarity {
     /// Clock low when idle
     IdleLow,
@@ -252,9 +442,10 @@ pub enum ClockPolarity {
 }
 
 /// Data length to be transferred and channel length
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum DataFormat {
     /// 16 bit data length on 16 bit wide channel
+    #[default]
     Data16Channel16,
     /// 16 bit data length on 32 bit wide channel
     Data16Channel32,
@@ -264,12 +455,6 @@ pub enum DataFormat {
     Data32Channel32,
 }
 
-impl Default for DataFormat {
-    fn default() -> Self {
-        DataFormat::Data16Channel16
-    }
-}
-
 #[derive(Debug, Clone, Copy)]
 /// I2S driver configuration
 ///
@@ -331,108 +516,221 @@ impl I2sDriverConfig<Master, Transmit, Philips> {
     }
 }
 
-/// rounding division
-fn div_round(n: u32, d: u32) -> u32 {
-    (n + (d >> 1)) / d
-}
-
 // unsafe, div should be greater or equal to 2
 fn _set_prescaler(w: &mut i2spr::W, odd: bool, div: u8) {
     w.odd().bit(odd);
     unsafe { w.i2sdiv().bits(div) };
 }
 
-// Note, calculation details:
-// Fs = i2s_clock / [128 * nb_chan * ((2 * div) + odd)] when master clock is enabled
-// Fs = i2s_clock / [(channel_length * nb_chan) * ((2 * div) + odd)]` when master clock is disabled
-// where:
-//  - nb_chan is 2 with Philips, Msb and LSB standards and 1 with Pcm standards.
-//  - channel_length is 16 or 32
-//
-// can be rewritten as
-// Fs = i2s_clock / (coef * division)
-// where:
-//  - coef is a constant that depends on i2s standard, channel length and master clock
-//  - and where division = (2 * div) + odd
-//
-// Equation can be rewritten as
-// division = i2s_clock/ (coef * Fs)
-//
-// note: division = (2 * div) + odd = (div << 1) + odd
-// in other word, from bits point of view, division[8:1] = div[7:0] and division[0] = odd
-fn _set_request_frequency(
-    w: &mut i2spr::W,
-    i2s_clock: u32,
-    request_freq: u32,
+// Get the prescaler configuration for the given frequency specification. `i2s_clock` is only
+// called when needed, since it's allowed to panic when the clock source is unavailable.
+// See the clock module for calculation details.
+fn _prescaler(
+    frequency: Frequency,
+    i2s_clock: impl FnOnce() -> u32,
     mclk: bool,
     std: I2sStandard,
     data_format: DataFormat,
-) {
-    let coef = _coef(mclk, std, data_format);
-    let division = div_round(i2s_clock, coef * request_freq);
-    let (odd, div) = if division < 4 {
-        (false, 2)
-    } else if division > 511 {
-        (true, 255)
-    } else {
-        ((division & 1) == 1, (division >> 1) as u8)
-    };
-    _set_prescaler(w, odd, div);
+) -> Result<(bool, u8), FrequencyError> {
+    match frequency {
+        Frequency::Prescaler(odd, div) => Ok((odd, div)),
+        Frequency::Request(freq) => Ok(clock::request_prescaler(
+            i2s_clock(),
+            freq,
+            mclk,
+            std,
+            data_format,
+        )),
+        Frequency::Require(freq) => {
+            let i2s_clock = i2s_clock();
+            clock::require_prescaler(i2s_clock, freq, mclk, std, data_format).ok_or_else(|| {
+                FrequencyError {
+                    best: clock::nearest_rate(i2s_clock, freq, mclk, std, data_format),
+                    max_ppm: 0,
+                }
+            })
+        }
+        Frequency::RequestWithin(freq, max_ppm) => {
+            let best = clock::nearest_rate(i2s_clock(), freq, mclk, std, data_format);
+            if best.ppm.unsigned_abs() <= max_ppm {
+                Ok((best.odd, best.div))
+            } else {
+                Err(FrequencyError { best, max_ppm })
+            }
+        }
+    }
 }
 
-// see _set_request_frequency for explanation
-#[allow(clippy::manual_range_contains)]
-fn _set_require_frequency(
-    w: &mut i2spr::W,
-    i2s_clock: u32,
-    request_freq: u32,
+// Find an i2s clock source frequency allowing to reach `frequency` exactly, when this isn't
+// possible with the `current` clock source.
+fn _find_exact_i2s_clock<C: I2sClockControl>(
+    clock_control: &C,
+    current: u32,
+    frequency: Frequency,
     mclk: bool,
     std: I2sStandard,
     data_format: DataFormat,
-) {
-    let coef = _coef(mclk, std, data_format);
-    let division = i2s_clock / (coef * request_freq);
-    let rem = i2s_clock % (coef * request_freq);
-    if rem == 0 && division >= 4 && division <= 511 {
-        let odd = (division & 1) == 1;
-        let div = (division >> 1) as u8;
-        _set_prescaler(w, odd, div);
-    } else {
-        panic!("Cannot reach exactly the required frequency")
+) -> Option<u32> {
+    let freq = match frequency {
+        Frequency::Prescaler(_, _) => return None,
+        Frequency::Request(freq) | Frequency::Require(freq) => freq,
+        Frequency::RequestWithin(freq, _) => freq,
     };
+    if _prescaler(Frequency::Require(freq), || current, mclk, std, data_format).is_ok() {
+        return None;
+    }
+    clock::find_exact_i2s_clock(freq, mclk, std, data_format, |f| {
+        clock_control.can_set_i2s_freq(f)
+    })
+    .map(|(i2s_clock, _, _)| i2s_clock)
+}
+
+// sample rate calculation from device information and clock source, see the clock module for
+// calculation details
+fn _sample_rate(registers: &RegisterBlock, i2s_freq: u32) -> u32 {
+    let config = decode_registers(
+        registers.i2scfgr.read().bits(),
+        registers.i2spr.read().bits(),
+    );
+    // with reserved data length, channel length is 32 bits
+    let data_format = config.data_format.unwrap_or(DataFormat::Data32Channel32);
+    clock::sample_rate(
+        i2s_freq,
+        config.odd,
+        config.div,
+        config.master_clock,
+        config.standard,
+        data_format,
+    )
 }
 
-// see _set_request_frequency for explanation
-fn _coef(mclk: bool, std: I2sStandard, data_format: DataFormat) -> u32 {
-    use I2sStandard::*;
-    let nb_chan = match std {
-        Philips | Msb | Lsb => 2,
-        PcmShortSync | PcmLongSync => 1,
+/// Human-readable content of the `I2SCFGR` and `I2SPR` registers.
+///
+/// This is obtained from raw register values with [`decode_registers`], or from a driver with
+/// `decoded_config()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodedConfig {
+    /// `true` when the peripheral is in I2S mode, `false` when it's in SPI mode.
+    pub i2s_mode: bool,
+    /// `true` when the peripheral is enabled.
+    pub enabled: bool,
+    /// Master or slave.
+    pub role: SlaveOrMaster,
+    /// Transmit or receive.
+    pub direction: TransmitOrReceive,
+    /// I2S standard.
+    pub standard: I2sStandard,
+    /// Data and channel length, `None` if the `DATLEN` field has the reserved value.
+    pub data_format: Option<DataFormat>,
+    /// Steady state clock polarity.
+    pub clock_polarity: ClockPolarity,
+    /// `true` when the master clock output is enabled.
+    pub master_clock: bool,
+    /// Odd factor of the prescaler.
+    pub odd: bool,
+    /// Linear divider of the prescaler.
+    pub div: u8,
+}
+
+/// Decode raw values of the `I2SCFGR` and `I2SPR` registers.
+pub fn decode_registers(i2scfgr: u32, i2spr: u32) -> DecodedConfig {
+    let cfg = i2scfgr::R::new(i2scfgr);
+    let pr = i2spr::R::new(i2spr);
+    let (role, direction) = if cfg.i2scfg().is_slave_tx() {
+        (SlaveOrMaster::Slave, TransmitOrReceive::Transmit)
+    } else if cfg.i2scfg().is_slave_rx() {
+        (SlaveOrMaster::Slave, TransmitOrReceive::Receive)
+    } else if cfg.i2scfg().is_master_tx() {
+        (SlaveOrMaster::Master, TransmitOrReceive::Transmit)
+    } else {
+        (SlaveOrMaster::Master, TransmitOrReceive::Receive)
     };
-    if mclk {
-        return 128 * nb_chan;
-    }
-    if let DataFormat::Data16Channel16 = data_format {
-        16 * nb_chan
+    let standard = if cfg.i2sstd().is_philips() {
+        I2sStandard::Philips
+    } else if cfg.i2sstd().is_msb() {
+        I2sStandard::Msb
+    } else if cfg.i2sstd().is_lsb() {
+        I2sStandard::Lsb
+    } else if cfg.pcmsync().is_long() {
+        I2sStandard::PcmLongSync
+    } else {
+        I2sStandard::PcmShortSync
+    };
+    // CHLEN is meaningful only with 16 bit data length, otherwise channel length is 32 bits
+    let data_format = if cfg.datlen().is_sixteen_bit() {
+        match cfg.chlen().bit() {
+            false => Some(DataFormat::Data16Channel16),
+            true => Some(DataFormat::Data16Channel32),
+        }
+    } else if cfg.datlen().is_twenty_four_bit() {
+        Some(DataFormat::Data24Channel32)
+    } else if cfg.datlen().is_thirty_two_bit() {
+        Some(DataFormat::Data32Channel32)
     } else {
-        32 * nb_chan
+        None
+    };
+    let clock_polarity = match cfg.ckpol().bit() {
+        false => ClockPolarity::IdleLow,
+        true => ClockPolarity::IdleHigh,
+    };
+    DecodedConfig {
+        i2s_mode: cfg.i2smod().bit(),
+        enabled: cfg.i2se().bit(),
+        role,
+        direction,
+        standard,
+        data_format,
+        clock_polarity,
+        master_clock: pr.mckoe().bit(),
+        odd: pr.odd().bit(),
+        div: pr.i2sdiv().bits(),
     }
 }
 
-// sample rate calculation from device information and clock source, see _set_request_frequency for
-// explanation
-fn _sample_rate(registers: &RegisterBlock, i2s_freq: u32) -> u32 {
-    let i2scfgr = registers.i2scfgr.read();
-    let i2spr = registers.i2spr.read();
-    let nb_chan = if i2scfgr.i2sstd().is_pcm() { 1 } else { 2 };
-    let channel_length = if i2scfgr.chlen().bit() { 32 } else { 16 };
-    let mckoe = i2spr.mckoe().bit();
-    let odd = i2spr.odd().bit();
-    let div = i2spr.i2sdiv().bits();
-    if mckoe {
-        i2s_freq / (128 * nb_chan * ((2 * div as u32) + odd as u32))
-    } else {
-        i2s_freq / ((channel_length * nb_chan) * ((2 * div as u32) + odd as u32))
+impl fmt::Display for DecodedConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.i2s_mode {
+            f.write_str("SPI mode (not I2S), ")?;
+        }
+        let role = match self.role {
+            SlaveOrMaster::Slave => "slave",
+            SlaveOrMaster::Master => "master",
+        };
+        let direction = match self.direction {
+            TransmitOrReceive::Transmit => "transmit",
+            TransmitOrReceive::Receive => "receive",
+        };
+        let standard = match self.standard {
+            I2sStandard::Philips => "Philips",
+            I2sStandard::Msb => "MSB justified",
+            I2sStandard::Lsb => "LSB justified",
+            I2sStandard::PcmShortSync => "PCM short sync",
+            I2sStandard::PcmLongSync => "PCM long sync",
+        };
+        let data_format = match self.data_format {
+            Some(DataFormat::Data16Channel16) => "16 bit data on 16 bit channel",
+            Some(DataFormat::Data16Channel32) => "16 bit data on 32 bit channel",
+            Some(DataFormat::Data24Channel32) => "24 bit data on 32 bit channel",
+            Some(DataFormat::Data32Channel32) => "32 bit data on 32 bit channel",
+            None => "reserved data length",
+        };
+        let clock_polarity = match self.clock_polarity {
+            ClockPolarity::IdleLow => "idle low",
+            ClockPolarity::IdleHigh => "idle high",
+        };
+        write!(
+            f,
+            "{} {}, {}, {}, clock {}, master clock {}, prescaler odd={} div={}, {}",
+            role,
+            direction,
+            standard,
+            data_format,
+            clock_polarity,
+            if self.master_clock { "on" } else { "off" },
+            self.odd as u8,
+            self.div,
+            if self.enabled { "enabled" } else { "disabled" },
+        )
     }
 }
 
@@ -441,8 +739,35 @@ impl<MS, DIR, STD> I2sDriverConfig<MS, DIR, STD> {
     ///
     /// # Panics
     ///
-    /// This method panics if an exact frequency is required and that frequency cannot be set.
+    /// This method panics if an exact frequency is required, or a frequency is requested within a
+    /// tolerance, and that frequency cannot be set.
     pub fn i2s_driver<I: I2sPeripheral>(self, i2s_peripheral: I) -> I2sDriver<I, MS, DIR, STD> {
+        match self.try_i2s_driver(i2s_peripheral) {
+            Ok(driver) => driver,
+            Err((_, error)) => panic!("{}", error),
+        }
+    }
+
+    /// Instantiate the driver by wrapping the given [`I2sPeripheral`], or give it back with an
+    /// error when the sampling frequency can't be set.
+    ///
+    /// The peripheral is left untouched on error.
+    #[allow(clippy::type_complexity)]
+    pub fn try_i2s_driver<I: I2sPeripheral>(
+        self,
+        i2s_peripheral: I,
+    ) -> Result<I2sDriver<I, MS, DIR, STD>, (I, FrequencyError)> {
+        let prescaler = _prescaler(
+            self.frequency,
+            || i2s_peripheral.i2s_freq(),
+            self.master_clock,
+            self.standard,
+            self.data_format,
+        );
+        let (odd, div) = match prescaler {
+            Ok(prescaler) => prescaler,
+            Err(error) => return Err((i2s_peripheral, error)),
+        };
         let driver = I2sDriver::<I, MS, DIR, STD> {
             i2s_peripheral,
             _ms: PhantomData,
@@ -466,6 +791,10 @@ impl<MS, DIR, STD> I2sDriverConfig<MS, DIR, STD> {
                 I2sStandard::PcmShortSync => w.i2sstd().pcm().pcmsync().short(),
                 I2sStandard::PcmLongSync => w.i2sstd().pcm().pcmsync().long(),
             };
+            match self.clock_polarity {
+                ClockPolarity::IdleLow => w.ckpol().idle_low(),
+                ClockPolarity::IdleHigh => w.ckpol().idle_high(),
+            };
             match self.data_format {
                 DataFormat::Data16Channel16 => w.datlen().sixteen_bit().chlen().sixteen_bit(),
                 DataFormat::Data16Channel32 => w.datlen().sixteen_bit().chlen().thirty_two_bit(),
@@ -478,28 +807,58 @@ impl<MS, DIR, STD> I2sDriverConfig<MS, DIR, STD> {
         });
         driver.registers().i2spr.write(|w| {
             w.mckoe().bit(self.master_clock);
-            match self.frequency {
-                Frequency::Prescaler(odd, div) => _set_prescaler(w, odd, div),
-                Frequency::Request(freq) => _set_request_frequency(
-                    w,
-                    driver.i2s_peripheral.i2s_freq(),
-                    freq,
-                    self.master_clock,
-                    self.standard,
-                    self.data_format,
-                ),
-                Frequency::Require(freq) => _set_require_frequency(
-                    w,
-                    driver.i2s_peripheral.i2s_freq(),
-                    freq,
-                    self.master_clock,
-                    self.standard,
-                    self.data_format,
-                ),
-            }
+            _set_prescaler(w, odd, div);
             w
         });
-        driver
+        Ok(driver)
+    }
+}
+
+impl<DIR, STD> I2sDriverConfig<Master, DIR, STD> {
+    /// Instantiate the driver like [`i2s_driver`](Self::i2s_driver), after reprogramming the i2s
+    /// clock source when this allows to reach the requested or required frequency exactly.
+    ///
+    /// The clock source is left untouched if the frequency can already be reached exactly, or
+    /// can't be reached exactly at all.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if an exact frequency is required, or a frequency is requested within a
+    /// tolerance, and that frequency cannot be set.
+    pub fn i2s_driver_with_clock_control<I>(
+        self,
+        i2s_peripheral: I,
+    ) -> I2sDriver<I, Master, DIR, STD>
+    where
+        I: I2sPeripheral + I2sClockControl,
+    {
+        match self.try_i2s_driver_with_clock_control(i2s_peripheral) {
+            Ok(driver) => driver,
+            Err((_, error)) => panic!("{}", error),
+        }
+    }
+
+    /// Fallible version of [`i2s_driver_with_clock_control`](Self::i2s_driver_with_clock_control).
+    #[allow(clippy::type_complexity)]
+    pub fn try_i2s_driver_with_clock_control<I>(
+        self,
+        mut i2s_peripheral: I,
+    ) -> Result<I2sDriver<I, Master, DIR, STD>, (I, FrequencyError)>
+    where
+        I: I2sPeripheral + I2sClockControl,
+    {
+        let i2s_clock = _find_exact_i2s_clock(
+            &i2s_peripheral,
+            i2s_peripheral.i2s_freq(),
+            self.frequency,
+            self.master_clock,
+            self.standard,
+            self.data_format,
+        );
+        if let Some(i2s_clock) = i2s_clock {
+            i2s_peripheral.set_i2s_freq(i2s_clock);
+        }
+        self.try_i2s_driver(i2s_peripheral)
     }
 }
 
@@ -597,6 +956,57 @@ impl<MS, DIR, STD> I2sDriverConfig<MS, DIR, STD> {
         self
     }
 
+    /// Get the role, master or slave.
+    pub fn get_role(&self) -> SlaveOrMaster {
+        self.slave_or_master
+    }
+
+    /// Get the communication direction.
+    pub fn get_direction(&self) -> TransmitOrReceive {
+        self.transmit_or_receive
+    }
+
+    /// Get the selected I2s standard.
+    pub fn get_standard(&self) -> I2sStandard {
+        self.standard
+    }
+
+    /// Get the selected data format.
+    pub fn get_data_format(&self) -> DataFormat {
+        self.data_format
+    }
+
+    /// Get the selected steady state clock polarity.
+    pub fn get_clock_polarity(&self) -> ClockPolarity {
+        self.clock_polarity
+    }
+
+    /// Return `true` if the master clock is enabled. This is always `false` for a slave.
+    pub fn get_master_clock(&self) -> bool {
+        self.master_clock
+    }
+
+    /// Get the way the sampling frequency is specified.
+    ///
+    /// This is meaningless for a slave.
+    pub fn get_frequency(&self) -> Frequency {
+        self.frequency
+    }
+
+    /// Get the prescaler configuration, `(odd, div)`, a driver created from this configuration
+    /// will use with the given i2s clock source frequency.
+    ///
+    /// This is meaningless for a slave.
+    pub fn get_prescaler(&self, i2s_freq: u32) -> Result<(bool, u8), FrequencyError> {
+        _prescaler(
+            self.frequency,
+            || i2s_freq,
+            self.master_clock,
+            self.standard,
+            self.data_format,
+        )
+    }
+
     /// Convert to a slave configuration.
     ///
     /// This deletes Master Only Settings.
@@ -701,6 +1111,16 @@ impl<DIR, STD> I2sDriverConfig<Master, DIR, STD> {
         self.frequency = Frequency::Require(freq);
         self
     }
+
+    /// Request an audio sampling frequency, with an error of at most `max_ppm` parts per million.
+    ///
+    /// The nearest reachable frequency is selected. If the error exceeds `max_ppm`, instantiating
+    /// the driver will panic, and the fallible instantiation will report the best achievable
+    /// frequency.
+    pub fn request_frequency_within(mut self, freq: u32, max_ppm: u32) -> Self {
+        self.frequency = Frequency::RequestWithin(freq, max_ppm);
+        self
+    }
 }
 
 /// Driver of a SPI peripheral in I2S mode.
@@ -818,6 +1238,47 @@ where
             _std: PhantomData,
         }
     }
+
+    /// Get the active configuration, decoded from the configuration registers.
+    pub fn decoded_config(&self) -> DecodedConfig {
+        let registers = self.registers();
+        decode_registers(
+            registers.i2scfgr.read().bits(),
+            registers.i2spr.read().bits(),
+        )
+    }
+}
+
+/// Event polling, Master Mode
+impl<I, DIR, STD> I2sDriver<I, Master, DIR, STD>
+where
+    I: I2sPeripheral,
+    DIR: Direction,
+    STD: marker::I2sStandard,
+{
+    /// Poll the status register and return pending events.
+    ///
+    /// Received data is read and flags are cleared as needed, so each event is reported once.
+    /// `ReadyToWrite` is cleared by writing the data register.
+    pub fn poll_events(&mut self) -> Events {
+        _poll_events(self.registers(), true, DIR::VALUE, STD::VALUE)
+    }
+}
+
+/// Event polling, Slave Mode
+impl<I, DIR, STD> I2sDriver<I, Slave, DIR, STD>
+where
+    I: I2sPeripheral,
+    DIR: Direction,
+    STD: marker::I2sStandard,
+{
+    /// Poll the status register and return pending events.
+    ///
+    /// Received data is read and flags are cleared as needed, so each event is reported once.
+    /// `ReadyToWrite` is cleared by writing the data register.
+    pub fn poll_events(&mut self) -> Events {
+        _poll_events(self.registers(), false, DIR::VALUE, STD::VALUE)
+    }
 }
 
 /// Master-only methods
@@ -849,6 +1310,117 @@ where
     }
 }
 
+/// Clock-only mode
+///
+/// Many codecs need the master clock running before they accept commands on their control
+/// interface. In clock-only mode, the peripheral generates its clocks while staying idle: Tx/Rx
+/// interrupts, error interrupt and DMA requests are disabled, and no data needs to be written or
+/// read.
+///
+/// The peripheral itself can't generate MCLK without SCK and WS. To output only MCLK, SCK and WS
+/// pins should be left unconnected to the peripheral (e.g. configured as plain GPIO) until normal
+/// operation, this is up to the HAL. Also, MCLK is generated only if the master clock is enabled
+/// in the configuration.
+impl<I, DIR, STD> I2sDriver<I, Master, DIR, STD>
+where
+    I: I2sPeripheral,
+{
+    /// Enable the peripheral to generate clocks without transferring data.
+    ///
+    /// In transmit mode, the content of the data line is undefined until data is written. In
+    /// receive mode, received data is ignored and the overrun flag is set.
+    pub fn enable_clocks_only(&mut self) {
+        let registers = self.registers();
+        registers.cr2.modify(|_, w| {
+            w.txeie()
+                .clear_bit()
+                .rxneie()
+                .clear_bit()
+                .errie()
+                .clear_bit()
+                .txdmaen()
+                .clear_bit()
+                .rxdmaen()
+                .clear_bit()
+        });
+        registers.i2scfgr.modify(|_, w| w.i2se().enabled());
+    }
+
+    /// Leave clock-only mode without stopping clocks.
+    ///
+    /// Stale received data and overrun flag are cleared, so interrupts, DMA or data transfer can be
+    /// started just after this call. Channel synchronization is still up to the caller.
+    ///
+    /// Interrupts and DMA requests disabled by [`enable_clocks_only`](Self::enable_clocks_only)
+    /// are not restored, the caller has to enable again the ones it needs, e.g. with
+    /// [`set_rx_interrupt`](I2sDriver::set_rx_interrupt) or
+    /// [`set_rx_dma`](I2sDriver::set_rx_dma).
+    pub fn start_data_transfer(&mut self) {
+        let registers = self.registers();
+        // reading data then status register clear the overrun flag
+        registers.dr.read();
+        registers.sr.read();
+    }
+}
+
+/// Master methods reprogramming the i2s clock source
+impl<I, DIR, STD> I2sDriver<I, Master, DIR, STD>
+where
+    I: I2sPeripheral + I2sClockControl,
+{
+    // change the sampling frequency, see request_frequency
+    fn _change_frequency(&mut self, frequency: Frequency) -> Result<(), FrequencyError> {
+        let config = self.decoded_config();
+        // with reserved data length, channel length is 32 bits
+        let data_format = config.data_format.unwrap_or(DataFormat::Data32Channel32);
+        let (mclk, std) = (config.master_clock, config.standard);
+        let current = self.i2s_peripheral.i2s_freq();
+        let new_clock = _find_exact_i2s_clock(
+            &self.i2s_peripheral,
+            current,
+            frequency,
+            mclk,
+            std,
+            data_format,
+        );
+        let i2s_clock = new_clock.unwrap_or(current);
+        let (odd, div) = _prescaler(frequency, || i2s_clock, mclk, std, data_format)?;
+        self.disable();
+        if let Some(i2s_clock) = new_clock {
+            self.i2s_peripheral.set_i2s_freq(i2s_clock);
+        }
+        self.registers().i2spr.modify(|_, w| {
+            _set_prescaler(w, odd, div);
+            w
+        });
+        self.reset_clocks();
+        if config.enabled {
+            self.enable();
+        }
+        Ok(())
+    }
+
+    /// Change the sampling frequency, reprogramming the i2s clock source when this allows to reach
+    /// `freq` exactly. Otherwise, the nearest frequency reachable with the current clock source is
+    /// set.
+    ///
+    /// The peripheral is disabled during the change and clocks are reset, so the current frame is
+    /// interrupted. The peripheral is enabled again afterward if it was enabled.
+    pub fn request_frequency(&mut self, freq: u32) {
+        // a request never fails
+        self._change_frequency(Frequency::Request(freq)).ok();
+    }
+
+    /// Change the sampling frequency to exactly `freq`, reprogramming the i2s clock source if
+    /// needed.
+    ///
+    /// On error, nothing is changed. See [`request_frequency`](Self::request_frequency) about
+    /// peripheral state during the change.
+    pub fn require_frequency(&mut self, freq: u32) -> Result<(), FrequencyError> {
+        self._change_frequency(Frequency::Require(freq))
+    }
+}
+
 /// Transmit-only methods
 impl<I, MS, STD> I2sDriver<I, MS, Transmit, STD>
 where
@@ -923,9 +1495,9 @@ where
 /// Dual I2S driver configuration. This can be used as a dual I2S driver builder.
 ///
 ///  - `MS`: `Master` or `Slave`. It applies to the "main" part only since the extension is always
-///  slave
+///    slave
 ///  - `MAIN_DIR` and `EXT_DIR` : Communication direction of the main and extension parts; can be
-///  `Transmit` or `Receive`.
+///    `Transmit` or `Receive`.
 ///  - `STD`: I2S standard, eg `Philips`
 ///
 /// **Note:** because of its typestate, methods of this type don't modify a config object. They
@@ -990,15 +1562,42 @@ impl DualI2sDriverConfig<Master, Transmit, Transmit, Philips> {
 //#[cfg(FALSE)]
 #[allow(non_camel_case_types)]
 impl<MS, MAIN_DIR, EXT_DIR, STD> DualI2sDriverConfig<MS, MAIN_DIR, EXT_DIR, STD> {
-    /// Instantiate the driver by wrapping the given [`I2sPeripheral`].
+    /// Instantiate the driver by wrapping the given [`DualI2sPeripheral`].
     ///
     /// # Panics
     ///
-    /// This method panics if an exact frequency is required and that frequency cannot be set.
+    /// This method panics if an exact frequency is required, or a frequency is requested within a
+    /// tolerance, and that frequency cannot be set.
     pub fn dual_i2s_driver<I: DualI2sPeripheral>(
         self,
         dual_i2s_peripheral: I,
     ) -> DualI2sDriver<I, MS, MAIN_DIR, EXT_DIR, STD> {
+        match self.try_dual_i2s_driver(dual_i2s_peripheral) {
+            Ok(driver) => driver,
+            Err((_, error)) => panic!("{}", error),
+        }
+    }
+
+    /// Instantiate the driver by wrapping the given [`DualI2sPeripheral`], or give it back with an
+    /// error when the sampling frequency can't be set.
+    ///
+    /// The peripheral is left untouched on error.
+    #[allow(clippy::type_complexity)]
+    pub fn try_dual_i2s_driver<I: DualI2sPeripheral>(
+        self,
+        dual_i2s_peripheral: I,
+    ) -> Result<DualI2sDriver<I, MS, MAIN_DIR, EXT_DIR, STD>, (I, FrequencyError)> {
+        let prescaler = _prescaler(
+            self.frequency,
+            || dual_i2s_peripheral.i2s_freq(),
+            self.master_clock,
+            self.standard,
+            self.data_format,
+        );
+        let (odd, div) = match prescaler {
+            Ok(prescaler) => prescaler,
+            Err(error) => return Err((dual_i2s_peripheral, error)),
+        };
         let driver = DualI2sDriver::<I, MS, MAIN_DIR, EXT_DIR, STD> {
             dual_i2s_peripheral,
             main: I2sCore::new(),
@@ -1022,6 +1621,10 @@ impl<MS, MAIN_DIR, EXT_DIR, STD> DualI2sDriverConfig<MS, MAIN_DIR, EXT_DIR, STD>
                 I2sStandard::PcmShortSync => w.i2sstd().pcm().pcmsync().short(),
                 I2sStandard::PcmLongSync => w.i2sstd().pcm().pcmsync().long(),
             };
+            match self.clock_polarity {
+                ClockPolarity::IdleLow => w.ckpol().idle_low(),
+                ClockPolarity::IdleHigh => w.ckpol().idle_high(),
+            };
             match self.data_format {
                 DataFormat::Data16Channel16 => w.datlen().sixteen_bit().chlen().sixteen_bit(),
                 DataFormat::Data16Channel32 => w.datlen().sixteen_bit().chlen().thirty_two_bit(),
@@ -1034,25 +1637,7 @@ impl<MS, MAIN_DIR, EXT_DIR, STD> DualI2sDriverConfig<MS, MAIN_DIR, EXT_DIR, STD>
         });
         driver.main.registers().i2spr.write(|w| {
             w.mckoe().bit(self.master_clock);
-            match self.frequency {
-                Frequency::Prescaler(odd, div) => _set_prescaler(w, odd, div),
-                Frequency::Request(freq) => _set_request_frequency(
-                    w,
-                    driver.dual_i2s_peripheral.i2s_freq(),
-                    freq,
-                    self.master_clock,
-                    self.standard,
-                    self.data_format,
-                ),
-                Frequency::Require(freq) => _set_require_frequency(
-                    w,
-                    driver.dual_i2s_peripheral.i2s_freq(),
-                    freq,
-                    self.master_clock,
-                    self.standard,
-                    self.data_format,
-                ),
-            }
+            _set_prescaler(w, odd, div);
             w
         });
         // ext peripheral setup
@@ -1071,6 +1656,10 @@ impl<MS, MAIN_DIR, EXT_DIR, STD> DualI2sDriverConfig<MS, MAIN_DIR, EXT_DIR, STD>
                 I2sStandard::PcmShortSync => w.i2sstd().pcm().pcmsync().short(),
                 I2sStandard::PcmLongSync => w.i2sstd().pcm().pcmsync().long(),
             };
+            match self.clock_polarity {
+                ClockPolarity::IdleLow => w.ckpol().idle_low(),
+                ClockPolarity::IdleHigh => w.ckpol().idle_high(),
+            };
             match self.data_format {
                 DataFormat::Data16Channel16 => w.datlen().sixteen_bit().chlen().sixteen_bit(),
                 DataFormat::Data16Channel32 => w.datlen().sixteen_bit().chlen().thirty_two_bit(),
@@ -1083,28 +1672,10 @@ impl<MS, MAIN_DIR, EXT_DIR, STD> DualI2sDriverConfig<MS, MAIN_DIR, EXT_DIR, STD>
         });
         driver.ext.registers().i2spr.write(|w| {
             w.mckoe().bit(self.master_clock);
-            match self.frequency {
-                Frequency::Prescaler(odd, div) => _set_prescaler(w, odd, div),
-                Frequency::Request(freq) => _set_request_frequency(
-                    w,
-                    driver.dual_i2s_peripheral.i2s_freq(),
-                    freq,
-                    self.master_clock,
-                    self.standard,
-                    self.data_format,
-                ),
-                Frequency::Require(freq) => _set_require_frequency(
-                    w,
-                    driver.dual_i2s_peripheral.i2s_freq(),
-                    freq,
-                    self.master_clock,
-                    self.standard,
-                    self.data_format,
-                ),
-            }
+            _set_prescaler(w, odd, div);
             w
         });
-        driver
+        Ok(driver)
     }
 }
 
@@ -1184,6 +1755,62 @@ impl<MS, MAIN_DIR, EXT_DIR, STD> DualI2sDriverConfig<MS, MAIN_DIR, EXT_DIR, STD>
         self
     }
 
+    /// Get the role of the main part, master or slave. The extension part is always slave.
+    pub fn get_role(&self) -> SlaveOrMaster {
+        self.slave_or_master
+    }
+
+    /// Get the communication direction of the main part.
+    pub fn get_main_direction(&self) -> TransmitOrReceive {
+        self.main_dir
+    }
+
+    /// Get the communication direction of the extension part.
+    pub fn get_ext_direction(&self) -> TransmitOrReceive {
+        self.ext_dir
+    }
+
+    /// Get the selected I2s standard.
+    pub fn get_standard(&self) -> I2sStandard {
+        self.standard
+    }
+
+    /// Get the selected data format.
+    pub fn get_data_format(&self) -> DataFormat {
+        self.data_format
+    }
+
+    /// Get the selected steady state clock polarity.
+    pub fn get_clock_polarity(&self) -> ClockPolarity {
+        self.clock_polarity
+    }
+
+    /// Return `true` if the master clock is enabled. This is always `false` for a slave.
+    pub fn get_master_clock(&self) -> bool {
+        self.master_clock
+    }
+
+    /// Get the way the sampling frequency is specified.
+    ///
+    /// This is meaningless for a slave.
+    pub fn get_frequency(&self) -> Frequency {
+        self.frequency
+    }
+
+    /// Get the prescaler configuration, `(odd, div)`, a driver created from this configuration
+    /// will use with the given i2s clock source frequency.
+    ///
+    /// This is meaningless for a slave.
+    pub fn get_prescaler(&self, i2s_freq: u32) -> Result<(bool, u8), FrequencyError> {
+        _prescaler(
+            self.frequency,
+            || i2s_freq,
+            self.master_clock,
+            self.standard,
+            self.data_format,
+        )
+    }
+
     /// Convert to a slave configuration. This deletes Master Only Settings.
     pub fn to_slave(self) -> DualI2sDriverConfig<Slave, MAIN_DIR, EXT_DIR, STD> {
         let Self {
@@ -1289,6 +1916,67 @@ impl<MAIN_DIR, EXT_DIR, STD> DualI2sDriverConfig<Master, MAIN_DIR, EXT_DIR, STD>
         self.frequency = Frequency::Require(freq);
         self
     }
+
+    /// Request an audio sampling frequency, with an error of at most `max_ppm` parts per million.
+    ///
+    /// The nearest reachable frequency is selected. If the error exceeds `max_ppm`, instantiating
+    /// the driver will panic, and the fallible instantiation will report the best achievable
+    /// frequency.
+    pub fn request_frequency_within(mut self, freq: u32, max_ppm: u32) -> Self {
+        self.frequency = Frequency::RequestWithin(freq, max_ppm);
+        self
+    }
+}
+
+#[allow(non_camel_case_types)]
+impl<MAIN_DIR, EXT_DIR, STD> DualI2sDriverConfig<Master, MAIN_DIR, EXT_DIR, STD> {
+    /// Instantiate the driver like [`dual_i2s_driver`](Self::dual_i2s_driver), after
+    /// reprogramming the i2s clock source when this allows to reach the requested or required
+    /// frequency exactly.
+    ///
+    /// The clock source is left untouched if the frequency can already be reached exactly, or
+    /// can't be reached exactly at all.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if an exact frequency is required, or a frequency is requested within a
+    /// tolerance, and that frequency cannot be set.
+    pub fn dual_i2s_driver_with_clock_control<I>(
+        self,
+        dual_i2s_peripheral: I,
+    ) -> DualI2sDriver<I, Master, MAIN_DIR, EXT_DIR, STD>
+    where
+        I: DualI2sPeripheral + I2sClockControl,
+    {
+        match self.try_dual_i2s_driver_with_clock_control(dual_i2s_peripheral) {
+            Ok(driver) => driver,
+            Err((_, error)) => panic!("{}", error),
+        }
+    }
+
+    /// Fallible version of
+    /// [`dual_i2s_driver_with_clock_control`](Self::dual_i2s_driver_with_clock_control).
+    #[allow(clippy::type_complexity)]
+    pub fn try_dual_i2s_driver_with_clock_control<I>(
+        self,
+        mut dual_i2s_peripheral: I,
+    ) -> Result<DualI2sDriver<I, Master, MAIN_DIR, EXT_DIR, STD>, (I, FrequencyError)>
+    where
+        I: DualI2sPeripheral + I2sClockControl,
+    {
+        let i2s_clock = _find_exact_i2s_clock(
+            &dual_i2s_peripheral,
+            dual_i2s_peripheral.i2s_freq(),
+            self.frequency,
+            self.master_clock,
+            self.standard,
+            self.data_format,
+        );
+        if let Some(i2s_clock) = i2s_clock {
+            dual_i2s_peripheral.set_i2s_freq(i2s_clock);
+        }
+        self.try_dual_i2s_driver(dual_i2s_peripheral)
+    }
 }
 
 /// Main or extension part of a `DualI2sDriver`.
@@ -1367,6 +2055,49 @@ where
             _std: PhantomData,
         }
     }
+
+    /// Get the active configuration, decoded from the configuration registers.
+    pub fn decoded_config(&self) -> DecodedConfig {
+        let registers = self.registers();
+        decode_registers(
+            registers.i2scfgr.read().bits(),
+            registers.i2spr.read().bits(),
+        )
+    }
+}
+
+/// Event polling, Master Mode
+impl<I, PART, DIR, STD> I2sCore<I, PART, Master, DIR, STD>
+where
+    I: DualI2sPeripheral,
+    DIR: Direction,
+    STD: marker::I2sStandard,
+    Self: I2sCoreRegisters,
+{
+    /// Poll the status register and return pending events.
+    ///
+    /// Received data is read and flags are cleared as needed, so each event is reported once.
+    /// `ReadyToWrite` is cleared by writing the data register.
+    pub fn poll_events(&mut self) -> Events {
+        _poll_events(self.registers(), true, DIR::VALUE, STD::VALUE)
+    }
+}
+
+/// Event polling, Slave Mode
+impl<I, PART, DIR, STD> I2sCore<I, PART, Slave, DIR, STD>
+where
+    I: DualI2sPeripheral,
+    DIR: Direction,
+    STD: marker::I2sStandard,
+    Self: I2sCoreRegisters,
+{
+    /// Poll the status register and return pending events.
+    ///
+    /// Received data is read and flags are cleared as needed, so each event is reported once.
+    /// `ReadyToWrite` is cleared by writing the data register.
+    pub fn poll_events(&mut self) -> Events {
+        _poll_events(self.registers(), false, DIR::VALUE, STD::VALUE)
+    }
 }
 
 /// Transmit-only methods
@@ -1448,7 +2179,7 @@ where
 ///  - `I`: the [DualI2sPeripheral] controlled by the driver.
 ///  - `MS`: `Master` or `Slave`. Role of the driver, which mainly applies to the "main" part.
 ///  - `MAIN_DIR` and `EXT_DIR` : Communication direction of the main and extension part, can be
-///  `Transmit` or `Receive`.
+///    `Transmit` or `Receive`.
 ///  - `STD`: I2S standard, eg `Philips`
 #[allow(non_camel_case_types)]
 pub struct DualI2sDriver<I, MS, MAIN_DIR, EXT_DIR, STD> {
@@ -1563,16 +2294,487 @@ where
     }
 }
 
+/// Master methods reprogramming the i2s clock source
+#[allow(non_camel_case_types)]
+impl<I, MAIN_DIR, EXT_DIR, STD> DualI2sDriver<I, Master, MAIN_DIR, EXT_DIR, STD>
+where
+    I: DualI2sPeripheral + I2sClockControl,
+{
+    // change the sampling frequency, see request_frequency
+    fn _change_frequency(&mut self, frequency: Frequency) -> Result<(), FrequencyError> {
+        let config = self.main.decoded_config();
+        let ext_enabled = self.ext.decoded_config().enabled;
+        // with reserved data length, channel length is 32 bits
+        let data_format = config.data_format.unwrap_or(DataFormat::Data32Channel32);
+        let (mclk, std) = (config.master_clock, config.standard);
+        let current = self.dual_i2s_peripheral.i2s_freq();
+        let new_clock = _find_exact_i2s_clock(
+            &self.dual_i2s_peripheral,
+            current,
+            frequency,
+            mclk,
+            std,
+            data_format,
+        );
+        let i2s_clock = new_clock.unwrap_or(current);
+        let (odd, div) = _prescaler(frequency, || i2s_clock, mclk, std, data_format)?;
+        self.main.disable();
+        self.ext.disable();
+        if let Some(i2s_clock) = new_clock {
+            self.dual_i2s_peripheral.set_i2s_freq(i2s_clock);
+        }
+        self.main.registers().i2spr.modify(|_, w| {
+            _set_prescaler(w, odd, div);
+            w
+        });
+        self.reset_clocks();
+        // the slave part is enabled first, so it's ready when clocks start
+        if ext_enabled {
+            self.ext.enable();
+        }
+        if config.enabled {
+            self.main.enable();
+        }
+        Ok(())
+    }
+
+    /// Change the sampling frequency, reprogramming the i2s clock source when this allows to reach
+    /// `freq` exactly. Otherwise, the nearest frequency reachable with the current clock source is
+    /// set.
+    ///
+    /// Both parts are disabled during the change and clocks are reset, so the current frame is
+    /// interrupted. Each part is enabled again afterward if it was enabled.
+    pub fn request_frequency(&mut self, freq: u32) {
+        // a request never fails
+        self._change_frequency(Frequency::Request(freq)).ok();
+    }
+
+    /// Change the sampling frequency to exactly `freq`, reprogramming the i2s clock source if
+    /// needed.
+    ///
+    /// On error, nothing is changed. See [`request_frequency`](Self::request_frequency) about
+    /// peripheral state during the change.
+    pub fn require_frequency(&mut self, freq: u32) -> Result<(), FrequencyError> {
+        self._change_frequency(Frequency::Require(freq))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    struct FakeWsPin;
+
+    impl WsPin for FakeWsPin {
+        fn is_low(&self) -> bool {
+            true
+        }
+        fn is_high(&self) -> bool {
+            false
+        }
+    }
+
+    /// `true` when `freq` can be produced by a PLLI2S fed by 1 MHz, with a VCO from 100 to 432 MHz
+    /// divided by 2 to 7.
+    fn plli2s_can_set(freq: u32) -> bool {
+        (2..=7).any(|r| {
+            let vco = freq as u64 * r;
+            vco % 1_000_000 == 0 && (100_000_000..=432_000_000).contains(&vco)
+        })
+    }
+
+    /// Define a fake peripheral backed by its own in-memory register block, so tests using
+    /// different fake peripherals can run in parallel. The peripheral holds its i2s clock
+    /// frequency. With `clock_control`, the clock can be changed like a PLLI2S, see
+    /// `plli2s_can_set`.
+    macro_rules! fake_peripheral {
+        ($name:ident, $registers:ident) => {
+            struct $name(u32);
+            static mut $registers: [u32; 9] = [0; 9];
+
+            unsafe impl I2sPeripheral for $name {
+                type WsPin = FakeWsPin;
+                const REGISTERS: *const () = core::ptr::addr_of!($registers) as *const ();
+                fn i2s_freq(&self) -> u32 {
+                    self.0
+                }
+                fn ws_pin(&self) -> &Self::WsPin {
+                    &FakeWsPin
+                }
+                fn ws_pin_mut(&mut self) -> &mut Self::WsPin {
+                    unimplemented!()
+                }
+                fn rcc_reset(&mut self) {}
+            }
+        };
+        ($name:ident, $registers:ident, clock_control) => {
+            fake_peripheral!($name, $registers);
+
+            impl I2sClockControl for $name {
+                fn can_set_i2s_freq(&self, freq: u32) -> bool {
+                    plli2s_can_set(freq)
+                }
+                fn set_i2s_freq(&mut self, freq: u32) {
+                    self.0 = freq;
+                }
+            }
+        };
+    }
+
+    fake_peripheral!(CkpolPeripheral, CKPOL_REGISTERS);
+    fake_peripheral!(FakePeripheral, REGISTERS);
+    fake_peripheral!(FakePeripheral2, REGISTERS2);
+    fake_peripheral!(FakePeripheral3, REGISTERS3);
+    fake_peripheral!(FakePeripheral4, REGISTERS4);
+    fake_peripheral!(FakePeripheral5, REGISTERS5);
+    fake_peripheral!(FakeClockPeripheral, REGISTERS_CLOCK);
+    fake_peripheral!(FakePllPeripheral, REGISTERS_PLL, clock_control);
+
+    /// Stop recording register accesses and get them as text lines.
+    fn recorded() -> std::vec::Vec<std::string::String> {
+        use std::string::ToString;
+        crate::trace::stop_recording()
+            .iter()
+            .map(|record| record.to_string())
+            .collect()
+    }
+
+    /// Prescaler and sample rate set by a driver from `config`.
+    fn driver_rate<STD>(
+        config: I2sDriverConfig<Master, Transmit, STD>,
+        i2s_clock: u32,
+    ) -> (bool, u8, u32) {
+        let driver = config.i2s_driver(FakeClockPeripheral(i2s_clock));
+        let decoded = driver.decoded_config();
+        (decoded.odd, decoded.div, driver.sample_rate())
+    }
+
+    #[test]
+    fn test_sample_rate_round_trip() {
+        let standards = [
+            I2sStandard::Philips,
+            I2sStandard::Msb,
+            I2sStandard::Lsb,
+            I2sStandard::PcmShortSync,
+            I2sStandard::PcmLongSync,
+        ];
+        let formats = [
+            DataFormat::Data16Channel16,
+            DataFormat::Data16Channel32,
+            DataFormat::Data24Channel32,
+            DataFormat::Data32Channel32,
+        ];
+        for &i2s_clock in [12_288_000, 86_000_000, 180_633_600].iter() {
+            for &standard in standards.iter() {
+                for &data_format in formats.iter() {
+                    for &master_clock in [false, true].iter() {
+                        for &freq in clock::STANDARD_RATES.iter() {
+                            let config = I2sDriverConfig::new_master()
+                                .data_format(data_format)
+                                .master_clock(master_clock)
+                                .request_frequency(freq);
+                            let (decoded_odd, decoded_div, rate) = match standard {
+                                I2sStandard::Philips => driver_rate(config, i2s_clock),
+                                I2sStandard::Msb => driver_rate(config.standard(Msb), i2s_clock),
+                                I2sStandard::Lsb => driver_rate(config.standard(Lsb), i2s_clock),
+                                I2sStandard::PcmShortSync => {
+                                    driver_rate(config.standard(PcmShortSync), i2s_clock)
+                                }
+                                I2sStandard::PcmLongSync => {
+                                    driver_rate(config.standard(PcmLongSync), i2s_clock)
+                                }
+                            };
+                            let (odd, div) = clock::request_prescaler(
+                                i2s_clock,
+                                freq,
+                                master_clock,
+                                standard,
+                                data_format,
+                            );
+                            assert_eq!((decoded_odd, decoded_div), (odd, div));
+                            let expected = clock::sample_rate(
+                                i2s_clock,
+                                odd,
+                                div,
+                                master_clock,
+                                standard,
+                                data_format,
+                            );
+                            assert_eq!(rate, expected);
+                            let require = clock::require_prescaler(
+                                i2s_clock,
+                                freq,
+                                master_clock,
+                                standard,
+                                data_format,
+                            );
+                            if require.is_some() {
+                                assert_eq!(rate, freq);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_clock_polarity() {
+        // CKPOL is bit 3 of I2SCFGR, the eighth register
+        let ckpol = || unsafe { CKPOL_REGISTERS[7] } & (1 << 3) != 0;
+        let driver = I2sDriverConfig::new_master()
+            .clock_polarity(ClockPolarity::IdleHigh)
+            .i2s_driver(CkpolPeripheral(86_000_000));
+        assert!(ckpol());
+        I2sDriverConfig::new_master()
+            .clock_polarity(ClockPolarity::IdleLow)
+            .i2s_driver(driver.release());
+        assert!(!ckpol());
+    }
+
+    #[test]
+    fn test_decoded_config() {
+        let config = I2sDriverConfig::new_master()
+            .receive()
+            .standard(PcmLongSync)
+            .data_format(DataFormat::Data24Channel32)
+            .clock_polarity(ClockPolarity::IdleHigh)
+            .master_clock(true)
+            .prescaler(true, 12);
+        assert_eq!(config.get_role(), SlaveOrMaster::Master);
+        assert_eq!(config.get_direction(), TransmitOrReceive::Receive);
+        assert_eq!(config.get_standard(), I2sStandard::PcmLongSync);
+        assert_eq!(config.get_frequency(), Frequency::Prescaler(true, 12));
+        let mut driver = config.i2s_driver(FakePeripheral(86_000_000));
+        driver.enable();
+        let decoded = driver.decoded_config();
+        let expected = DecodedConfig {
+            i2s_mode: true,
+            enabled: true,
+            role: config.get_role(),
+            direction: config.get_direction(),
+            standard: config.get_standard(),
+            data_format: Some(config.get_data_format()),
+            clock_polarity: config.get_clock_polarity(),
+            master_clock: config.get_master_clock(),
+            odd: true,
+            div: 12,
+        };
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn test_request_frequency_within() {
+        // 86 MHz with master clock: 48 kHz is reached at -186 ppm
+        let config = I2sDriverConfig::new_master().master_clock(true);
+        let res = config
+            .request_frequency_within(48_000, 100)
+            .try_i2s_driver(FakePeripheral2(86_000_000));
+        let (_, error) = res.err().unwrap();
+        assert_eq!(error.max_ppm, 100);
+        assert_eq!((error.best.odd, error.best.div), (true, 3));
+        assert_eq!(error.best.ppm, -186);
+        let res = config
+            .require_frequency(48_000)
+            .try_i2s_driver(FakePeripheral2(86_000_000));
+        assert_eq!(res.err().unwrap().1.max_ppm, 0);
+        let driver = config
+            .request_frequency_within(48_000, 200)
+            .try_i2s_driver(FakePeripheral2(86_000_000))
+            .ok()
+            .unwrap();
+        let decoded = driver.decoded_config();
+        assert_eq!((decoded.odd, decoded.div), (true, 3));
+    }
+
     #[test]
-    fn test_div_round() {
-        let fracs = [(1, 2), (2, 2), (1, 3), (2, 3), (2, 4), (3, 5), (9, 2)];
-        for (n, d) in fracs {
-            let res = div_round(n, d);
-            let check = f32::round((n as f32) / (d as f32)) as u32;
-            assert_eq!(res, check);
+    fn test_clock_control() {
+        // 86 MHz only gives 48 kHz at -186 ppm
+        let pll = FakePllPeripheral(86_000_000);
+        let mut driver = I2sDriverConfig::new_master()
+            .require_frequency(48_000)
+            .i2s_driver_with_clock_control(pll);
+        // 48 kHz * 32 * 25 from a 192 MHz VCO divided by 5
+        assert_eq!(driver.i2s_peripheral.0, 38_400_000);
+        assert_eq!(driver.sample_rate(), 48_000);
+        driver.enable();
+        // switch to 32 kHz, from a 128 MHz VCO divided by 5
+        driver.require_frequency(32_000).unwrap();
+        assert_eq!(driver.i2s_peripheral.0, 25_600_000);
+        assert_eq!(driver.sample_rate(), 32_000);
+        assert!(driver.decoded_config().enabled);
+        // the 44.1 kHz family isn't reachable with a 1 MHz PLL input
+        assert!(driver.require_frequency(44_100).is_err());
+        assert_eq!(driver.i2s_peripheral.0, 25_600_000);
+        // reachable with the current clock
+        driver.request_frequency(16_000);
+        assert_eq!(driver.i2s_peripheral.0, 25_600_000);
+        assert_eq!(driver.sample_rate(), 16_000);
+    }
+
+    /// Fake dual peripheral with a PLLI2S.
+    struct FakeDualPllPeripheral(u32);
+    static mut REGISTERS_DUAL_MAIN: [u32; 9] = [0; 9];
+    static mut REGISTERS_DUAL_EXT: [u32; 9] = [0; 9];
+
+    unsafe impl DualI2sPeripheral for FakeDualPllPeripheral {
+        type WsPin = FakeWsPin;
+        const MAIN_REGISTERS: *const () = core::ptr::addr_of!(REGISTERS_DUAL_MAIN) as *const ();
+        const EXT_REGISTERS: *const () = core::ptr::addr_of!(REGISTERS_DUAL_EXT) as *const ();
+        fn i2s_freq(&self) -> u32 {
+            self.0
         }
+        fn ws_pin(&self) -> &Self::WsPin {
+            &FakeWsPin
+        }
+        fn ws_pin_mut(&mut self) -> &mut Self::WsPin {
+            unimplemented!()
+        }
+        fn rcc_reset(&mut self) {}
+    }
+
+    impl I2sClockControl for FakeDualPllPeripheral {
+        fn can_set_i2s_freq(&self, freq: u32) -> bool {
+            plli2s_can_set(freq)
+        }
+        fn set_i2s_freq(&mut self, freq: u32) {
+            self.0 = freq;
+        }
+    }
+
+    #[test]
+    fn test_dual_clock_control() {
+        let mut driver = DualI2sDriverConfig::new_master()
+            .require_frequency(48_000)
+            .dual_i2s_driver_with_clock_control(FakeDualPllPeripheral(86_000_000));
+        assert_eq!(driver.dual_i2s_peripheral.0, 38_400_000);
+        assert_eq!(driver.sample_rate(), 48_000);
+        driver.ext().enable();
+        driver.require_frequency(32_000).unwrap();
+        assert_eq!(driver.dual_i2s_peripheral.0, 25_600_000);
+        assert_eq!(driver.sample_rate(), 32_000);
+        // enabled state of each part is kept
+        assert!(driver.ext().decoded_config().enabled);
+        assert!(!driver.main().decoded_config().enabled);
+        assert!(driver.require_frequency(44_100).is_err());
+        assert_eq!(driver.sample_rate(), 32_000);
+    }
+
+    #[test]
+    fn test_clocks_only() {
+        let mut driver = I2sDriverConfig::new_master()
+            .receive()
+            .master_clock(true)
+            .i2s_driver(FakePeripheral3(86_000_000));
+        driver.set_rx_interrupt(true);
+        driver.set_rx_dma(true);
+        driver.set_error_interrupt(true);
+        assert_ne!(driver.registers().cr2.read().bits() & 0b1110_0011, 0);
+        driver.enable_clocks_only();
+        let registers = driver.registers();
+        assert_eq!(registers.cr2.read().bits() & 0b1110_0011, 0);
+        assert!(driver.decoded_config().enabled);
+        driver.start_data_transfer();
+        assert!(driver.decoded_config().enabled);
+        // interrupts and DMA stay disabled until the caller enables them again
+        let registers = driver.registers();
+        assert_eq!(registers.cr2.read().bits() & 0b1110_0011, 0);
+        driver.set_rx_interrupt(true);
+        driver.set_rx_dma(true);
+        let registers = driver.registers();
+        assert_eq!(registers.cr2.read().bits() & 0b1110_0011, 0b0100_0001);
+    }
+
+    #[test]
+    fn test_poll_events() {
+        const SR: usize = 2;
+        const DR: usize = 3;
+        let set_registers = |sr, dr| unsafe {
+            REGISTERS4[SR] = sr;
+            REGISTERS4[DR] = dr;
+        };
+        // TXE, CHSIDE, UDR, FRE
+        set_registers(0b1_0000_1110, 0);
+        let mut driver = I2sDriverConfig::new_slave().i2s_driver(FakePeripheral4(86_000_000));
+        let events = driver.poll_events();
+        assert!(events.has_error());
+        let mut iter = events.iter();
+        assert_eq!(iter.next(), Some(Event::ReadyToWrite(Some(Channel::Right))));
+        assert_eq!(iter.next(), Some(Event::Underrun));
+        assert_eq!(iter.next(), Some(Event::FrameError));
+        assert_eq!(iter.next(), None);
+        // master transmit has neither channel information nor error
+        let mut driver = driver.reconfigure(I2sDriverConfig::new_master());
+        let events = driver.poll_events();
+        assert_eq!(events.data(), Some(Event::ReadyToWrite(None)));
+        assert!(!events.has_error());
+        // RXNE, OVR
+        set_registers(0b0100_0001, 0x1234);
+        let config = I2sDriverConfig::new_master()
+            .receive()
+            .standard(PcmLongSync);
+        let mut driver = driver.reconfigure(config);
+        let events = driver.poll_events();
+        assert_eq!(events.data(), Some(Event::DataReceived(0x1234, None)));
+        assert!(events.overrun() && !events.frame_error());
+        set_registers(0, 0);
+        assert!(driver.poll_events().is_empty());
+    }
+
+    #[test]
+    fn test_golden_driver() {
+        crate::trace::start_recording();
+        let mut driver = I2sDriverConfig::new_master()
+            .standard(Msb)
+            .data_format(DataFormat::Data24Channel32)
+            .master_clock(true)
+            .request_frequency(48_000)
+            .i2s_driver(FakePeripheral5(86_000_000));
+        let expected = [
+            "write CR1 0x00000000 []",
+            "write CR2 0x00000000 []",
+            "write I2SCFGR 0x00000a13 [CHLEN DATLEN=1 I2SSTD=1 I2SCFG=2 I2SMOD]",
+            "write I2SPR 0x00000303 [I2SDIV=3 ODD MCKOE]",
+        ];
+        assert_eq!(recorded(), expected);
+        crate::trace::start_recording();
+        driver.enable();
+        let expected = [
+            "modify I2SCFGR 0x00000a13 -> 0x00000e13 [CHLEN DATLEN=1 I2SSTD=1 I2SCFG=2 I2SE I2SMOD]",
+        ];
+        assert_eq!(recorded(), expected);
+        crate::trace::start_recording();
+        driver.reset_clocks();
+        let expected = [
+            "read CR2 0x00000000 []",
+            "read I2SCFGR 0x00000e13 [CHLEN DATLEN=1 I2SSTD=1 I2SCFG=2 I2SE I2SMOD]",
+            "read I2SPR 0x00000303 [I2SDIV=3 ODD MCKOE]",
+            "write CR2 0x00000000 []",
+            "write I2SPR 0x00000303 [I2SDIV=3 ODD MCKOE]",
+            "write I2SCFGR 0x00000e13 [CHLEN DATLEN=1 I2SSTD=1 I2SCFG=2 I2SE I2SMOD]",
+        ];
+        assert_eq!(recorded(), expected);
+    }
+
+    #[test]
+    fn test_decode_registers() {
+        // reset values
+        let decoded = decode_registers(0x0000, 0x0002);
+        assert!(!decoded.i2s_mode);
+        assert_eq!(decoded.role, SlaveOrMaster::Slave);
+        assert_eq!(decoded.direction, TransmitOrReceive::Transmit);
+        assert_eq!(decoded.standard, I2sStandard::Philips);
+        assert_eq!(decoded.data_format, Some(DataFormat::Data16Channel16));
+        // I2SMOD, I2SE, master Tx, MSB, CKPOL, 24 bit; MCKOE, odd, div = 3
+        let decoded = decode_registers(0x0E1A, 0x0303);
+        assert_eq!(decoded.role, SlaveOrMaster::Master);
+        assert_eq!(decoded.direction, TransmitOrReceive::Transmit);
+        assert_eq!(decoded.standard, I2sStandard::Msb);
+        assert_eq!(decoded.clock_polarity, ClockPolarity::IdleHigh);
+        assert_eq!(decoded.data_format, Some(DataFormat::Data24Channel32));
+        assert!(decoded.master_clock && decoded.odd && decoded.enabled);
+        assert_eq!(decoded.div, 3);
+        // reserved data length
+        assert_eq!(decode_registers(0x0806, 0x0002).data_format, None);
     }
 }
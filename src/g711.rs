@@ -0,0 +1,298 @@
+//! G.711 companding for telephony links.
+//!
+//! PCM standards are mostly used by telephony codecs and voice links at 8 or 16 kHz, where voice
+//! samples are often exchanged as 8 bit G.711 codes. [`MuLaw`] and [`ALaw`] hold such codes and can
+//! be written to and read from an `I2sTransfer` using a PCM standard and 16 bit data: codes are
+//! expanded to linear samples when written, and linear samples are compressed when read.
+//!
+//! ```ignore
+//! let mut transfer = I2sTransferConfig::new_master()
+//!     .standard(PcmShortSync)
+//!     .data_format(Data16Channel16)
+//!     .request_frequency(8_000)
+//!     .i2s_transfer(i2s_peripheral);
+//! for &code in voice_packet.iter() {
+//!     block!(transfer.write(MuLaw(code))).ok();
+//! }
+//! ```
+//!
+//! Conversions follow the ITU-T G.711 reference implementation: linear samples are truncated to
+//! 14 bits for μ-law and 13 bits for A-law, and expanded samples are scaled back to 16 bits.
+use crate::marker::{Data16Channel16, Data16Channel32, PcmLongSync, PcmShortSync};
+use crate::transfer::{FromRawFrame, ToRawFrame};
+
+/// Ends of μ-law segments, for 14 bit magnitudes with bias.
+const MU_LAW_SEGMENTS: [i32; 8] = [0x3F, 0x7F, 0xFF, 0x1FF, 0x3FF, 0x7FF, 0xFFF, 0x1FFF];
+/// Ends of A-law segments, for 13 bit magnitudes.
+const A_LAW_SEGMENTS: [i32; 8] = [0x1F, 0x3F, 0x7F, 0xFF, 0x1FF, 0x3FF, 0x7FF, 0xFFF];
+const MU_LAW_BIAS: i32 = 0x84;
+const MU_LAW_CLIP: i32 = 8159;
+
+fn segment(value: i32, ends: &[i32; 8]) -> usize {
+    ends.iter().position(|&end| value <= end).unwrap_or(8)
+}
+
+/// Compress a linear sample into a μ-law code.
+pub fn linear_to_mu_law(sample: i16) -> u8 {
+    let mut value = sample as i32 >> 2;
+    let mask = if value < 0 {
+        value = -value;
+        0x7F
+    } else {
+        0xFF
+    };
+    value = value.min(MU_LAW_CLIP) + (MU_LAW_BIAS >> 2);
+    let code = match segment(value, &MU_LAW_SEGMENTS) {
+        8 => 0x7F,
+        seg => (seg << 4) as i32 | ((value >> (seg + 1)) & 0xF),
+    };
+    (code ^ mask) as u8
+}
+
+/// Expand a μ-law code into a linear sample.
+pub fn mu_law_to_linear(code: u8) -> i16 {
+    let code = !code as i32;
+    let magnitude = (((code & 0xF) << 3) + MU_LAW_BIAS) << ((code & 0x70) >> 4);
+    let sample = if code & 0x80 != 0 {
+        MU_LAW_BIAS - magnitude
+    } else {
+        magnitude - MU_LAW_BIAS
+    };
+    sample as i16
+}
+
+/// Compress a linear sample into an A-law code.
+pub fn linear_to_a_law(sample: i16) -> u8 {
+    let mut value = sample as i32 >> 3;
+    let mask = if value >= 0 {
+        0xD5
+    } else {
+        value = -value - 1;
+        0x55
+    };
+    let code = match segment(value, &A_LAW_SEGMENTS) {
+        8 => 0x7F,
+        seg @ 0..=1 => (seg << 4) as i32 | ((value >> 1) & 0xF),
+        seg => (seg << 4) as i32 | ((value >> seg) & 0xF),
+    };
+    (code ^ mask) as u8
+}
+
+/// Expand an A-law code into a linear sample.
+pub fn a_law_to_linear(code: u8) -> i16 {
+    let code = (code ^ 0x55) as i32;
+    let mantissa = (code & 0xF) << 4;
+    let magnitude = match (code & 0x70) >> 4 {
+        0 => mantissa + 8,
+        seg => (mantissa + 0x108) << (seg - 1),
+    };
+    let sample = if code & 0x80 != 0 {
+        magnitude
+    } else {
+        -magnitude
+    };
+    sample as i16
+}
+
+/// A μ-law (G.711) code, as used on North American and Japanese telephone networks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MuLaw(pub u8);
+
+impl MuLaw {
+    /// Compress a linear sample.
+    pub fn from_linear(sample: i16) -> Self {
+        Self(linear_to_mu_law(sample))
+    }
+
+    /// Expand to a linear sample.
+    pub fn to_linear(self) -> i16 {
+        mu_law_to_linear(self.0)
+    }
+}
+
+/// An A-law (G.711) code, as used on European telephone networks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ALaw(pub u8);
+
+impl ALaw {
+    /// Compress a linear sample.
+    pub fn from_linear(sample: i16) -> Self {
+        Self(linear_to_a_law(sample))
+    }
+
+    /// Expand to a linear sample.
+    pub fn to_linear(self) -> i16 {
+        a_law_to_linear(self.0)
+    }
+}
+
+macro_rules! impl_companded_frame {
+    ($($type:ty),*) => {
+        $(
+            impl_companded_frame!(@std $type, PcmShortSync, PcmLongSync);
+        )*
+    };
+    (@std $type:ty, $($std:ident),*) => {
+        $(
+            impl_companded_frame!(@fmt $type, $std, Data16Channel16, Data16Channel32);
+        )*
+    };
+    (@fmt $type:ty, $std:ident, $($fmt:ident),*) => {
+        $(
+            impl ToRawFrame<$std, $fmt> for $type {
+                fn to_raw(&self) -> [u16; 1] {
+                    [self.to_linear() as u16]
+                }
+            }
+
+            impl FromRawFrame<$std, $fmt> for $type {
+                fn from_raw(raw: [u16; 1]) -> Self {
+                    Self::from_linear(raw[0] as i16)
+                }
+            }
+        )*
+    };
+}
+
+impl_companded_frame!(MuLaw, ALaw);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// μ-law expansion of codes 0 to 255, from the G.711 reference implementation.
+    const MU_LAW_TABLE: [i16; 256] = [
+        -32124, -31100, -30076, -29052, -28028, -27004, -25980, -24956, -23932, -22908, -21884,
+        -20860, -19836, -18812, -17788, -16764, -15996, -15484, -14972, -14460, -13948, -13436,
+        -12924, -12412, -11900, -11388, -10876, -10364, -9852, -9340, -8828, -8316, -7932, -7676,
+        -7420, -7164, -6908, -6652, -6396, -6140, -5884, -5628, -5372, -5116, -4860, -4604, -4348,
+        -4092, -3900, -3772, -3644, -3516, -3388, -3260, -3132, -3004, -2876, -2748, -2620, -2492,
+        -2364, -2236, -2108, -1980, -1884, -1820, -1756, -1692, -1628, -1564, -1500, -1436, -1372,
+        -1308, -1244, -1180, -1116, -1052, -988, -924, -876, -844, -812, -780, -748, -716, -684,
+        -652, -620, -588, -556, -524, -492, -460, -428, -396, -372, -356, -340, -324, -308, -292,
+        -276, -260, -244, -228, -212, -196, -180, -164, -148, -132, -120, -112, -104, -96, -88,
+        -80, -72, -64, -56, -48, -40, -32, -24, -16, -8, 0, 32124, 31100, 30076, 29052, 28028,
+        27004, 25980, 24956, 23932, 22908, 21884, 20860, 19836, 18812, 17788, 16764, 15996, 15484,
+        14972, 14460, 13948, 13436, 12924, 12412, 11900, 11388, 10876, 10364, 9852, 9340, 8828,
+        8316, 7932, 7676, 7420, 7164, 6908, 6652, 6396, 6140, 5884, 5628, 5372, 5116, 4860, 4604,
+        4348, 4092, 3900, 3772, 3644, 3516, 3388, 3260, 3132, 3004, 2876, 2748, 2620, 2492, 2364,
+        2236, 2108, 1980, 1884, 1820, 1756, 1692, 1628, 1564, 1500, 1436, 1372, 1308, 1244, 1180,
+        1116, 1052, 988, 924, 876, 844, 812, 780, 748, 716, 684, 652, 620, 588, 556, 524, 492, 460,
+        428, 396, 372, 356, 340, 324, 308, 292, 276, 260, 244, 228, 212, 196, 180, 164, 148, 132,
+        120, 112, 104, 96, 88, 80, 72, 64, 56, 48, 40, 32, 24, 16, 8, 0,
+    ];
+
+    /// A-law expansion of codes 0 to 255, from the G.711 reference implementation.
+    const A_LAW_TABLE: [i16; 256] = [
+        -5504, -5248, -6016, -5760, -4480, -4224, -4992, -4736, -7552, -7296, -8064, -7808, -6528,
+        -6272, -7040, -6784, -2752, -2624, -3008, -2880, -2240, -2112, -2496, -2368, -3776, -3648,
+        -4032, -3904, -3264, -3136, -3520, -3392, -22016, -20992, -24064, -23040, -17920, -16896,
+        -19968, -18944, -30208, -29184, -32256, -31232, -26112, -25088, -28160, -27136, -11008,
+        -10496, -12032, -11520, -8960, -8448, -9984, -9472, -15104, -14592, -16128, -15616, -13056,
+        -12544, -14080, -13568, -344, -328, -376, -360, -280, -264, -312, -296, -472, -456, -504,
+        -488, -408, -392, -440, -424, -88, -72, -120, -104, -24, -8, -56, -40, -216, -200, -248,
+        -232, -152, -136, -184, -168, -1376, -1312, -1504, -1440, -1120, -1056, -1248, -1184,
+        -1888, -1824, -2016, -1952, -1632, -1568, -1760, -1696, -688, -656, -752, -720, -560, -528,
+        -624, -592, -944, -912, -1008, -976, -816, -784, -880, -848, 5504, 5248, 6016, 5760, 4480,
+        4224, 4992, 4736, 7552, 7296, 8064, 7808, 6528, 6272, 7040, 6784, 2752, 2624, 3008, 2880,
+        2240, 2112, 2496, 2368, 3776, 3648, 4032, 3904, 3264, 3136, 3520, 3392, 22016, 20992,
+        24064, 23040, 17920, 16896, 19968, 18944, 30208, 29184, 32256, 31232, 26112, 25088, 28160,
+        27136, 11008, 10496, 12032, 11520, 8960, 8448, 9984, 9472, 15104, 14592, 16128, 15616,
+        13056, 12544, 14080, 13568, 344, 328, 376, 360, 280, 264, 312, 296, 472, 456, 504, 488,
+        408, 392, 440, 424, 88, 72, 120, 104, 24, 8, 56, 40, 216, 200, 248, 232, 152, 136, 184,
+        168, 1376, 1312, 1504, 1440, 1120, 1056, 1248, 1184, 1888, 1824, 2016, 1952, 1632, 1568,
+        1760, 1696, 688, 656, 752, 720, 560, 528, 624, 592, 944, 912, 1008, 976, 816, 784, 880,
+        848,
+    ];
+
+    /// Linear samples with their μ-law and A-law codes, from the G.711 reference implementation.
+    const ENCODED: [(i16, u8, u8); 32] = [
+        (-32768, 0x00, 0x2A),
+        (-32124, 0x00, 0x2A),
+        (-20000, 0x0C, 0x26),
+        (-1000, 0x4E, 0x7A),
+        (-256, 0x67, 0x5A),
+        (-33, 0x7A, 0x57),
+        (-32, 0x7B, 0x54),
+        (-9, 0x7D, 0x55),
+        (-8, 0x7E, 0x55),
+        (-5, 0x7E, 0x55),
+        (-4, 0x7E, 0x55),
+        (-1, 0x7E, 0x55),
+        (0, 0xFF, 0xD5),
+        (1, 0xFF, 0xD5),
+        (3, 0xFF, 0xD5),
+        (4, 0xFE, 0xD5),
+        (7, 0xFE, 0xD5),
+        (8, 0xFE, 0xD5),
+        (15, 0xFD, 0xD5),
+        (16, 0xFD, 0xD4),
+        (31, 0xFB, 0xD4),
+        (32, 0xFB, 0xD7),
+        (100, 0xF2, 0xD3),
+        (255, 0xE7, 0xDA),
+        (256, 0xE7, 0xC5),
+        (1000, 0xCE, 0xFA),
+        (4000, 0xAF, 0x9A),
+        (8000, 0xA0, 0x8A),
+        (16000, 0x90, 0xBA),
+        (20000, 0x8C, 0xA6),
+        (32000, 0x80, 0xAA),
+        (32767, 0x80, 0xAA),
+    ];
+
+    #[test]
+    fn test_expand() {
+        for code in 0..=255 {
+            assert_eq!(
+                mu_law_to_linear(code),
+                MU_LAW_TABLE[code as usize],
+                "{}",
+                code
+            );
+            assert_eq!(
+                a_law_to_linear(code),
+                A_LAW_TABLE[code as usize],
+                "{}",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn test_compress() {
+        for &(sample, mu_law, a_law) in ENCODED.iter() {
+            assert_eq!(linear_to_mu_law(sample), mu_law, "{}", sample);
+            assert_eq!(linear_to_a_law(sample), a_law, "{}", sample);
+        }
+        // expanded codes are compressed back to themselves, except the negative μ-law zero
+        for code in 0..=255 {
+            if code != 0x7F {
+                assert_eq!(linear_to_mu_law(mu_law_to_linear(code)), code);
+            }
+            assert_eq!(linear_to_a_law(a_law_to_linear(code)), code);
+        }
+        // compression is monotonic
+        let mut previous = (i32::MIN, i32::MIN);
+        for sample in i16::MIN..=i16::MAX {
+            let levels = (
+                mu_law_to_linear(linear_to_mu_law(sample)) as i32,
+                a_law_to_linear(linear_to_a_law(sample)) as i32,
+            );
+            assert!(
+                levels.0 >= previous.0 && levels.1 >= previous.1,
+                "{}",
+                sample
+            );
+            previous = levels;
+        }
+    }
+
+    #[test]
+    fn test_frames() {
+        let raw = <MuLaw as ToRawFrame<PcmShortSync, Data16Channel16>>::to_raw(&MuLaw(0x80));
+        assert_eq!(raw, [32124]);
+        let code = <ALaw as FromRawFrame<PcmLongSync, Data16Channel32>>::from_raw([0x8000]);
+        assert_eq!(code, ALaw(0x2A));
+    }
+}
@@ -12,9 +12,10 @@
 //! # For STM32 MCU HAL implementers
 //!
 //! To support I2S by using this library, HAL implementers must implements [`I2sPeripheral`],
-//! [`DualI2sPeripheral`] and [`WsPin`] and  trait and reexport this crate. It's also recommended
-//! to create some examples. For reference, implementation and examples are (or will be soon)
-//! available in [stm32f4xx-hal](https://github.com/stm32-rs/stm32f4xx-hal/).
+//! [`DualI2sPeripheral`] and [`WsPin`] and  trait and reexport this crate. Implementing
+//! [`I2sClockControl`] is optional and allows drivers to reprogram the I2S clock source. It's
+//! also recommended to create some examples. For reference, implementation and examples are (or
+//! will be soon) available in [stm32f4xx-hal](https://github.com/stm32-rs/stm32f4xx-hal/).
 //!
 //! # For I2S users
 //!
@@ -27,9 +28,9 @@
 //! at least. Compared to other modes:
 //!  - PCM is monophonic; this is why the Channel flag information is meaningless.
 //!  - With the same prescaler configuration, the sampling frequency is doubled. This is because
-//!  the bit rate is the same with half the samples.
+//!    the bit rate is the same with half the samples.
 //!  - When master clock is enabled, its frequency is 128 * sampling_frequency, instead of 256 *
-//!  sampling_frequency.
+//!    sampling_frequency.
 //!
 //! # Issues and limitations
 //! - In master transmit mode, the CHSIDE flag appears to be sporadically wrong, so don't use it.
@@ -39,11 +40,29 @@
 //!
 #![no_std]
 
+#[cfg(any(test, feature = "trace"))]
+extern crate std;
+
 mod pac;
 
+pub mod audio;
+pub mod capture;
+pub mod channel;
+pub mod clock;
+pub mod codec;
 pub mod driver;
+pub mod dynamic;
+pub mod g711;
 pub mod marker;
+pub mod pdm;
+pub mod sample;
+#[cfg(any(test, feature = "trace"))]
+pub mod sim;
+#[cfg(any(test, feature = "trace"))]
+pub mod trace;
 pub mod transfer;
+pub mod wav;
+pub mod waveform;
 
 mod sealed {
     pub trait Sealed {}
@@ -94,9 +113,9 @@ pub unsafe trait I2sPeripheral {
 /// It is only safe to implement this trait when:
 ///
 /// * The implementing type has ownership of the peripherals, preventing any other accesses to the
-/// register blocks.
+///   register blocks.
 /// * `MAIN_REGISTERS` and `EXT_REGISTERS` are pointers to that peripheral's register blocks and
-/// can be safely accessed  as long as ownership or a borrow of the implementing type is present.
+///   can be safely accessed  as long as ownership or a borrow of the implementing type is present.
 pub unsafe trait DualI2sPeripheral {
     type WsPin: WsPin;
     /// Pointer to the SPI register block
@@ -116,6 +135,26 @@ pub unsafe trait DualI2sPeripheral {
     fn rcc_reset(&mut self);
 }
 
+/// An I2S device whose clock source frequency can be changed, for example by reprogramming the
+/// PLLI2S or another audio PLL.
+///
+/// Implementing this trait is optional. When it's implemented on an [`I2sPeripheral`] or a
+/// [`DualI2sPeripheral`], drivers can reach exact sampling frequencies that the I2S prescaler
+/// alone can't reach, and switch between sampling frequency families (e.g. 44.1 kHz and 48 kHz)
+/// at runtime.
+///
+/// When the clock source is shared with other peripherals, changing it also affects them. It's up
+/// to the implementer to refuse frequencies when this is a problem.
+pub trait I2sClockControl {
+    /// Return `true` if the I2S clock source can be set to exactly `freq`.
+    fn can_set_i2s_freq(&self, freq: u32) -> bool;
+    /// Set the I2S clock source to exactly `freq`, and wait until it's stable.
+    ///
+    /// This is only called with frequencies accepted by `can_set_i2s_freq`, while the I2S
+    /// peripheral is disabled. Afterward, `i2s_freq()` must return `freq`.
+    fn set_i2s_freq(&mut self, freq: u32);
+}
+
 /// A pin carrying the WS (word select) signal from/to an i2s peripheral.
 ///
 /// Implementing this trait means implementing read operation on a pin physically configured in
@@ -21,6 +21,31 @@ pub trait ResetValue {
     fn reset_value() -> Self::Type;
 }
 
+///Offset of a register in its register block
+pub trait RegisterSpec {
+    ///Offset in bytes
+    const OFFSET: usize;
+}
+
+///Raw content of a register
+pub trait RegisterBits: Copy {
+    ///Convert to a 32 bits value
+    fn to_u32(self) -> u32;
+    ///Convert from a 32 bits value
+    fn from_u32(value: u32) -> Self;
+}
+
+impl RegisterBits for u32 {
+    #[inline(always)]
+    fn to_u32(self) -> u32 {
+        self
+    }
+    #[inline(always)]
+    fn from_u32(value: u32) -> Self {
+        value
+    }
+}
+
 ///This structure provides volatile access to register
 pub struct Reg<U, REG> {
     register: vcell::VolatileCell<U>,
@@ -29,10 +54,66 @@ pub struct Reg<U, REG> {
 
 unsafe impl<U: Send, REG> Send for Reg<U, REG> {}
 
+///Raw register accesses, going through the tracing layer when enabled
+impl<U, REG> Reg<U, REG>
+where
+    U: RegisterBits,
+    REG: RegisterSpec,
+{
+    #[cfg(any(test, feature = "trace"))]
+    fn address(&self) -> usize {
+        self.register.as_ptr() as usize
+    }
+
+    #[cfg(not(any(test, feature = "trace")))]
+    #[inline(always)]
+    fn load(&self) -> U {
+        self.register.get()
+    }
+
+    #[cfg(not(any(test, feature = "trace")))]
+    #[inline(always)]
+    fn store(&self, bits: U) {
+        self.register.set(bits)
+    }
+
+    #[cfg(not(any(test, feature = "trace")))]
+    #[inline(always)]
+    fn update<F: FnOnce(U) -> U>(&self, f: F) {
+        self.register.set(f(self.register.get()))
+    }
+
+    #[cfg(any(test, feature = "trace"))]
+    fn load(&self) -> U {
+        U::from_u32(crate::trace::read(REG::OFFSET, self.address(), || {
+            self.register.get().to_u32()
+        }))
+    }
+
+    #[cfg(any(test, feature = "trace"))]
+    fn store(&self, bits: U) {
+        crate::trace::write(REG::OFFSET, self.address(), bits.to_u32(), |bits| {
+            self.register.set(U::from_u32(bits))
+        })
+    }
+
+    #[cfg(any(test, feature = "trace"))]
+    fn update<F: FnOnce(U) -> U>(&self, f: F) {
+        crate::trace::modify(
+            REG::OFFSET,
+            self.address(),
+            || self.register.get().to_u32(),
+            |bits| f(U::from_u32(bits)).to_u32(),
+            |bits| self.register.set(U::from_u32(bits)),
+        )
+    }
+}
+
 impl<U, REG> Reg<U, REG>
 where
     Self: Readable,
-    U: Copy,
+    U: RegisterBits,
+    REG: RegisterSpec,
 {
     ///Reads the contents of `Readable` register
     ///
@@ -49,7 +130,7 @@ where
     #[inline(always)]
     pub fn read(&self) -> R<U, Self> {
         R {
-            bits: self.register.get(),
+            bits: self.load(),
             _reg: marker::PhantomData,
         }
     }
@@ -58,21 +139,23 @@ where
 impl<U, REG> Reg<U, REG>
 where
     Self: ResetValue<Type = U> + Writable,
-    U: Copy,
+    U: RegisterBits,
+    REG: RegisterSpec,
 {
     ///Writes the reset value to `Writable` register
     ///
     ///Resets the register to its initial state
     #[inline(always)]
     pub fn reset(&self) {
-        self.register.set(Self::reset_value())
+        self.store(Self::reset_value())
     }
 }
 
 impl<U, REG> Reg<U, REG>
 where
     Self: ResetValue<Type = U> + Writable,
-    U: Copy,
+    U: RegisterBits,
+    REG: RegisterSpec,
 {
     ///Writes bits to `Writable` register
     ///
@@ -94,7 +177,7 @@ where
     where
         F: FnOnce(&mut W<U, Self>) -> &mut W<U, Self>,
     {
-        self.register.set(
+        self.store(
             f(&mut W {
                 bits: Self::reset_value(),
                 _reg: marker::PhantomData,
@@ -107,7 +190,8 @@ where
 impl<U, REG> Reg<U, REG>
 where
     Self: Writable,
-    U: Copy + Default,
+    U: RegisterBits + Default,
+    REG: RegisterSpec,
 {
     ///Writes Zero to `Writable` register
     ///
@@ -117,7 +201,7 @@ where
     where
         F: FnOnce(&mut W<U, Self>) -> &mut W<U, Self>,
     {
-        self.register.set(
+        self.store(
             f(&mut W {
                 bits: U::default(),
                 _reg: marker::PhantomData,
@@ -130,7 +214,8 @@ where
 impl<U, REG> Reg<U, REG>
 where
     Self: Readable + Writable,
-    U: Copy,
+    U: RegisterBits,
+    REG: RegisterSpec,
 {
     ///Modifies the contents of the register
     ///
@@ -154,8 +239,7 @@ where
     where
         for<'w> F: FnOnce(&R<U, Self>, &'w mut W<U, Self>) -> &'w mut W<U, Self>,
     {
-        let bits = self.register.get();
-        self.register.set(
+        self.update(|bits| {
             f(
                 &R {
                     bits,
@@ -166,8 +250,8 @@ where
                     _reg: marker::PhantomData,
                 },
             )
-            .bits,
-        );
+            .bits
+        });
     }
 }
 
@@ -8,6 +8,11 @@
 #![allow(non_snake_case)]
 #![allow(unused)]
 #![allow(clippy::upper_case_acronyms)]
+// Field writer accessors like `fn ckpol(&mut self) -> CKPOL_W` hide the lifetime borrowed from
+// the register writer. Since Rust 1.89 this warns in each of them, so the lint is allowed here to
+// keep the generated code as is. Older toolchains don't know the lint.
+#![allow(unknown_lints)]
+#![allow(mismatched_lifetime_syntaxes)]
 use core::marker::PhantomData;
 use core::ops::Deref;
 
@@ -25,6 +25,9 @@ pub type CR1 = crate::pac::Reg<u32, _CR1>;
 #[allow(missing_docs)]
 #[doc(hidden)]
 pub struct _CR1;
+impl crate::pac::RegisterSpec for _CR1 {
+    const OFFSET: usize = 0x00;
+}
 #[doc = "`read()` method returns [cr1::R](cr1::R) reader structure"]
 impl crate::pac::Readable for CR1 {}
 #[doc = "`write(|w| ..)` method takes [cr1::W](cr1::W) writer structure"]
@@ -36,6 +39,9 @@ pub type CR2 = crate::pac::Reg<u32, _CR2>;
 #[allow(missing_docs)]
 #[doc(hidden)]
 pub struct _CR2;
+impl crate::pac::RegisterSpec for _CR2 {
+    const OFFSET: usize = 0x04;
+}
 #[doc = "`read()` method returns [cr2::R](cr2::R) reader structure"]
 impl crate::pac::Readable for CR2 {}
 #[doc = "`write(|w| ..)` method takes [cr2::W](cr2::W) writer structure"]
@@ -47,6 +53,9 @@ pub type SR = crate::pac::Reg<u32, _SR>;
 #[allow(missing_docs)]
 #[doc(hidden)]
 pub struct _SR;
+impl crate::pac::RegisterSpec for _SR {
+    const OFFSET: usize = 0x08;
+}
 #[doc = "`read()` method returns [sr::R](sr::R) reader structure"]
 impl crate::pac::Readable for SR {}
 #[doc = "`write(|w| ..)` method takes [sr::W](sr::W) writer structure"]
@@ -58,6 +67,9 @@ pub type DR = crate::pac::Reg<u32, _DR>;
 #[allow(missing_docs)]
 #[doc(hidden)]
 pub struct _DR;
+impl crate::pac::RegisterSpec for _DR {
+    const OFFSET: usize = 0x0c;
+}
 #[doc = "`read()` method returns [dr::R](dr::R) reader structure"]
 impl crate::pac::Readable for DR {}
 #[doc = "`write(|w| ..)` method takes [dr::W](dr::W) writer structure"]
@@ -69,6 +81,9 @@ pub type CRCPR = crate::pac::Reg<u32, _CRCPR>;
 #[allow(missing_docs)]
 #[doc(hidden)]
 pub struct _CRCPR;
+impl crate::pac::RegisterSpec for _CRCPR {
+    const OFFSET: usize = 0x10;
+}
 #[doc = "`read()` method returns [crcpr::R](crcpr::R) reader structure"]
 impl crate::pac::Readable for CRCPR {}
 #[doc = "`write(|w| ..)` method takes [crcpr::W](crcpr::W) writer structure"]
@@ -80,6 +95,9 @@ pub type RXCRCR = crate::pac::Reg<u32, _RXCRCR>;
 #[allow(missing_docs)]
 #[doc(hidden)]
 pub struct _RXCRCR;
+impl crate::pac::RegisterSpec for _RXCRCR {
+    const OFFSET: usize = 0x14;
+}
 #[doc = "`read()` method returns [rxcrcr::R](rxcrcr::R) reader structure"]
 impl crate::pac::Readable for RXCRCR {}
 #[doc = "RX CRC register"]
@@ -89,6 +107,9 @@ pub type TXCRCR = crate::pac::Reg<u32, _TXCRCR>;
 #[allow(missing_docs)]
 #[doc(hidden)]
 pub struct _TXCRCR;
+impl crate::pac::RegisterSpec for _TXCRCR {
+    const OFFSET: usize = 0x18;
+}
 #[doc = "`read()` method returns [txcrcr::R](txcrcr::R) reader structure"]
 impl crate::pac::Readable for TXCRCR {}
 #[doc = "TX CRC register"]
@@ -98,6 +119,9 @@ pub type I2SCFGR = crate::pac::Reg<u32, _I2SCFGR>;
 #[allow(missing_docs)]
 #[doc(hidden)]
 pub struct _I2SCFGR;
+impl crate::pac::RegisterSpec for _I2SCFGR {
+    const OFFSET: usize = 0x1c;
+}
 #[doc = "`read()` method returns [i2scfgr::R](i2scfgr::R) reader structure"]
 impl crate::pac::Readable for I2SCFGR {}
 #[doc = "`write(|w| ..)` method takes [i2scfgr::W](i2scfgr::W) writer structure"]
@@ -109,6 +133,9 @@ pub type I2SPR = crate::pac::Reg<u32, _I2SPR>;
 #[allow(missing_docs)]
 #[doc(hidden)]
 pub struct _I2SPR;
+impl crate::pac::RegisterSpec for _I2SPR {
+    const OFFSET: usize = 0x20;
+}
 #[doc = "`read()` method returns [i2spr::R](i2spr::R) reader structure"]
 impl crate::pac::Readable for I2SPR {}
 #[doc = "`write(|w| ..)` method takes [i2spr::W](i2spr::W) writer structure"]
@@ -0,0 +1,501 @@
+//! PDM microphone capture and PDM output.
+//!
+//! A PDM (pulse density modulation) microphone outputs a one-bit stream at a high rate, where the
+//! density of ones represents the signal. It can be clocked and read by a master receive
+//! peripheral: SCK drives the microphone clock, SD receives the bitstream, and WS is left
+//! unused. With 16 bit channels, every word read holds 16 consecutive PDM bits, most significant
+//! bit first, and a frame holds 32 bits, so the I2S sampling frequency is 1/32 of the PDM clock.
+//!
+//! [`PdmFilter`] converts the bitstream into PCM samples. A 4th order CIC filter decimates it to
+//! twice the output rate, then a 60 taps FIR low pass filter removes what's above the output
+//! Nyquist frequency and decimates by 2 to the output rate.
+//!
+//! ```ignore
+//! // MP45DT02 on STM32F4-Discovery: 16 kHz output, PDM clock of 1.024 MHz
+//! let pdm_clock = pdm::pdm_clock(16_000, 64);
+//! let mut transfer = pdm::transfer_config(pdm_clock).i2s_transfer(i2s_peripheral);
+//! let mut filter = PdmFilter::new(64);
+//! transfer.read_while(|(first, second): (i16, i16)| {
+//!     for &word in [first as u16, second as u16].iter() {
+//!         if let Some(sample) = filter.push(word) {
+//!             /* process the sample */
+//!         }
+//!     }
+//!     true
+//! });
+//! ```
+//!
+//! # PDM output
+//!
+//! The other way around, [`PdmModulator`] turns PCM samples into a PDM bitstream with a sigma-delta
+//! modulator, to be sent by a master transmit peripheral. This can drive a class-D stage, or a
+//! speaker through a low pass filter, without a DAC.
+//!
+//! ```ignore
+//! // 16 kHz input, PDM clock of 1.024 MHz
+//! let config = pdm::output_driver_config(pdm::pdm_clock(16_000, 64));
+//! let mut driver = config.i2s_driver(i2s_peripheral);
+//! let mut modulator = PdmModulator::new(ModulatorOrder::Second, 64);
+//! driver.enable();
+//! for &sample in samples.iter() {
+//!     modulator.modulate(sample, |word| {
+//!         while !driver.status().txe() {}
+//!         driver.write_data_register(word);
+//!     });
+//! }
+//! ```
+use crate::driver::{ClockPolarity, DataFormat, I2sDriverConfig};
+use crate::marker::{Data16Channel16, Lsb, Master, Receive, Transmit};
+use crate::transfer::I2sTransferConfig;
+
+/// Number of PDM bits received in an I2S frame.
+pub const BITS_PER_FRAME: u32 = 32;
+
+/// Get the PDM clock frequency giving `output_rate` with a decimation factor of `decimation`.
+pub fn pdm_clock(output_rate: u32, decimation: u32) -> u32 {
+    output_rate * decimation
+}
+
+/// Get a master receive driver configuration clocking a PDM microphone at `pdm_clock`.
+///
+/// The frequency is requested, so the effective PDM clock is `sample_rate() * 32`.
+pub fn driver_config(pdm_clock: u32) -> I2sDriverConfig<Master, Receive, Lsb> {
+    I2sDriverConfig::new_master()
+        .receive()
+        .standard(Lsb)
+        .data_format(DataFormat::Data16Channel16)
+        .clock_polarity(ClockPolarity::IdleHigh)
+        .request_frequency(pdm_clock / BITS_PER_FRAME)
+}
+
+/// Get a master receive transfer configuration clocking a PDM microphone at `pdm_clock`.
+///
+/// The frequency is requested, so the effective PDM clock is `sample_rate() * 32`.
+pub fn transfer_config(pdm_clock: u32) -> I2sTransferConfig<Master, Receive, Lsb, Data16Channel16> {
+    I2sTransferConfig::new_master()
+        .receive()
+        .standard(Lsb)
+        .data_format(Data16Channel16)
+        .clock_polarity(ClockPolarity::IdleHigh)
+        .request_frequency(pdm_clock / BITS_PER_FRAME)
+}
+
+/// Get a master transmit driver configuration sending a PDM bitstream at `pdm_clock`.
+///
+/// The frequency is requested, so the effective PDM clock is `sample_rate() * 32`.
+///
+/// The clock polarity is left to the default [`ClockPolarity::IdleLow`]: SD changes on falling
+/// edges of CK and is stable on rising edges, where class-D amplifiers and PDM receivers usually
+/// sample it. Receivers sampling on falling edges need [`ClockPolarity::IdleHigh`], set with
+/// `clock_polarity()` on the returned configuration.
+pub fn output_driver_config(pdm_clock: u32) -> I2sDriverConfig<Master, Transmit, Lsb> {
+    I2sDriverConfig::new_master()
+        .standard(Lsb)
+        .data_format(DataFormat::Data16Channel16)
+        .request_frequency(pdm_clock / BITS_PER_FRAME)
+}
+
+/// Get a master transmit transfer configuration sending a PDM bitstream at `pdm_clock`.
+///
+/// The frequency is requested, so the effective PDM clock is `sample_rate() * 32`. Clock polarity
+/// is the same as with [`output_driver_config`].
+pub fn output_transfer_config(
+    pdm_clock: u32,
+) -> I2sTransferConfig<Master, Transmit, Lsb, Data16Channel16> {
+    I2sTransferConfig::new_master()
+        .standard(Lsb)
+        .data_format(Data16Channel16)
+        .request_frequency(pdm_clock / BITS_PER_FRAME)
+}
+
+const CIC_ORDER: usize = 4;
+
+/// Low pass filter applied at twice the output rate, cutoff at 0.2 of its input rate, Blackman
+/// window, Q15 coefficients.
+const FIR: [i32; 60] = [
+    -1, -3, 0, 9, 9, -13, -29, 0, 53, 43, -56, -116, 0, 182, 139, -171, -337, 0, 494, 368, -443,
+    -865, 0, 1282, 984, -1254, -2696, 0, 6553, 12252, 12252, 6553, 0, -2696, -1254, 984, 1282, 0,
+    -865, -443, 368, 494, 0, -337, -171, 139, 182, 0, -116, -56, 43, 53, 0, -29, -13, 9, 9, 0, -3,
+    -1,
+];
+
+/// Decimation filter converting a PDM bitstream into 16 bit PCM samples.
+///
+/// A one is +1 and a zero is -1, so a bitstream containing only ones gives full scale positive
+/// samples.
+#[derive(Debug, Clone)]
+pub struct PdmFilter {
+    decimation: u32,
+    // CIC state, using wrapping arithmetic
+    integrators: [i32; CIC_ORDER],
+    combs: [i32; CIC_ORDER],
+    count: u32,
+    gain: i64,
+    // FIR state
+    history: [i32; FIR.len()],
+    position: usize,
+    odd: bool,
+}
+
+impl PdmFilter {
+    /// Create a filter dividing the bit rate by `decimation`.
+    ///
+    /// # Panics
+    ///
+    /// `decimation` must be even and between 16 and 256.
+    pub fn new(decimation: u32) -> Self {
+        assert!(
+            decimation % 2 == 0 && (16..=256).contains(&decimation),
+            "decimation must be even and between 16 and 256"
+        );
+        let cic_decimation = decimation / 2;
+        Self {
+            decimation,
+            integrators: [0; CIC_ORDER],
+            combs: [0; CIC_ORDER],
+            count: 0,
+            gain: (cic_decimation as i64).pow(CIC_ORDER as u32),
+            history: [0; FIR.len()],
+            position: 0,
+            odd: false,
+        }
+    }
+
+    /// Get the decimation factor, that is the ratio between PDM clock and output rate.
+    pub fn decimation(&self) -> u32 {
+        self.decimation
+    }
+
+    /// Clear the filter state.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.decimation);
+    }
+
+    /// Push 16 PDM bits, most significant bit first.
+    ///
+    /// Since the decimation is at least 16, this gives at most one sample.
+    pub fn push(&mut self, word: u16) -> Option<i16> {
+        let mut sample = None;
+        for i in (0..16).rev() {
+            let bit = if word & (1 << i) != 0 { 1 } else { -1 };
+            if let Some(value) = self.push_bit(bit) {
+                sample = Some(value);
+            }
+        }
+        sample
+    }
+
+    fn push_bit(&mut self, bit: i32) -> Option<i16> {
+        let mut acc = bit;
+        for integrator in self.integrators.iter_mut() {
+            *integrator = integrator.wrapping_add(acc);
+            acc = *integrator;
+        }
+        self.count += 1;
+        if self.count < self.decimation / 2 {
+            return None;
+        }
+        self.count = 0;
+        for comb in self.combs.iter_mut() {
+            let delayed = *comb;
+            *comb = acc;
+            acc = acc.wrapping_sub(delayed);
+        }
+        // normalize to Q15
+        self.push_fir((acc as i64 * (1 << 15) / self.gain) as i32)
+    }
+
+    fn push_fir(&mut self, value: i32) -> Option<i16> {
+        self.history[self.position] = value;
+        self.position = (self.position + 1) % FIR.len();
+        self.odd = !self.odd;
+        if self.odd {
+            return None;
+        }
+        // oldest value first, the filter being symmetric the order doesn't really matter
+        let (old, new) = self.history.split_at(self.position);
+        let acc: i64 = new
+            .iter()
+            .chain(old.iter())
+            .zip(FIR.iter())
+            .map(|(&x, &c)| x as i64 * c as i64)
+            .sum();
+        let sample = (acc >> 15).clamp(i16::MIN as i64, i16::MAX as i64);
+        Some(sample as i16)
+    }
+}
+
+/// Order of the noise shaping of a [`PdmModulator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModulatorOrder {
+    /// Single integrator. Simple and unconditionally stable, but leaves more noise in the audio
+    /// band, and idle tones with constant input.
+    First,
+    /// Two integrators, pushing the quantization noise further above the audio band.
+    Second,
+}
+
+/// Full scale of the modulator feedback, in the Q15 format of input samples.
+const FULL_SCALE: i32 = 1 << 15;
+
+/// Sigma-delta modulator converting 16 bit PCM samples into a PDM bitstream.
+///
+/// Samples are linearly interpolated to the bit rate, so the output is delayed by one sample.
+/// Integrators are clamped, allowing the modulator to recover after an overload. Full scale
+/// samples still overload the second order modulator, so input should stay a few dB below it.
+#[derive(Debug, Clone)]
+pub struct PdmModulator {
+    order: ModulatorOrder,
+    oversampling: u32,
+    previous: i32,
+    integrators: [i32; 2],
+    output: i32,
+}
+
+impl PdmModulator {
+    /// Create a modulator producing `oversampling` bits for each sample.
+    ///
+    /// # Panics
+    ///
+    /// `oversampling` must be a multiple of 16 between 16 and 256.
+    pub fn new(order: ModulatorOrder, oversampling: u32) -> Self {
+        assert!(
+            oversampling % 16 == 0 && (16..=256).contains(&oversampling),
+            "oversampling must be a multiple of 16 between 16 and 256"
+        );
+        Self {
+            order,
+            oversampling,
+            previous: 0,
+            integrators: [0; 2],
+            output: FULL_SCALE,
+        }
+    }
+
+    /// Get the modulator order.
+    pub fn order(&self) -> ModulatorOrder {
+        self.order
+    }
+
+    /// Get the oversampling ratio, that is the ratio between PDM clock and input rate.
+    pub fn oversampling(&self) -> u32 {
+        self.oversampling
+    }
+
+    /// Clear the modulator state.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.order, self.oversampling);
+    }
+
+    /// Modulate a sample, calling `write` with each of the `oversampling / 16` words produced.
+    ///
+    /// Bits are packed most significant bit first.
+    pub fn modulate(&mut self, sample: i16, mut write: impl FnMut(u16)) {
+        let sample = sample as i32;
+        let step = sample - self.previous;
+        let mut index = 0;
+        for _ in 0..self.oversampling / 16 {
+            let mut word = 0;
+            for _ in 0..16 {
+                index += 1;
+                let x = self.previous + step * index / self.oversampling as i32;
+                word = word << 1 | self.push(x) as u16;
+            }
+            write(word);
+        }
+        self.previous = sample;
+    }
+
+    fn push(&mut self, x: i32) -> bool {
+        let [i1, i2] = &mut self.integrators;
+        let y = self.output;
+        *i1 = (*i1 + x - y).clamp(-4 * FULL_SCALE, 4 * FULL_SCALE);
+        let quantized = match self.order {
+            ModulatorOrder::First => *i1,
+            ModulatorOrder::Second => {
+                *i2 = (*i2 + *i1 - 2 * y).clamp(-16 * FULL_SCALE, 16 * FULL_SCALE);
+                *i2
+            }
+        };
+        let bit = quantized >= 0;
+        self.output = if bit { FULL_SCALE } else { -FULL_SCALE };
+        bit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    /// Encode a signal, given as a function of the bit index, into PDM words with a first order
+    /// sigma-delta modulator.
+    fn modulate(words: usize, signal: impl Fn(usize) -> f64) -> Vec<u16> {
+        let mut integrator = 0.0;
+        let mut output = 1.0;
+        let mut pdm = Vec::new();
+        for i in 0..words {
+            let mut word = 0;
+            for j in 0..16 {
+                integrator += signal(i * 16 + j) - output;
+                output = if integrator >= 0.0 { 1.0 } else { -1.0 };
+                word = word << 1 | (integrator >= 0.0) as u16;
+            }
+            pdm.push(word);
+        }
+        pdm
+    }
+
+    fn filter(decimation: u32, pdm: &[u16]) -> Vec<i16> {
+        let mut filter = PdmFilter::new(decimation);
+        pdm.iter().filter_map(|&word| filter.push(word)).collect()
+    }
+
+    /// Amplitude of the `freq` component of a signal, `freq` being relative to its rate.
+    fn amplitude(samples: &[i16], freq: f64) -> f64 {
+        let (mut re, mut im) = (0.0, 0.0);
+        for (i, &x) in samples.iter().enumerate() {
+            let phase = 2.0 * core::f64::consts::PI * freq * i as f64;
+            re += x as f64 * phase.cos();
+            im += x as f64 * phase.sin();
+        }
+        2.0 * (re * re + im * im).sqrt() / samples.len() as f64
+    }
+
+    #[test]
+    fn test_constant() {
+        // step response of the FIR filter rings first
+        assert!(filter(64, &[0xFFFF; 1024])[32..].iter().all(|&x| x >= 32_700));
+        assert!(filter(64, &[0; 1024])[32..].iter().all(|&x| x <= -32_700));
+        for &level in [-0.5, 0.0, 0.25, 0.75].iter() {
+            let samples = filter(64, &modulate(4096, |_| level));
+            assert_eq!(samples.len(), 1024);
+            for &x in &samples[64..] {
+                assert!((x as f64 / 32768.0 - level).abs() < 0.01, "{} {}", level, x);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sine() {
+        // tones below the output Nyquist frequency, 16 kHz output
+        for &(decimation, freq) in [(64, 1_000.0), (32, 1_000.0), (128, 500.0)].iter() {
+            let pdm_clock = pdm_clock(16_000, decimation) as f64;
+            let pdm = modulate(decimation as usize * 400 / 16, |i| {
+                0.5 * (2.0 * core::f64::consts::PI * freq * i as f64 / pdm_clock).sin()
+            });
+            let samples = filter(decimation, &pdm);
+            assert_eq!(samples.len(), 400);
+            // 20 periods after settling
+            let a = amplitude(&samples[80..400], freq / 16_000.0);
+            assert!((a / 16384.0 - 1.0).abs() < 0.02, "{} {}", decimation, a);
+        }
+        // above the output Nyquist frequency
+        let pdm = modulate(64 * 400 / 16, |i| {
+            0.5 * (2.0 * core::f64::consts::PI * 12_000.0 * i as f64 / 1_024_000.0).sin()
+        });
+        let samples = filter(64, &pdm);
+        assert!(samples[80..].iter().all(|&x| x.abs() < 200));
+    }
+
+    #[test]
+    fn test_config() {
+        let config = driver_config(pdm_clock(16_000, 64));
+        assert_eq!(config.get_data_format(), DataFormat::Data16Channel16);
+        // 86 MHz / (32 * 32 kHz) = 84
+        assert_eq!(config.get_prescaler(86_000_000), Ok((false, 42)));
+    }
+
+    /// Fit a sine of frequency `freq`, relative to the signal rate, to a signal spanning whole
+    /// periods. Return its amplitude and the RMS value of the residual.
+    fn fit(samples: &[i16], freq: f64) -> (f64, f64) {
+        let phase = |i: usize| 2.0 * core::f64::consts::PI * freq * i as f64;
+        let (mut re, mut im) = (0.0, 0.0);
+        for (i, &x) in samples.iter().enumerate() {
+            re += x as f64 * phase(i).cos();
+            im += x as f64 * phase(i).sin();
+        }
+        let (re, im) = (
+            2.0 * re / samples.len() as f64,
+            2.0 * im / samples.len() as f64,
+        );
+        let residual: f64 = samples
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| x as f64 - re * phase(i).cos() - im * phase(i).sin())
+            .map(|e| e * e)
+            .sum();
+        let rms = (residual / samples.len() as f64).sqrt();
+        ((re * re + im * im).sqrt(), rms)
+    }
+
+    fn sine(amplitude: f64, freq: f64, rate: f64) -> impl Fn(usize) -> f64 {
+        move |i| amplitude * (2.0 * core::f64::consts::PI * freq * i as f64 / rate).sin()
+    }
+
+    fn modulate_pcm(order: ModulatorOrder, oversampling: u32, samples: &[i16]) -> Vec<u16> {
+        let mut modulator = PdmModulator::new(order, oversampling);
+        let mut pdm = Vec::new();
+        for &sample in samples {
+            modulator.modulate(sample, |word| pdm.push(word));
+        }
+        pdm
+    }
+
+    #[test]
+    fn test_modulator_constant() {
+        for &order in [ModulatorOrder::First, ModulatorOrder::Second].iter() {
+            for &level in [-16_384, 0, 8_192, 24_576].iter() {
+                let pdm = modulate_pcm(order, 32, &[level; 512]);
+                assert_eq!(pdm.len(), 1024);
+                let ones: u32 = pdm[32..].iter().map(|w| w.count_ones()).sum();
+                let density = 2.0 * ones as f64 / (992.0 * 16.0) - 1.0;
+                let expected = level as f64 / 32768.0;
+                assert!((density - expected).abs() < 0.002, "{:?} {}", order, level);
+            }
+        }
+    }
+
+    #[test]
+    fn test_modulator_sine() {
+        // 1 kHz tone at 16 kHz, filtered back by the decimation filter
+        let input: Vec<i16> = (0..400)
+            .map(|i| (sine(16_384.0, 1_000.0, 16_000.0)(i)) as i16)
+            .collect();
+        let mut residuals = [0.0; 2];
+        for (&order, residual) in [ModulatorOrder::First, ModulatorOrder::Second]
+            .iter()
+            .zip(residuals.iter_mut())
+        {
+            let samples = filter(64, &modulate_pcm(order, 64, &input));
+            assert_eq!(samples.len(), 400);
+            let (a, rms) = fit(&samples[80..400], 1_000.0 / 16_000.0);
+            assert!((a / 16384.0 - 1.0).abs() < 0.03, "{:?} {}", order, a);
+            // at least 50 dB SNR
+            assert!(rms < 16384.0 / 316.0, "{:?} {}", order, rms);
+            *residual = rms;
+        }
+        assert!(residuals[1] < residuals[0]);
+    }
+
+    #[test]
+    fn test_modulator_overload() {
+        // full scale square wave, then silence
+        let mut input = [0; 256];
+        for (i, x) in input[..128].iter_mut().enumerate() {
+            *x = if i % 16 < 8 { i16::MAX } else { i16::MIN };
+        }
+        let samples = filter(64, &modulate_pcm(ModulatorOrder::Second, 64, &input));
+        assert!(samples[192..].iter().all(|&x| x.abs() < 200));
+    }
+
+    #[test]
+    fn test_output_config() {
+        let config = output_driver_config(pdm_clock(16_000, 64));
+        assert_eq!(
+            config.get_direction(),
+            crate::driver::TransmitOrReceive::Transmit
+        );
+        assert_eq!(config.get_prescaler(86_000_000), Ok((false, 42)));
+    }
+}
@@ -0,0 +1,244 @@
+//! Sample types and conversions.
+//!
+//! Besides plain integers, `I2sTransfer` accepts normalized `f32` samples, in the `[-1.0, 1.0)`
+//! range, and [`Q15`]/[`Q31`] fixed point samples, as tuples, `[T; 2]` arrays, or single samples
+//! for PCM standards. They can be used with any data format: samples are scaled to the channel
+//! width, and narrowing conversions round to nearest and saturate.
+//!
+//! ```ignore
+//! // 16 bit data from Q31 processing
+//! let mut transfer = I2sTransferConfig::new_master()
+//!     .data_format(Data16Channel16)
+//!     .i2s_transfer(i2s_peripheral);
+//! block!(transfer.write((Q31(left), Q31(right)))).ok();
+//! // or with dithering
+//! let mut dither = TpdfDither::new(1);
+//! block!(transfer.write(dither.reduce_frame((left, right)))).ok();
+//! ```
+
+/// A Q15 fixed point sample, `0x8000` being -1.0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Q15(pub i16);
+
+/// A Q31 fixed point sample, `0x8000_0000` being -1.0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Q31(pub i32);
+
+impl From<Q15> for Q31 {
+    fn from(sample: Q15) -> Self {
+        Q31((sample.0 as i32) << 16)
+    }
+}
+
+impl From<Q31> for Q15 {
+    /// Round to nearest and saturate.
+    fn from(sample: Q31) -> Self {
+        Q15(q31_to_q15(sample.0))
+    }
+}
+
+/// Saturating conversion of a 32 bit integer to 16 bits, for samples already at 16 bit scale
+/// but computed with some headroom.
+pub fn saturate_i16(sample: i32) -> i16 {
+    sample.clamp(i16::MIN as i32, i16::MAX as i32) as i16
+}
+
+/// Convert a Q31 sample to Q15, rounding to nearest and saturating.
+pub fn q31_to_q15(sample: i32) -> i16 {
+    saturate_i16(((sample as i64 + 0x8000) >> 16) as i32)
+}
+
+/// Convert a normalized `f32` sample to Q15, rounding to nearest and saturating.
+///
+/// NaN gives 0.
+pub fn f32_to_q15(sample: f32) -> i16 {
+    round(sample * 32768.0) as i16
+}
+
+/// Convert a normalized `f32` sample to Q31, rounding to nearest and saturating.
+///
+/// NaN gives 0.
+pub fn f32_to_q31(sample: f32) -> i32 {
+    round(sample * 2_147_483_648.0) as i32
+}
+
+// round half away from zero, float to integer casts then truncate and saturate
+fn round(x: f32) -> f32 {
+    if x >= 0.0 {
+        x + 0.5
+    } else {
+        x - 0.5
+    }
+}
+
+/// Triangular probability density function dither, for reducing Q31 or `f32` samples to 16
+/// bits.
+///
+/// The noise added before rounding spans ±1 LSB of the 16 bit result, which decorrelates the
+/// quantization error from the signal. The noise comes from a xorshift generator, so the
+/// sequence only depends on the seed.
+#[derive(Debug, Clone)]
+pub struct TpdfDither {
+    state: u32,
+}
+
+impl TpdfDither {
+    /// Create a dither generator. A zero seed is replaced by a non-zero one.
+    pub fn new(seed: u32) -> Self {
+        Self {
+            state: if seed == 0 { 0x9E37_79B9 } else { seed },
+        }
+    }
+
+    // uniform noise in [0, 1) LSB of 16 bit samples, at Q31 scale
+    fn uniform(&mut self) -> i32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        (x >> 16) as i32
+    }
+
+    /// Reduce a Q31 sample to Q15.
+    pub fn reduce(&mut self, sample: i32) -> i16 {
+        let noise = self.uniform() - self.uniform();
+        q31_to_q15(sample.saturating_add(noise))
+    }
+
+    /// Reduce a normalized `f32` sample to Q15.
+    pub fn reduce_f32(&mut self, sample: f32) -> i16 {
+        self.reduce(f32_to_q31(sample))
+    }
+
+    /// Reduce a frame of Q31 samples to Q15.
+    pub fn reduce_frame(&mut self, frame: (i32, i32)) -> (i16, i16) {
+        (self.reduce(frame.0), self.reduce(frame.1))
+    }
+}
+
+/// Conversion of a sample from and to the content of a 16 or 32 bit channel.
+pub(crate) trait Sample: Sized {
+    fn to_word16(&self) -> u16;
+    fn from_word16(word: u16) -> Self;
+    fn to_word32(&self) -> u32;
+    fn from_word32(word: u32) -> Self;
+}
+
+impl Sample for f32 {
+    fn to_word16(&self) -> u16 {
+        f32_to_q15(*self) as u16
+    }
+    fn from_word16(word: u16) -> Self {
+        word as i16 as f32 / 32768.0
+    }
+    fn to_word32(&self) -> u32 {
+        f32_to_q31(*self) as u32
+    }
+    fn from_word32(word: u32) -> Self {
+        word as i32 as f32 / 2_147_483_648.0
+    }
+}
+
+impl Sample for Q15 {
+    fn to_word16(&self) -> u16 {
+        self.0 as u16
+    }
+    fn from_word16(word: u16) -> Self {
+        Q15(word as i16)
+    }
+    fn to_word32(&self) -> u32 {
+        Q31::from(*self).0 as u32
+    }
+    fn from_word32(word: u32) -> Self {
+        Q31(word as i32).into()
+    }
+}
+
+impl Sample for Q31 {
+    fn to_word16(&self) -> u16 {
+        Q15::from(*self).0 as u16
+    }
+    fn from_word16(word: u16) -> Self {
+        Q15(word as i16).into()
+    }
+    fn to_word32(&self) -> u32 {
+        self.0 as u32
+    }
+    fn from_word32(word: u32) -> Self {
+        Q31(word as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marker::*;
+    use crate::transfer::{FromRawFrame, ToRawFrame};
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(saturate_i16(40_000), i16::MAX);
+        assert_eq!(saturate_i16(-40_000), i16::MIN);
+        assert_eq!(saturate_i16(-1234), -1234);
+        assert_eq!(q31_to_q15(0x1234_7FFF), 0x1234);
+        assert_eq!(q31_to_q15(0x1234_8000), 0x1235);
+        assert_eq!(q31_to_q15(-0x1234_8001), -0x1235);
+        assert_eq!(q31_to_q15(i32::MAX), i16::MAX);
+        assert_eq!(q31_to_q15(i32::MIN), i16::MIN);
+        assert_eq!(f32_to_q15(0.5), 0x4000);
+        assert_eq!(f32_to_q15(-1.0), i16::MIN);
+        assert_eq!(f32_to_q15(1.0), i16::MAX);
+        assert_eq!(f32_to_q15(-3.0), i16::MIN);
+        assert_eq!(f32_to_q15(f32::NAN), 0);
+        assert_eq!(f32_to_q15(1.4 / 32768.0), 1);
+        assert_eq!(f32_to_q15(-1.6 / 32768.0), -2);
+        assert_eq!(f32_to_q31(-0.25), -0x2000_0000);
+        assert_eq!(f32_to_q31(1.0), i32::MAX);
+        assert_eq!(Q31::from(Q15(-2)), Q31(-0x2_0000));
+    }
+
+    #[test]
+    fn test_frames() {
+        let raw = <(f32, f32) as ToRawFrame<Msb, Data16Channel32>>::to_raw(&(0.5, -2.0));
+        assert_eq!(raw, [0x4000, 0x8000]);
+        let raw = <[Q31; 2] as ToRawFrame<Philips, Data16Channel16>>::to_raw(&[
+            Q31(0x7FFF_FFFF),
+            Q31(0x10_0000),
+        ]);
+        assert_eq!(raw, [0x7FFF, 0x10]);
+        let frame = <[i16; 2] as FromRawFrame<Lsb, Data16Channel16>>::from_raw([1, 0xFFFF]);
+        assert_eq!(frame, [1, -1]);
+        let raw = <[f32; 2] as ToRawFrame<Philips, Data32Channel32>>::to_raw(&[-0.5, 0.25]);
+        assert_eq!(raw, [0xC000, 0, 0x2000, 0]);
+        let frame = <(Q15, Q15) as FromRawFrame<Philips, Data32Channel32>>::from_raw([
+            0x1234, 0x8000, 0x7FFF, 0xC000,
+        ]);
+        assert_eq!(frame, (Q15(0x1235), Q15(0x7FFF)));
+        let raw = <Q15 as ToRawFrame<PcmShortSync, Data32Channel32>>::to_raw(&Q15(-1));
+        assert_eq!(raw, [0xFFFF, 0]);
+        let sample = <f32 as FromRawFrame<PcmLongSync, Data16Channel16>>::from_raw([0xC000]);
+        assert_eq!(sample, -0.5);
+    }
+
+    #[test]
+    fn test_dither() {
+        let mut dither = TpdfDither::new(0);
+        let (mut sum, mut min, mut max) = (0i64, 0, 0);
+        // 0.25 LSB above 0x1234
+        let sample = 0x1234_4000;
+        for _ in 0..10_000 {
+            let error = dither.reduce(sample) as i32 - 0x1234;
+            min = error.min(min);
+            max = error.max(max);
+            sum += error as i64;
+        }
+        // noise spans ±1 LSB, and the mean is kept
+        assert_eq!((min, max), (-1, 1));
+        assert!((sum as f64 / 10_000.0 - 0.25).abs() < 0.02);
+        // saturation
+        assert_eq!(dither.reduce(i32::MAX), i16::MAX);
+        assert!(dither.reduce(i32::MIN) <= i16::MIN + 1);
+        assert!((dither.reduce_f32(-0.5) + 0x4000).abs() <= 1);
+    }
+}
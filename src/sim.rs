@@ -0,0 +1,868 @@
+//! Simulated I2S peripherals connected through a virtual bus.
+//!
+//! Simulated peripherals implement [`I2sPeripheral`] and [`DualI2sPeripheral`], so drivers and
+//! transfers can run on a host. Their registers are emulated by a [`VirtualBus`], installed as
+//! the [`trace`] backend of the current thread. The bus carries CK, WS and SD lines
+//! shared by all peripherals created on it: the enabled master generates clocks, transmitters
+//! drive SD and receivers sample it. This allows end-to-end tests of a master and a slave, or of
+//! both parts of a dual peripheral.
+//!
+//! The simulation is bit accurate but time is virtual: the bus is clocked by accesses to
+//! simulated peripherals, including WS pin reads, so it stops when nothing is polled. Faults can
+//! be injected with [`BusConfig`].
+//!
+//! ```
+//! # use stm32_i2s_v12x::sim::*;
+//! # use stm32_i2s_v12x::transfer::*;
+//! let bus = VirtualBus::new(BusConfig::new().start_offset(5));
+//! bus.install();
+//! let mut transmitter = I2sTransferConfig::new_master().i2s_transfer(SimI2sA::new(&bus));
+//! let mut receiver = I2sTransferConfig::new_slave()
+//!     .receive()
+//!     .i2s_transfer(SimI2sB::new(&bus));
+//! let mut received = Vec::new();
+//! let mut next = 0;
+//! while received.len() < 4 {
+//!     if transmitter.write((next, -next)).is_ok() {
+//!         next += 1;
+//!     }
+//!     if let Ok(frame) = receiver.read::<(i16, i16)>() {
+//!         received.push(frame);
+//!     }
+//! }
+//! // the receiver synchronizes on WS, so first frames may be lost
+//! assert_eq!(received[1].0, received[0].0 + 1);
+//! ```
+//!
+//! This module requires `std`. Register blocks of simulated peripherals are statics that are
+//! never accessed, registers being fully emulated by the bus of the current thread, so each
+//! thread can run its own simulation.
+use core::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::vec::Vec;
+
+use crate::audio::{AudioSink, AudioSource};
+use crate::driver::{decode_registers, I2sStandard, SlaveOrMaster, TransmitOrReceive};
+use crate::trace::{self, Backend, Register};
+use crate::transfer::{
+    marker, FrameFormat, FromRawFrame, I2sTransfer, I2sTransferConfig, I2sTransferError, Raw,
+    ToRawFrame,
+};
+use crate::waveform::Waveform;
+use crate::{DualI2sPeripheral, I2sPeripheral, WsPin};
+
+/// I2S clock source frequency of simulated peripherals.
+pub const SIM_I2S_FREQ: u32 = 86_000_000;
+
+const RXNE: u32 = 1 << 0;
+const TXE: u32 = 1 << 1;
+const CHSIDE: u32 = 1 << 2;
+const UDR: u32 = 1 << 3;
+const OVR: u32 = 1 << 6;
+const FRE: u32 = 1 << 8;
+const I2SE: u32 = 1 << 10;
+
+/// [`VirtualBus`] configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusConfig {
+    clock_ratio: u32,
+    start_offset: u32,
+    bit_errors: Option<u32>,
+    ws_glitches: Option<u32>,
+}
+
+impl BusConfig {
+    /// Create a default configuration: one bit every 4 accesses, no fault.
+    pub fn new() -> Self {
+        Self {
+            clock_ratio: 4,
+            start_offset: 0,
+            bit_errors: None,
+            ws_glitches: None,
+        }
+    }
+
+    /// Set the number of peripheral accesses per bit. A low ratio simulates a slow CPU, causing
+    /// overruns and underruns.
+    ///
+    /// # Panics
+    ///
+    /// `accesses` must not be 0, otherwise the method panics.
+    pub fn clock_ratio(mut self, accesses: u32) -> Self {
+        assert!(accesses > 0, "clock ratio must not be 0");
+        self.clock_ratio = accesses;
+        self
+    }
+
+    /// Set the position, in bits, of a master clock generator in its frame when it's created, as
+    /// if it was already running before. Resetting the peripheral resets this position to 0.
+    pub fn start_offset(mut self, bits: u32) -> Self {
+        self.start_offset = bits;
+        self
+    }
+
+    /// Invert the SD line every `bits` clocked bits.
+    ///
+    /// # Panics
+    ///
+    /// `bits` must not be 0, otherwise the method panics.
+    pub fn bit_errors(mut self, bits: u32) -> Self {
+        assert!(bits > 0, "bit error period must not be 0");
+        self.bit_errors = Some(bits);
+        self
+    }
+
+    /// Invert the WS line for one bit every `bits` clocked bits.
+    ///
+    /// # Panics
+    ///
+    /// `bits` must not be 0, otherwise the method panics.
+    pub fn ws_glitches(mut self, bits: u32) -> Self {
+        assert!(bits > 0, "WS glitch period must not be 0");
+        self.ws_glitches = Some(bits);
+        self
+    }
+}
+
+impl Default for BusConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Errors flagged by simulated peripherals since the bus creation.
+///
+/// Each lost or missing word is counted, as well as each bit with an unexpected WS level in slave
+/// mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ErrorCounts {
+    /// Words received while the Rx buffer was full, causing the OVR flag.
+    pub overruns: u32,
+    /// Words sent while the Tx buffer was empty. In slave mode, this causes the UDR flag.
+    pub underruns: u32,
+    /// Bits with an unexpected WS level in slave mode, causing the FRE flag.
+    pub frame_errors: u32,
+}
+
+impl ErrorCounts {
+    /// Total number of errors.
+    pub fn total(&self) -> u32 {
+        self.overruns + self.underruns + self.frame_errors
+    }
+}
+
+/// State of a simulated peripheral.
+#[derive(Debug, Clone)]
+struct Peripheral {
+    /// Address of the register block.
+    base: usize,
+    cr2: u32,
+    i2scfgr: u32,
+    i2spr: u32,
+    sr: u32,
+    tx_buffer: u16,
+    rx_buffer: u16,
+    /// Word being transmitted or received.
+    shift: u16,
+    /// Bit counter of the clock generator, used in master mode.
+    clock: u32,
+    /// Position in the frame, `None` until a slave is synchronized.
+    position: Option<u32>,
+    /// A slave saw WS at the non-start level since being enabled.
+    armed: bool,
+    /// The first bit of the word being received was sampled.
+    receiving: bool,
+    /// DR was read while OVR is set, so reading SR clears OVR.
+    ovr_read: bool,
+    errors: ErrorCounts,
+}
+
+impl Peripheral {
+    fn new(base: usize, clock: u32) -> Self {
+        Self {
+            base,
+            cr2: 0,
+            i2scfgr: 0,
+            i2spr: 2,
+            sr: TXE,
+            tx_buffer: 0,
+            rx_buffer: 0,
+            shift: 0,
+            clock,
+            position: None,
+            armed: false,
+            receiving: false,
+            ovr_read: false,
+            errors: ErrorCounts::default(),
+        }
+    }
+
+    /// Role, direction and waveform of an enabled peripheral with a valid configuration.
+    fn config(&self) -> Option<(SlaveOrMaster, TransmitOrReceive, Waveform)> {
+        let config = decode_registers(self.i2scfgr, self.i2spr);
+        if !config.enabled || !config.i2s_mode {
+            return None;
+        }
+        let waveform = Waveform::new(config.standard, config.data_format?, config.clock_polarity);
+        Some((config.role, config.direction, waveform))
+    }
+
+    fn set_i2scfgr(&mut self, value: u32) {
+        if value & I2SE == 0 {
+            self.position = None;
+            self.armed = false;
+            self.receiving = false;
+        }
+        self.i2scfgr = value;
+    }
+
+    fn set_chside(&mut self, right: bool) {
+        match right {
+            true => self.sr |= CHSIDE,
+            false => self.sr &= !CHSIDE,
+        }
+    }
+
+    /// Output the data bit at `position`, loading a word from the Tx buffer if needed.
+    fn transmit(&mut self, waveform: &Waveform, position: u32, slave: bool) -> bool {
+        let (right, bit) = data_bit(waveform, position);
+        let bit = match bit {
+            Some(bit) => bit,
+            None => return false,
+        };
+        if bit % 16 == 0 {
+            if self.sr & TXE == 0 {
+                self.shift = self.tx_buffer;
+                self.sr |= TXE;
+            } else {
+                self.shift = 0;
+                self.errors.underruns += 1;
+                if slave {
+                    self.sr |= UDR;
+                }
+            }
+            self.set_chside(right);
+        }
+        self.shift >> (15 - bit % 16) & 1 != 0
+    }
+
+    /// Sample the data bit at `position`, moving complete words to the Rx buffer.
+    fn receive(&mut self, waveform: &Waveform, position: u32, sd: bool) {
+        let (right, bit) = data_bit(waveform, position);
+        let bit = match bit {
+            Some(bit) => bit,
+            None => return,
+        };
+        if bit % 16 == 0 {
+            self.shift = 0;
+            self.receiving = true;
+        }
+        self.shift |= (sd as u16) << (15 - bit % 16);
+        let last = bit % 16 == 15 || bit + 1 == waveform.data_length();
+        if last && core::mem::replace(&mut self.receiving, false) {
+            if self.sr & (RXNE | OVR) != 0 {
+                self.sr |= OVR;
+                self.errors.overruns += 1;
+            } else {
+                self.rx_buffer = self.shift;
+                self.sr |= RXNE;
+                self.set_chside(right);
+            }
+        }
+    }
+}
+
+/// Channel and index of the data bit at `position` in a frame, the index being `None` outside
+/// data bits.
+fn data_bit(waveform: &Waveform, position: u32) -> (bool, Option<u32>) {
+    let bits_per_frame = waveform.bits_per_frame();
+    let channel_length = waveform.channel_length();
+    let data_length = waveform.data_length();
+    let slot = (position + bits_per_frame - waveform.delay()) % bits_per_frame;
+    let padding = match waveform.standard() {
+        I2sStandard::Lsb => channel_length - data_length,
+        _ => 0,
+    };
+    let bit = (slot % channel_length)
+        .checked_sub(padding)
+        .filter(|&bit| bit < data_length);
+    (slot >= channel_length, bit)
+}
+
+#[derive(Debug)]
+struct Bus {
+    config: BusConfig,
+    peripherals: Vec<Peripheral>,
+    accesses: u32,
+    bits: u64,
+    ws: bool,
+    sd: bool,
+    /// Faults injected on the next bit.
+    ws_glitch: bool,
+    bit_error: bool,
+}
+
+impl Bus {
+    fn peripheral(&mut self, base: usize) -> &mut Peripheral {
+        let index = match self.peripherals.iter().position(|p| p.base == base) {
+            Some(index) => index,
+            None => {
+                let peripheral = Peripheral::new(base, self.config.start_offset);
+                self.peripherals.push(peripheral);
+                self.peripherals.len() - 1
+            }
+        };
+        &mut self.peripherals[index]
+    }
+
+    fn reset(&mut self, base: usize) {
+        let peripheral = self.peripheral(base);
+        let errors = peripheral.errors;
+        *peripheral = Peripheral::new(base, 0);
+        peripheral.errors = errors;
+    }
+
+    /// Count an access, and clock a bit when needed.
+    fn tick(&mut self) {
+        self.accesses += 1;
+        if self.accesses >= self.config.clock_ratio {
+            self.accesses = 0;
+            self.step();
+        }
+    }
+
+    /// Clock one bit, if a master is enabled.
+    fn step(&mut self) {
+        let master = self.peripherals.iter_mut().find_map(|p| match p.config() {
+            Some((SlaveOrMaster::Master, _, waveform)) => Some((p, waveform)),
+            _ => None,
+        });
+        let (master, waveform) = match master {
+            Some(master) => master,
+            None => return,
+        };
+        let index = master.clock % waveform.bits_per_frame();
+        master.clock = index + 1;
+        let base = master.base;
+        self.bits += 1;
+        let bits = self.bits;
+        let fault = |every: Option<u32>| every.is_some_and(|every| bits % every as u64 == 0);
+        let ws_glitch = fault(self.config.ws_glitches) || core::mem::take(&mut self.ws_glitch);
+        let bit_error = fault(self.config.bit_errors) || core::mem::take(&mut self.bit_error);
+        let ws = waveform.ws(index) != ws_glitch;
+
+        let mut active = Vec::new();
+        for peripheral in self.peripherals.iter_mut() {
+            let (role, direction, waveform) = match peripheral.config() {
+                Some(config) => config,
+                None => continue,
+            };
+            let position = match role {
+                SlaveOrMaster::Master if peripheral.base == base => index,
+                SlaveOrMaster::Master => continue,
+                SlaveOrMaster::Slave => match peripheral.position {
+                    Some(position) => {
+                        if waveform.ws(position) != ws {
+                            peripheral.sr |= FRE;
+                            peripheral.errors.frame_errors += 1;
+                        }
+                        position
+                    }
+                    None if peripheral.armed && ws == waveform.start_level() => 0,
+                    None => {
+                        peripheral.armed |= ws != waveform.start_level();
+                        continue;
+                    }
+                },
+            };
+            let slave = role == SlaveOrMaster::Slave;
+            if slave {
+                peripheral.position = Some((position + 1) % waveform.bits_per_frame());
+            }
+            active.push((peripheral.base, direction, waveform, position, slave));
+        }
+
+        let mut sd = false;
+        for &(base, direction, waveform, position, slave) in active.iter() {
+            if direction == TransmitOrReceive::Transmit {
+                sd |= self.peripheral(base).transmit(&waveform, position, slave);
+            }
+        }
+        sd ^= bit_error;
+        for &(base, direction, waveform, position, _) in active.iter() {
+            if direction == TransmitOrReceive::Receive {
+                self.peripheral(base).receive(&waveform, position, sd);
+            }
+        }
+        self.ws = ws;
+        self.sd = sd;
+    }
+}
+
+/// A virtual I2S bus emulating the registers of simulated peripherals.
+///
+/// This is a shared handle, clones refer to the same bus.
+#[derive(Debug, Clone)]
+pub struct VirtualBus(Rc<RefCell<Bus>>);
+
+impl VirtualBus {
+    /// Create a bus without any peripheral.
+    pub fn new(config: BusConfig) -> Self {
+        Self(Rc::new(RefCell::new(Bus {
+            config,
+            peripherals: Vec::new(),
+            accesses: 0,
+            bits: 0,
+            ws: false,
+            sd: false,
+            ws_glitch: false,
+            bit_error: false,
+        })))
+    }
+
+    /// Install the bus as the register access backend of the current thread, replacing any
+    /// previous backend. It can be removed with `trace::set_backend(None)`.
+    pub fn install(&self) {
+        trace::set_backend(Some(std::boxed::Box::new(self.clone())));
+    }
+
+    /// Number of bits clocked since the bus creation.
+    pub fn bits(&self) -> u64 {
+        self.0.borrow().bits
+    }
+
+    /// Level of the WS line during the last clocked bit.
+    pub fn ws(&self) -> bool {
+        self.0.borrow().ws
+    }
+
+    /// Level of the SD line during the last clocked bit.
+    pub fn sd(&self) -> bool {
+        self.0.borrow().sd
+    }
+
+    /// Errors flagged by all peripherals of the bus.
+    pub fn errors(&self) -> ErrorCounts {
+        let bus = self.0.borrow();
+        let mut errors = ErrorCounts::default();
+        for peripheral in bus.peripherals.iter() {
+            errors.overruns += peripheral.errors.overruns;
+            errors.underruns += peripheral.errors.underruns;
+            errors.frame_errors += peripheral.errors.frame_errors;
+        }
+        errors
+    }
+
+    /// Invert the WS line during the next clocked bit.
+    pub fn glitch_ws(&self) {
+        self.0.borrow_mut().ws_glitch = true;
+    }
+
+    /// Invert the SD line during the next clocked bit.
+    pub fn flip_sd(&self) {
+        self.0.borrow_mut().bit_error = true;
+    }
+
+    fn reset(&self, base: *const ()) {
+        self.0.borrow_mut().reset(base as usize);
+    }
+}
+
+impl Backend for VirtualBus {
+    fn emulates(&self, _address: usize) -> bool {
+        true
+    }
+
+    fn read(&mut self, address: usize, register: Register, value: u32) -> u32 {
+        let mut bus = self.0.borrow_mut();
+        bus.tick();
+        let peripheral = bus.peripheral(address - register.offset());
+        match register {
+            Register::Cr2 => peripheral.cr2,
+            Register::I2scfgr => peripheral.i2scfgr,
+            Register::I2spr => peripheral.i2spr,
+            Register::Sr => {
+                let sr = peripheral.sr;
+                peripheral.sr &= !(UDR | FRE);
+                if peripheral.ovr_read {
+                    peripheral.sr &= !OVR;
+                    peripheral.ovr_read = false;
+                }
+                sr
+            }
+            Register::Dr => {
+                peripheral.sr &= !RXNE;
+                peripheral.ovr_read = peripheral.sr & OVR != 0;
+                peripheral.rx_buffer as u32
+            }
+            _ => value,
+        }
+    }
+
+    fn write(&mut self, address: usize, register: Register, value: u32) -> u32 {
+        let mut bus = self.0.borrow_mut();
+        bus.tick();
+        let peripheral = bus.peripheral(address - register.offset());
+        match register {
+            Register::Cr2 => peripheral.cr2 = value,
+            Register::I2scfgr => peripheral.set_i2scfgr(value),
+            Register::I2spr => peripheral.i2spr = value,
+            Register::Dr => {
+                peripheral.tx_buffer = value as u16;
+                peripheral.sr &= !TXE;
+            }
+            _ => {}
+        }
+        value
+    }
+}
+
+/// WS pin of a simulated peripheral, reading the WS line of the bus.
+#[derive(Debug, Clone)]
+pub struct SimWsPin {
+    bus: VirtualBus,
+}
+
+impl WsPin for SimWsPin {
+    fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+    fn is_high(&self) -> bool {
+        let mut bus = self.bus.0.borrow_mut();
+        bus.tick();
+        bus.ws
+    }
+}
+
+macro_rules! sim_i2s_peripheral {
+    ($(#[$attr:meta])* $name:ident, $registers:ident) => {
+        static mut $registers: [u32; 9] = [0; 9];
+
+        $(#[$attr])*
+        #[derive(Debug)]
+        pub struct $name {
+            ws_pin: SimWsPin,
+        }
+
+        impl $name {
+            /// Create the peripheral on `bus`.
+            pub fn new(bus: &VirtualBus) -> Self {
+                Self {
+                    ws_pin: SimWsPin { bus: bus.clone() },
+                }
+            }
+        }
+
+        unsafe impl I2sPeripheral for $name {
+            type WsPin = SimWsPin;
+            const REGISTERS: *const () = core::ptr::addr_of!($registers) as *const ();
+            fn i2s_freq(&self) -> u32 {
+                SIM_I2S_FREQ
+            }
+            fn ws_pin(&self) -> &Self::WsPin {
+                &self.ws_pin
+            }
+            fn ws_pin_mut(&mut self) -> &mut Self::WsPin {
+                &mut self.ws_pin
+            }
+            fn rcc_reset(&mut self) {
+                self.ws_pin.bus.reset(Self::REGISTERS);
+            }
+        }
+    };
+}
+
+sim_i2s_peripheral!(
+    /// A simulated SPI/I2S peripheral.
+    SimI2sA,
+    SIM_I2S_A_REGISTERS
+);
+sim_i2s_peripheral!(
+    /// Another simulated SPI/I2S peripheral.
+    SimI2sB,
+    SIM_I2S_B_REGISTERS
+);
+
+static mut SIM_DUAL_MAIN_REGISTERS: [u32; 9] = [0; 9];
+static mut SIM_DUAL_EXT_REGISTERS: [u32; 9] = [0; 9];
+
+/// A simulated SPI/I2S peripheral with its I2SEXT part.
+#[derive(Debug)]
+pub struct SimDualI2s {
+    ws_pin: SimWsPin,
+}
+
+impl SimDualI2s {
+    /// Create the peripheral on `bus`.
+    pub fn new(bus: &VirtualBus) -> Self {
+        Self {
+            ws_pin: SimWsPin { bus: bus.clone() },
+        }
+    }
+}
+
+unsafe impl DualI2sPeripheral for SimDualI2s {
+    type WsPin = SimWsPin;
+    const MAIN_REGISTERS: *const () = core::ptr::addr_of!(SIM_DUAL_MAIN_REGISTERS) as *const ();
+    const EXT_REGISTERS: *const () = core::ptr::addr_of!(SIM_DUAL_EXT_REGISTERS) as *const ();
+    fn i2s_freq(&self) -> u32 {
+        SIM_I2S_FREQ
+    }
+    fn ws_pin(&self) -> &Self::WsPin {
+        &self.ws_pin
+    }
+    fn ws_pin_mut(&mut self) -> &mut Self::WsPin {
+        &mut self.ws_pin
+    }
+    fn rcc_reset(&mut self) {
+        self.ws_pin.bus.reset(Self::MAIN_REGISTERS);
+        self.ws_pin.bus.reset(Self::EXT_REGISTERS);
+    }
+}
+
+/// A master transmitter and a slave receiver on a [`VirtualBus`], used as an [`AudioSink`] and
+/// an [`AudioSource`].
+///
+/// Frames written to the sink come back from the source. Each call clocks the bus and keeps
+/// received frames, so frames are not lost if reads are late, but the receiver synchronizes on
+/// the first frames like any slave receiver.
+pub struct SimLoopback<STD, FMT>
+where
+    (STD, FMT): FrameFormat,
+{
+    transmitter: I2sTransfer<SimI2sA, marker::Master, marker::Transmit, STD, FMT>,
+    receiver: I2sTransfer<SimI2sB, marker::Slave, marker::Receive, STD, FMT>,
+    received: VecDeque<Raw<STD, FMT>>,
+}
+
+impl<STD, FMT> SimLoopback<STD, FMT>
+where
+    STD: marker::I2sStandard + Copy,
+    FMT: marker::DataFormat + Copy,
+    (STD, FMT): FrameFormat,
+{
+    /// Create the loopback on `bus`, using [`SimI2sA`] and [`SimI2sB`].
+    pub fn new(bus: &VirtualBus, standard: STD, data_format: FMT) -> Self {
+        let config = I2sTransferConfig::new_master()
+            .standard(standard)
+            .data_format(data_format);
+        Self {
+            transmitter: config.i2s_transfer(SimI2sA::new(bus)),
+            receiver: config.to_slave().receive().i2s_transfer(SimI2sB::new(bus)),
+            received: VecDeque::new(),
+        }
+    }
+}
+
+impl<STD, FMT> SimLoopback<STD, FMT>
+where
+    STD: marker::I2sStandard,
+    (STD, FMT): FrameFormat,
+{
+    fn receive(&mut self) {
+        if let Ok(frame) = self.receiver.read() {
+            self.received.push_back(frame);
+        }
+    }
+}
+
+impl<STD, FMT, T> AudioSink<T> for SimLoopback<STD, FMT>
+where
+    STD: marker::I2sStandard,
+    (STD, FMT): FrameFormat,
+    T: ToRawFrame<STD, FMT>,
+{
+    type Error = core::convert::Infallible;
+
+    fn write_frame(&mut self, frame: T) -> nb::Result<(), Self::Error> {
+        let written = self.transmitter.write(frame);
+        self.receive();
+        written
+    }
+}
+
+impl<STD, FMT, T> AudioSource<T> for SimLoopback<STD, FMT>
+where
+    STD: marker::I2sStandard,
+    (STD, FMT): FrameFormat,
+    T: FromRawFrame<STD, FMT>,
+{
+    type Error = I2sTransferError;
+
+    fn read_frame(&mut self) -> nb::Result<T, Self::Error> {
+        if self.received.is_empty() {
+            self.receive();
+        }
+        match self.received.pop_front() {
+            Some(Raw(raw)) => Ok(T::from_raw(raw)),
+            None => Err(nb::Error::WouldBlock),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::{DataFormat, DualI2sDriverConfig};
+    use crate::transfer::*;
+
+    /// Poll until `bits` bits have been clocked on the bus.
+    fn run(bus: &VirtualBus, bits: u64, mut poll: impl FnMut()) {
+        while bus.bits() < bits {
+            poll();
+        }
+    }
+
+    /// Check that frames built as `(n, -n)` are consecutive, allowing `gaps` discontinuities.
+    fn check_frames(frames: &[(i32, i32)], gaps: usize) {
+        assert!(frames.len() > 10);
+        assert!(frames.iter().all(|&(left, right)| left == -right));
+        let discontinuities = frames.windows(2).filter(|w| w[1].0 != w[0].0 + 1).count();
+        assert!(discontinuities <= gaps, "{} > {}", discontinuities, gaps);
+    }
+
+    #[test]
+    fn test_transfer_loopback() {
+        let bus = VirtualBus::new(BusConfig::new().start_offset(5));
+        bus.install();
+        let mut transmitter = I2sTransferConfig::new_master()
+            .standard(Msb)
+            .data_format(Data32Channel32)
+            .i2s_transfer(SimI2sA::new(&bus));
+        let mut receiver = I2sTransferConfig::new_slave()
+            .receive()
+            .standard(Msb)
+            .data_format(Data32Channel32)
+            .i2s_transfer(SimI2sB::new(&bus));
+        let mut received = Vec::new();
+        let mut next = 0x10000;
+        run(&bus, 64 * 40, || {
+            if transmitter.write((next, -next)).is_ok() {
+                next += 1;
+            }
+            if let Ok(frame) = receiver.read::<(i32, i32)>() {
+                received.push(frame);
+            }
+        });
+        check_frames(&received, 0);
+
+        // PCM frames are mono, the master starts in the middle of a frame
+        let bus = VirtualBus::new(BusConfig::new().start_offset(7));
+        bus.install();
+        let mut transmitter = I2sTransferConfig::new_master()
+            .standard(PcmShortSync)
+            .i2s_transfer(SimI2sA::new(&bus));
+        let mut receiver = I2sTransferConfig::new_slave()
+            .receive()
+            .standard(PcmShortSync)
+            .i2s_transfer(SimI2sB::new(&bus));
+        let mut received = Vec::new();
+        let mut next = 0;
+        run(&bus, 16 * 40, || {
+            if transmitter.write(next).is_ok() {
+                next += 1;
+            }
+            if let Ok(frame) = receiver.read::<i16>() {
+                received.push((frame as i32, -(frame as i32)));
+            }
+        });
+        check_frames(&received, 0);
+    }
+
+    #[test]
+    fn test_dual_loopback() {
+        let bus = VirtualBus::new(BusConfig::new());
+        bus.install();
+        let mut driver = DualI2sDriverConfig::new_master()
+            .direction(Transmit, Receive)
+            .data_format(DataFormat::Data16Channel32)
+            .dual_i2s_driver(SimDualI2s::new(&bus));
+        driver.ext().enable();
+        driver.main().enable();
+        let mut received = Vec::new();
+        let mut next = 0;
+        run(&bus, 64 * 20, || {
+            if driver.main().status().txe() {
+                driver.main().write_data_register(next);
+                next += 1;
+            }
+            if driver.ext().status().rxne() {
+                received.push(driver.ext().read_data_register());
+            }
+        });
+        assert!(received.len() > 20);
+        assert!(received.windows(2).all(|w| w[1] == w[0] + 1));
+    }
+
+    #[test]
+    fn test_fault_injection() {
+        // the slave receiver detects WS glitches as frame errors and synchronizes again
+        let bus = VirtualBus::new(BusConfig::new().ws_glitches(1000));
+        bus.install();
+        let mut transmitter = I2sTransferConfig::new_master().i2s_transfer(SimI2sA::new(&bus));
+        let mut receiver = I2sTransferConfig::new_slave()
+            .receive()
+            .i2s_transfer(SimI2sB::new(&bus));
+        let mut received = Vec::new();
+        let mut next = 0;
+        run(&bus, 20_000, || {
+            if transmitter.write((next, -next)).is_ok() {
+                next += 1;
+            }
+            if let Ok((left, right)) = receiver.read::<(i16, i16)>() {
+                received.push((left as i32, right as i32));
+            }
+        });
+        check_frames(&received, 20);
+        assert!(received.windows(2).any(|w| w[1].0 != w[0].0 + 1));
+
+        // bit errors corrupt data without breaking synchronization
+        let bus = VirtualBus::new(BusConfig::new().bit_errors(301));
+        bus.install();
+        let mut transmitter = I2sTransferConfig::new_master().i2s_transfer(SimI2sA::new(&bus));
+        let mut receiver = I2sTransferConfig::new_slave()
+            .receive()
+            .i2s_transfer(SimI2sB::new(&bus));
+        let mut received = Vec::new();
+        let mut next = 0;
+        run(&bus, 20_000, || {
+            if transmitter.write((next, -next)).is_ok() {
+                next += 1;
+            }
+            if let Ok(frame) = receiver.read::<(i16, i16)>() {
+                received.push(frame);
+            }
+        });
+        let corrupted = received.iter().filter(|f| f.0 != -f.1).count();
+        assert!(corrupted > 0 && corrupted <= 20_000 / 301 + 1);
+        // no frame lost, except the ones being transmitted
+        assert!(next as usize - received.len() <= 4);
+    }
+
+    #[test]
+    fn test_underrun_recovery() {
+        // the slave transmitter stops writing for a while, then synchronizes again
+        let bus = VirtualBus::new(BusConfig::new().start_offset(3));
+        bus.install();
+        let mut receiver = I2sTransferConfig::new_master()
+            .receive()
+            .i2s_transfer(SimI2sA::new(&bus));
+        let mut transmitter = I2sTransferConfig::new_slave().i2s_transfer(SimI2sB::new(&bus));
+        let mut received = Vec::new();
+        let mut next = 0;
+        run(&bus, 10_000, || {
+            let paused = (3000..4000).contains(&bus.bits());
+            if !paused && transmitter.write((next, -next)).is_ok() {
+                next += 1;
+            }
+            if let Ok((left, right)) = receiver.read::<(i16, i16)>() {
+                received.push((left as i32, right as i32));
+            }
+        });
+        // silence is received during the pause, then frames again
+        let restart = received.iter().rposition(|f| f.0 == 0).unwrap();
+        assert!(restart > 100);
+        check_frames(&received[restart + 1..], 1);
+    }
+}
@@ -0,0 +1,457 @@
+//! Register access tracing.
+//!
+//! With the `trace` feature, every access to SPI/I2S registers goes through this module. Accesses
+//! can be recorded with decoded field names, in order to debug hardware issues or to check
+//! register sequences in host tests. A [`Backend`] can also intercept accesses, for example to
+//! simulate a peripheral.
+//!
+//! Recording and backends are per thread, so parallel tests don't interfere. This module requires
+//! `std`.
+//!
+//! ```no_run
+//! # use stm32_i2s_v12x::trace;
+//! trace::start_recording();
+//! // driver operations
+//! for record in trace::stop_recording() {
+//!     println!("{}", record);
+//! }
+//! ```
+//!
+//! Example output:
+//! ```text
+//! modify I2SCFGR 0x00000a00 -> 0x00000e00 [I2SCFG=2 I2SE I2SMOD]
+//! read SR 0x00000002 [TXE]
+//! write DR 0x00001234 [DR=0x1234]
+//! ```
+use core::fmt;
+use std::boxed::Box;
+use std::cell::RefCell;
+use std::vec::Vec;
+
+/// A register of the SPI/I2S register block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    /// Control register 1
+    Cr1,
+    /// Control register 2
+    Cr2,
+    /// Status register
+    Sr,
+    /// Data register
+    Dr,
+    /// CRC polynomial register
+    Crcpr,
+    /// Rx CRC register
+    Rxcrcr,
+    /// Tx CRC register
+    Txcrcr,
+    /// I2S configuration register
+    I2scfgr,
+    /// I2S prescaler register
+    I2spr,
+}
+
+/// Register fields as `(name, offset, width)`.
+type FieldTable = &'static [(&'static str, u8, u8)];
+
+impl Register {
+    /// Get a register from its offset in the register block.
+    pub fn from_offset(offset: usize) -> Option<Self> {
+        use Register::*;
+        let register = match offset {
+            0x00 => Cr1,
+            0x04 => Cr2,
+            0x08 => Sr,
+            0x0c => Dr,
+            0x10 => Crcpr,
+            0x14 => Rxcrcr,
+            0x18 => Txcrcr,
+            0x1c => I2scfgr,
+            0x20 => I2spr,
+            _ => return None,
+        };
+        Some(register)
+    }
+
+    /// Offset of the register in the register block.
+    pub fn offset(self) -> usize {
+        self as usize * 4
+    }
+
+    /// Name of the register, as in reference manuals.
+    pub fn name(self) -> &'static str {
+        use Register::*;
+        match self {
+            Cr1 => "CR1",
+            Cr2 => "CR2",
+            Sr => "SR",
+            Dr => "DR",
+            Crcpr => "CRCPR",
+            Rxcrcr => "RXCRCR",
+            Txcrcr => "TXCRCR",
+            I2scfgr => "I2SCFGR",
+            I2spr => "I2SPR",
+        }
+    }
+
+    fn fields(self) -> FieldTable {
+        use Register::*;
+        match self {
+            Cr1 => &[
+                ("CPHA", 0, 1),
+                ("CPOL", 1, 1),
+                ("MSTR", 2, 1),
+                ("BR", 3, 3),
+                ("SPE", 6, 1),
+                ("LSBFIRST", 7, 1),
+                ("SSI", 8, 1),
+                ("SSM", 9, 1),
+                ("RXONLY", 10, 1),
+                ("DFF", 11, 1),
+                ("CRCNEXT", 12, 1),
+                ("CRCEN", 13, 1),
+                ("BIDIOE", 14, 1),
+                ("BIDIMODE", 15, 1),
+            ],
+            Cr2 => &[
+                ("RXDMAEN", 0, 1),
+                ("TXDMAEN", 1, 1),
+                ("SSOE", 2, 1),
+                ("FRF", 4, 1),
+                ("ERRIE", 5, 1),
+                ("RXNEIE", 6, 1),
+                ("TXEIE", 7, 1),
+            ],
+            Sr => &[
+                ("RXNE", 0, 1),
+                ("TXE", 1, 1),
+                ("CHSIDE", 2, 1),
+                ("UDR", 3, 1),
+                ("CRCERR", 4, 1),
+                ("MODF", 5, 1),
+                ("OVR", 6, 1),
+                ("BSY", 7, 1),
+                ("FRE", 8, 1),
+            ],
+            Dr => &[("DR", 0, 16)],
+            Crcpr => &[("CRCPOLY", 0, 16)],
+            Rxcrcr => &[("RXCRC", 0, 16)],
+            Txcrcr => &[("TXCRC", 0, 16)],
+            I2scfgr => &[
+                ("CHLEN", 0, 1),
+                ("DATLEN", 1, 2),
+                ("CKPOL", 3, 1),
+                ("I2SSTD", 4, 2),
+                ("PCMSYNC", 7, 1),
+                ("I2SCFG", 8, 2),
+                ("I2SE", 10, 1),
+                ("I2SMOD", 11, 1),
+            ],
+            I2spr => &[("I2SDIV", 0, 8), ("ODD", 8, 1), ("MCKOE", 9, 1)],
+        }
+    }
+
+    /// Decode a value of this register into named fields.
+    pub fn decode(self, value: u32) -> Fields {
+        Fields {
+            register: self,
+            value,
+        }
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Non-zero fields of a register value.
+///
+/// One bit fields are displayed by name only, wider fields as `NAME=value`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fields {
+    register: Register,
+    value: u32,
+}
+
+impl fmt::Display for Fields {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut separator = "";
+        for &(name, offset, width) in self.register.fields() {
+            let field = (self.value >> offset) & ((1 << width) - 1);
+            if field == 0 {
+                continue;
+            }
+            match width {
+                1 => write!(f, "{}{}", separator, name)?,
+                16 => write!(f, "{}{}={:#06x}", separator, name, field)?,
+                _ => write!(f, "{}{}={}", separator, name, field)?,
+            }
+            separator = " ";
+        }
+        Ok(())
+    }
+}
+
+/// Kind of register access, with accessed values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Value read.
+    Read(u32),
+    /// Value written.
+    Write(u32),
+    /// Read-modify-write, with read and written values.
+    Modify(u32, u32),
+}
+
+/// A recorded register access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record {
+    /// Address of the accessed register.
+    pub address: usize,
+    /// Accessed register.
+    pub register: Register,
+    /// Access kind and values.
+    pub access: Access,
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (kind, value) = match self.access {
+            Access::Read(value) => ("read", value),
+            Access::Write(value) => ("write", value),
+            Access::Modify(old, new) => {
+                write!(f, "modify {} {:#010x} -> ", self.register, old)?;
+                ("", new)
+            }
+        };
+        if !kind.is_empty() {
+            write!(f, "{} {} ", kind, self.register)?;
+        }
+        let fields = self.register.decode(value);
+        write!(f, "{:#010x} [{}]", value, fields)
+    }
+}
+
+/// Interception of register accesses.
+///
+/// Default implementations let accesses reach the memory unchanged.
+pub trait Backend {
+    /// `true` if the backend emulates the register at `address`. The memory is then never
+    /// accessed: reads get 0 as memory content and written values are dropped, so emulated
+    /// register blocks can be shared by threads.
+    fn emulates(&self, address: usize) -> bool {
+        let _ = address;
+        false
+    }
+
+    /// Called on each read. `value` is the content of the memory, the returned value is the one
+    /// seen by the driver.
+    fn read(&mut self, address: usize, register: Register, value: u32) -> u32 {
+        let _ = (address, register);
+        value
+    }
+
+    /// Called on each write. `value` is the one written by the driver, the returned value is the
+    /// one stored in memory.
+    fn write(&mut self, address: usize, register: Register, value: u32) -> u32 {
+        let _ = (address, register);
+        value
+    }
+}
+
+#[derive(Default)]
+struct State {
+    backend: Option<Box<dyn Backend>>,
+    records: Option<Vec<Record>>,
+}
+
+std::thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::default());
+}
+
+/// Install a backend for the current thread, or remove it with `None`. The previous backend is
+/// returned.
+pub fn set_backend(backend: Option<Box<dyn Backend>>) -> Option<Box<dyn Backend>> {
+    STATE.with(|state| core::mem::replace(&mut state.borrow_mut().backend, backend))
+}
+
+/// Start recording register accesses of the current thread, discarding previous records.
+pub fn start_recording() {
+    STATE.with(|state| state.borrow_mut().records = Some(Vec::new()));
+}
+
+/// Stop recording register accesses of the current thread and return records.
+pub fn stop_recording() -> Vec<Record> {
+    STATE.with(|state| state.borrow_mut().records.take().unwrap_or_default())
+}
+
+/// Call `f` with the backend, if any, taken out of the state so it can't observe a borrowed
+/// state.
+fn with_backend<T>(f: impl FnOnce(Option<&mut dyn Backend>) -> T) -> T {
+    let mut backend = STATE.with(|state| state.borrow_mut().backend.take());
+    let value = match backend.as_mut() {
+        Some(backend) => f(Some(&mut **backend)),
+        None => f(None),
+    };
+    if let Some(backend) = backend {
+        // keep a backend installed meanwhile, if any
+        STATE.with(|state| {
+            state.borrow_mut().backend.get_or_insert(backend);
+        });
+    }
+    value
+}
+
+fn record(address: usize, register: Register, access: Access) {
+    STATE.with(|state| {
+        if let Some(records) = state.borrow_mut().records.as_mut() {
+            records.push(Record {
+                address,
+                register,
+                access,
+            });
+        }
+    });
+}
+
+fn register(offset: usize) -> Register {
+    Register::from_offset(offset).expect("unknown register offset")
+}
+
+fn load(address: usize, register: Register, load: impl FnOnce() -> u32) -> u32 {
+    with_backend(|backend| match backend {
+        Some(backend) if backend.emulates(address) => backend.read(address, register, 0),
+        Some(backend) => backend.read(address, register, load()),
+        None => load(),
+    })
+}
+
+fn store(address: usize, register: Register, value: u32, store: impl FnOnce(u32)) {
+    with_backend(|backend| match backend {
+        Some(backend) if backend.emulates(address) => {
+            backend.write(address, register, value);
+        }
+        Some(backend) => store(backend.write(address, register, value)),
+        None => store(value),
+    })
+}
+
+pub(crate) fn read(offset: usize, address: usize, memory: impl FnOnce() -> u32) -> u32 {
+    let register = register(offset);
+    let value = load(address, register, memory);
+    record(address, register, Access::Read(value));
+    value
+}
+
+pub(crate) fn write(offset: usize, address: usize, value: u32, memory: impl FnOnce(u32)) {
+    let register = register(offset);
+    record(address, register, Access::Write(value));
+    store(address, register, value, memory);
+}
+
+pub(crate) fn modify(
+    offset: usize,
+    address: usize,
+    load_memory: impl FnOnce() -> u32,
+    f: impl FnOnce(u32) -> u32,
+    store_memory: impl FnOnce(u32),
+) {
+    let register = register(offset);
+    let old = load(address, register, load_memory);
+    let new = f(old);
+    record(address, register, Access::Modify(old, new));
+    store(address, register, new, store_memory);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::ToString;
+
+    #[test]
+    fn test_display() {
+        let record = Record {
+            address: 0,
+            register: Register::I2scfgr,
+            access: Access::Modify(0x0a00, 0x0e1a),
+        };
+        assert_eq!(
+            record.to_string(),
+            "modify I2SCFGR 0x00000a00 -> 0x00000e1a [DATLEN=1 CKPOL I2SSTD=1 I2SCFG=2 I2SE I2SMOD]"
+        );
+        let record = Record {
+            address: 0,
+            register: Register::Dr,
+            access: Access::Write(0x1234),
+        };
+        assert_eq!(record.to_string(), "write DR 0x00001234 [DR=0x1234]");
+        for offset in (0..0x24).step_by(4) {
+            assert_eq!(Register::from_offset(offset).unwrap().offset(), offset);
+        }
+    }
+
+    struct Inverter;
+
+    impl Backend for Inverter {
+        fn read(&mut self, _: usize, _: Register, value: u32) -> u32 {
+            !value
+        }
+    }
+
+    struct Emulator(u32);
+
+    impl Backend for Emulator {
+        fn emulates(&self, _: usize) -> bool {
+            true
+        }
+        fn read(&mut self, _: usize, _: Register, value: u32) -> u32 {
+            assert_eq!(value, 0);
+            self.0
+        }
+        fn write(&mut self, _: usize, _: Register, value: u32) -> u32 {
+            self.0 = value;
+            value
+        }
+    }
+
+    #[test]
+    fn test_backend() {
+        let memory = [0u32; 9];
+        let address = memory.as_ptr() as usize;
+        let mut stored = None;
+        assert!(set_backend(Some(Box::new(Inverter))).is_none());
+        start_recording();
+        assert_eq!(read(0x08, address + 8, || 2), !2);
+        write(0x0c, address + 12, 5, |bits| stored = Some(bits));
+        assert_eq!(stored, Some(5));
+        modify(
+            0x1c,
+            address + 28,
+            || 0,
+            |bits| bits & 0xff,
+            |bits| stored = Some(bits),
+        );
+        assert_eq!(stored, Some(0xff));
+        assert!(set_backend(None).is_some());
+        let records = stop_recording();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].access, Access::Read(!2));
+        assert_eq!(records[2].register, Register::I2scfgr);
+        assert!(stop_recording().is_empty());
+    }
+
+    #[test]
+    fn test_emulating_backend() {
+        // the memory is never accessed
+        let memory = || -> u32 { panic!("memory read") };
+        let store = |_| panic!("memory written");
+        set_backend(Some(Box::new(Emulator(3))));
+        assert_eq!(read(0x08, 0, memory), 3);
+        write(0x0c, 0, 5, store);
+        modify(0x1c, 0, memory, |bits| bits + 1, store);
+        assert_eq!(read(0x08, 0, memory), 6);
+        set_backend(None);
+    }
+}
@@ -5,7 +5,7 @@
 //! trait. The job is mainly done by [`I2sTransfer`], a type that wraps an I2sPeripheral to control
 //! it.
 //!
-//! At the moment, transfer is not implemented for 24-bit data.
+//! 24-bit data is transferred as left-justified `i32` samples, with the same words as 32-bit data.
 //!
 //! # Configure and instantiate transfer
 //!
@@ -93,29 +93,37 @@
 //! }
 //! ```
 use crate::sealed::Sealed;
-use core::convert::Infallible;
+use core::convert::{Infallible, TryInto};
 use core::marker::PhantomData;
 
 use nb::Error::WouldBlock;
 
+use crate::audio::{AudioSink, AudioSource, Frames};
 use crate::driver::ClockPolarity;
+use crate::driver::FrequencyError;
 use crate::driver::I2sDriver as Driver;
 use crate::driver::I2sDriverConfig as DriverConfig;
-use crate::{I2sPeripheral, WsPin};
+use crate::{I2sClockControl, I2sPeripheral, WsPin};
 
 pub use crate::marker::{self, *};
+use crate::sample::Sample;
+pub use crate::sample::{Q15, Q31};
 
-/// Trait to build an internal frame representation of an `I2sTransfer` from markers.
-#[doc(hidden)]
+/// Frame representation of an `I2sTransfer`, selected by a `(STD, FMT)` pair of markers.
+///
+/// This is implemented for every valid pair of standard and data format, and can't be
+/// implemented outside of this crate.
 pub trait FrameFormat: Sealed {
-    /// Raw frame representation for transfer implementation
+    /// Words of a frame, in transfer order.
     ///
-    /// The actual type is always an array of u16
+    /// This is always an array of u16: one word per 16 bit channel, two words per 32 bit
+    /// channel, and a single channel with PCM standards. For example, it's `[u16; 4]` for
+    /// `(Philips, Data32Channel32)` and `[u16; 1]` for `(PcmShortSync, Data16Channel32)`.
     type RawFrame: Default + Copy + Sync + Send + AsRef<[u16]> + AsMut<[u16]>;
 }
 
-/// Syntax sugar to get the appropriate internal frame representation from markers.
-type RawFrame<STD, FMT> = <(STD, FMT) as FrameFormat>::RawFrame;
+/// Frame representation for a standard and a data format, see [`FrameFormat::RawFrame`].
+pub type RawFrame<STD, FMT> = <(STD, FMT) as FrameFormat>::RawFrame;
 
 macro_rules! impl_frame_format{
     ($(([$($std:ident),*],$fmt:ident,$raw_frame:ty)),*) => {
@@ -134,17 +142,23 @@ impl<T: Sealed, U: Sealed> Sealed for (T, U) {}
 impl_frame_format!(
     ([Philips, Msb, Lsb], Data16Channel16, [u16; 2]),
     ([Philips, Msb, Lsb], Data16Channel32, [u16; 2]),
+    ([Philips, Msb, Lsb], Data24Channel32, [u16; 4]),
     ([Philips, Msb, Lsb], Data32Channel32, [u16; 4]),
     ([PcmShortSync, PcmLongSync], Data16Channel16, [u16; 1]),
     ([PcmShortSync, PcmLongSync], Data16Channel32, [u16; 1]),
+    ([PcmShortSync, PcmLongSync], Data24Channel32, [u16; 2]),
     ([PcmShortSync, PcmLongSync], Data32Channel32, [u16; 2])
 );
 
 /// Types written to `I2sTransfer`.
+///
+/// Implementing this trait on a struct made of samples is easiest with
+/// [`derive_raw_frame`](crate::derive_raw_frame).
 pub trait ToRawFrame<STD, FMT>
 where
     (STD, FMT): FrameFormat,
 {
+    /// Get the words of the frame, in transfer order.
     fn to_raw(&self) -> RawFrame<STD, FMT>;
 }
 
@@ -169,6 +183,15 @@ impl_to_raw_frame!(
     fn to_raw(&self) -> [u16; 2] {
         [self.0 as u16, self.1 as u16]
     },
+    ((i32, i32), [Philips, Msb, Lsb], Data24Channel32),
+    fn to_raw(&self) -> [u16; 4] {
+        [
+            (self.0 as u32 >> 16) as u16,
+            (self.0 as u32 & 0xFFFF) as u16,
+            (self.1 as u32 >> 16) as u16,
+            (self.1 as u32 & 0xFFFF) as u16,
+        ]
+    },
     ((i32, i32), [Philips, Msb, Lsb], Data32Channel32),
     fn to_raw(&self) -> [u16; 4] {
         [
@@ -186,20 +209,165 @@ impl_to_raw_frame!(
     fn to_raw(&self) -> [u16; 1] {
         [*self as u16]
     },
+    (i32, [PcmShortSync, PcmLongSync], Data24Channel32),
+    fn to_raw(&self) -> [u16; 2] {
+        [(*self as u32 >> 16) as u16, (*self as u32 & 0xFFFF) as u16]
+    },
     (i32, [PcmShortSync, PcmLongSync], Data32Channel32),
     fn to_raw(&self) -> [u16; 2] {
         [(*self as u32 >> 16) as u16, (*self as u32 & 0xFFFF) as u16]
     }
 );
 
+impl_to_raw_frame!(
+    ([i16; 2], [Philips, Msb, Lsb], Data16Channel16),
+    fn to_raw(&self) -> [u16; 2] {
+        [self[0] as u16, self[1] as u16]
+    },
+    ([i16; 2], [Philips, Msb, Lsb], Data16Channel32),
+    fn to_raw(&self) -> [u16; 2] {
+        [self[0] as u16, self[1] as u16]
+    },
+    ([i32; 2], [Philips, Msb, Lsb], Data24Channel32),
+    fn to_raw(&self) -> [u16; 4] {
+        [
+            (self[0] as u32 >> 16) as u16,
+            (self[0] as u32 & 0xFFFF) as u16,
+            (self[1] as u32 >> 16) as u16,
+            (self[1] as u32 & 0xFFFF) as u16,
+        ]
+    },
+    ([i32; 2], [Philips, Msb, Lsb], Data32Channel32),
+    fn to_raw(&self) -> [u16; 4] {
+        [
+            (self[0] as u32 >> 16) as u16,
+            (self[0] as u32 & 0xFFFF) as u16,
+            (self[1] as u32 >> 16) as u16,
+            (self[1] as u32 & 0xFFFF) as u16,
+        ]
+    }
+);
+
 /// Types read from `I2sTransfer`.
+///
+/// Implementing this trait on a struct made of samples is easiest with
+/// [`derive_raw_frame`](crate::derive_raw_frame).
 pub trait FromRawFrame<STD, FMT>
 where
     (STD, FMT): FrameFormat,
 {
+    /// Build a frame from its words, in transfer order.
     fn from_raw(raw: RawFrame<STD, FMT>) -> Self;
 }
 
+/// Implement [`ToRawFrame`] and [`FromRawFrame`] on a struct of samples, for the given standards
+/// and data formats.
+///
+/// A stereo struct has `left` and `right` fields and a mono struct, for PCM standards, has a
+/// `mono` field. Those fields are converted like samples of a tuple, so every sample type usable
+/// in tuples is accepted (`i16`, `i32`, `f32`, [`Q15`], [`Q31`]), and combinations that aren't
+/// valid, for example 16 bit samples with 32 bit data or stereo with PCM standards, don't
+/// compile.
+///
+/// ```
+/// use stm32_i2s_v12x::derive_raw_frame;
+/// use stm32_i2s_v12x::transfer::*;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct StereoFrame {
+///     l: i16,
+///     r: i16,
+/// }
+///
+/// struct Voice {
+///     sample: f32,
+/// }
+///
+/// derive_raw_frame!(StereoFrame { left: l, right: r }: i16, [Philips, Msb], [Data16Channel16]);
+/// derive_raw_frame!(Voice { mono: sample }: f32, [PcmShortSync], [Data16Channel16, Data32Channel32]);
+///
+/// let frame = StereoFrame { l: 1, r: -1 };
+/// let raw = ToRawFrame::<Philips, Data16Channel16>::to_raw(&frame);
+/// assert_eq!(raw, [0x0001, 0xFFFF]);
+/// let read: StereoFrame = FromRawFrame::<Msb, Data16Channel16>::from_raw(raw);
+/// assert_eq!(read, frame);
+/// let raw = ToRawFrame::<PcmShortSync, Data32Channel32>::to_raw(&Voice { sample: 0.5 });
+/// assert_eq!(raw, [0x4000, 0x0000]);
+/// ```
+///
+/// 16 bit integer samples can't be used with 32 bit data:
+///
+/// ```compile_fail
+/// # use stm32_i2s_v12x::derive_raw_frame;
+/// # use stm32_i2s_v12x::transfer::*;
+/// struct StereoFrame {
+///     l: i16,
+///     r: i16,
+/// }
+///
+/// derive_raw_frame!(StereoFrame { left: l, right: r }: i16, [Philips], [Data32Channel32]);
+/// ```
+#[macro_export]
+macro_rules! derive_raw_frame {
+    ($name:ident { left: $left:ident, right: $right:ident }: $sample:ty, [$($std:ident),+ $(,)?], $fmts:tt) => {
+        $(
+            $crate::derive_raw_frame!(@stereo $name, $left, $right, $sample, $std, $fmts);
+        )+
+    };
+    ($name:ident { mono: $mono:ident }: $sample:ty, [$($std:ident),+ $(,)?], $fmts:tt) => {
+        $(
+            $crate::derive_raw_frame!(@mono $name, $mono, $sample, $std, $fmts);
+        )+
+    };
+    (@stereo $name:ident, $left:ident, $right:ident, $sample:ty, $std:ident, [$($fmt:ident),+ $(,)?]) => {
+        $(
+            impl $crate::transfer::ToRawFrame<$crate::marker::$std, $crate::marker::$fmt> for $name {
+                fn to_raw(
+                    &self,
+                ) -> $crate::transfer::RawFrame<$crate::marker::$std, $crate::marker::$fmt> {
+                    use $crate::marker::{$fmt, $std};
+                    let frame: ($sample, $sample) = (self.$left, self.$right);
+                    $crate::transfer::ToRawFrame::<$std, $fmt>::to_raw(&frame)
+                }
+            }
+
+            impl $crate::transfer::FromRawFrame<$crate::marker::$std, $crate::marker::$fmt> for $name {
+                fn from_raw(
+                    raw: $crate::transfer::RawFrame<$crate::marker::$std, $crate::marker::$fmt>,
+                ) -> Self {
+                    use $crate::marker::{$fmt, $std};
+                    let ($left, $right): ($sample, $sample) =
+                        $crate::transfer::FromRawFrame::<$std, $fmt>::from_raw(raw);
+                    Self { $left, $right }
+                }
+            }
+        )+
+    };
+    (@mono $name:ident, $mono:ident, $sample:ty, $std:ident, [$($fmt:ident),+ $(,)?]) => {
+        $(
+            impl $crate::transfer::ToRawFrame<$crate::marker::$std, $crate::marker::$fmt> for $name {
+                fn to_raw(
+                    &self,
+                ) -> $crate::transfer::RawFrame<$crate::marker::$std, $crate::marker::$fmt> {
+                    use $crate::marker::{$fmt, $std};
+                    let sample: $sample = self.$mono;
+                    $crate::transfer::ToRawFrame::<$std, $fmt>::to_raw(&sample)
+                }
+            }
+
+            impl $crate::transfer::FromRawFrame<$crate::marker::$std, $crate::marker::$fmt> for $name {
+                fn from_raw(
+                    raw: $crate::transfer::RawFrame<$crate::marker::$std, $crate::marker::$fmt>,
+                ) -> Self {
+                    use $crate::marker::{$fmt, $std};
+                    let $mono: $sample = $crate::transfer::FromRawFrame::<$std, $fmt>::from_raw(raw);
+                    Self { $mono }
+                }
+            }
+        )+
+    };
+}
+
 macro_rules! impl_from_raw_frame{
     ($(($type:ty,[$($std:ident),*],$fmt:ident),$func:item),*) => {
         $(
@@ -221,6 +389,12 @@ impl_from_raw_frame!(
     fn from_raw(raw: [u16; 2]) -> Self {
         (raw[0] as i16, raw[1] as i16)
     },
+    ((i32, i32), [Philips, Msb, Lsb], Data24Channel32),
+    fn from_raw(raw: [u16; 4]) -> Self {
+        let l = (raw[0] as i32) << 16 | raw[1] as i32;
+        let r = (raw[2] as i32) << 16 | raw[3] as i32;
+        (l, r)
+    },
     ((i32, i32), [Philips, Msb, Lsb], Data32Channel32),
     fn from_raw(raw: [u16; 4]) -> Self {
         let l = (raw[0] as i32) << 16 | raw[1] as i32;
@@ -235,18 +409,281 @@ impl_from_raw_frame!(
     fn from_raw(raw: [u16; 1]) -> Self {
         raw[0] as i16
     },
+    (i32, [PcmShortSync, PcmLongSync], Data24Channel32),
+    fn from_raw(raw: [u16; 2]) -> Self {
+        (raw[0] as i32) << 16 | raw[1] as i32
+    },
     (i32, [PcmShortSync, PcmLongSync], Data32Channel32),
     fn from_raw(raw: [u16; 2]) -> Self {
         (raw[0] as i32) << 16 | raw[1] as i32
     }
 );
 
+impl_from_raw_frame!(
+    ([i16; 2], [Philips, Msb, Lsb], Data16Channel16),
+    fn from_raw(raw: [u16; 2]) -> Self {
+        [raw[0] as i16, raw[1] as i16]
+    },
+    ([i16; 2], [Philips, Msb, Lsb], Data16Channel32),
+    fn from_raw(raw: [u16; 2]) -> Self {
+        [raw[0] as i16, raw[1] as i16]
+    },
+    ([i32; 2], [Philips, Msb, Lsb], Data24Channel32),
+    fn from_raw(raw: [u16; 4]) -> Self {
+        let l = (raw[0] as i32) << 16 | raw[1] as i32;
+        let r = (raw[2] as i32) << 16 | raw[3] as i32;
+        [l, r]
+    },
+    ([i32; 2], [Philips, Msb, Lsb], Data32Channel32),
+    fn from_raw(raw: [u16; 4]) -> Self {
+        let l = (raw[0] as i32) << 16 | raw[1] as i32;
+        let r = (raw[2] as i32) << 16 | raw[3] as i32;
+        [l, r]
+    }
+);
+
+/// Implement frame conversions of a [`Sample`] type for every valid standard and data format, as
+/// a tuple or an array for stereo standards, and as a single value for PCM standards.
+macro_rules! impl_sample_frames {
+    ($($type:ty),*) => {
+        $(
+            impl_to_raw_frame!(
+                (($type, $type), [Philips, Msb, Lsb], Data16Channel16),
+                fn to_raw(&self) -> [u16; 2] {
+                    [self.0.to_word16(), self.1.to_word16()]
+                },
+                (($type, $type), [Philips, Msb, Lsb], Data16Channel32),
+                fn to_raw(&self) -> [u16; 2] {
+                    [self.0.to_word16(), self.1.to_word16()]
+                },
+                (($type, $type), [Philips, Msb, Lsb], Data32Channel32),
+                fn to_raw(&self) -> [u16; 4] {
+                    let (l, r) = (self.0.to_word32(), self.1.to_word32());
+                    [(l >> 16) as u16, l as u16, (r >> 16) as u16, r as u16]
+                },
+                ([$type; 2], [Philips, Msb, Lsb], Data16Channel16),
+                fn to_raw(&self) -> [u16; 2] {
+                    [self[0].to_word16(), self[1].to_word16()]
+                },
+                ([$type; 2], [Philips, Msb, Lsb], Data16Channel32),
+                fn to_raw(&self) -> [u16; 2] {
+                    [self[0].to_word16(), self[1].to_word16()]
+                },
+                ([$type; 2], [Philips, Msb, Lsb], Data32Channel32),
+                fn to_raw(&self) -> [u16; 4] {
+                    let (l, r) = (self[0].to_word32(), self[1].to_word32());
+                    [(l >> 16) as u16, l as u16, (r >> 16) as u16, r as u16]
+                },
+                ($type, [PcmShortSync, PcmLongSync], Data16Channel16),
+                fn to_raw(&self) -> [u16; 1] {
+                    [self.to_word16()]
+                },
+                ($type, [PcmShortSync, PcmLongSync], Data16Channel32),
+                fn to_raw(&self) -> [u16; 1] {
+                    [self.to_word16()]
+                },
+                ($type, [PcmShortSync, PcmLongSync], Data32Channel32),
+                fn to_raw(&self) -> [u16; 2] {
+                    let word = self.to_word32();
+                    [(word >> 16) as u16, word as u16]
+                }
+            );
+            impl_from_raw_frame!(
+                (($type, $type), [Philips, Msb, Lsb], Data16Channel16),
+                fn from_raw(raw: [u16; 2]) -> Self {
+                    (Sample::from_word16(raw[0]), Sample::from_word16(raw[1]))
+                },
+                (($type, $type), [Philips, Msb, Lsb], Data16Channel32),
+                fn from_raw(raw: [u16; 2]) -> Self {
+                    (Sample::from_word16(raw[0]), Sample::from_word16(raw[1]))
+                },
+                (($type, $type), [Philips, Msb, Lsb], Data32Channel32),
+                fn from_raw(raw: [u16; 4]) -> Self {
+                    let l = (raw[0] as u32) << 16 | raw[1] as u32;
+                    let r = (raw[2] as u32) << 16 | raw[3] as u32;
+                    (Sample::from_word32(l), Sample::from_word32(r))
+                },
+                ([$type; 2], [Philips, Msb, Lsb], Data16Channel16),
+                fn from_raw(raw: [u16; 2]) -> Self {
+                    [Sample::from_word16(raw[0]), Sample::from_word16(raw[1])]
+                },
+                ([$type; 2], [Philips, Msb, Lsb], Data16Channel32),
+                fn from_raw(raw: [u16; 2]) -> Self {
+                    [Sample::from_word16(raw[0]), Sample::from_word16(raw[1])]
+                },
+                ([$type; 2], [Philips, Msb, Lsb], Data32Channel32),
+                fn from_raw(raw: [u16; 4]) -> Self {
+                    let l = (raw[0] as u32) << 16 | raw[1] as u32;
+                    let r = (raw[2] as u32) << 16 | raw[3] as u32;
+                    [Sample::from_word32(l), Sample::from_word32(r)]
+                },
+                ($type, [PcmShortSync, PcmLongSync], Data16Channel16),
+                fn from_raw(raw: [u16; 1]) -> Self {
+                    Sample::from_word16(raw[0])
+                },
+                ($type, [PcmShortSync, PcmLongSync], Data16Channel32),
+                fn from_raw(raw: [u16; 1]) -> Self {
+                    Sample::from_word16(raw[0])
+                },
+                ($type, [PcmShortSync, PcmLongSync], Data32Channel32),
+                fn from_raw(raw: [u16; 2]) -> Self {
+                    Sample::from_word32((raw[0] as u32) << 16 | raw[1] as u32)
+                }
+            );
+        )*
+    };
+}
+
+impl_sample_frames!(f32, Q15, Q31);
+
+impl<STD, FMT, T> ToRawFrame<STD, FMT> for &T
+where
+    (STD, FMT): FrameFormat,
+    T: ToRawFrame<STD, FMT>,
+{
+    fn to_raw(&self) -> RawFrame<STD, FMT> {
+        (*self).to_raw()
+    }
+}
+
+/// Sample types of interleaved buffers, that is left and right samples alternating (L, R, L, R,
+/// ...), or consecutive samples with PCM standards.
+pub trait InterleavedSample<STD, FMT>: Copy
+where
+    (STD, FMT): FrameFormat,
+{
+    /// Number of samples in a frame: 2, or 1 with PCM standards.
+    const CHANNELS: usize;
+    /// Get the words of a frame from its samples, `samples.len()` being `CHANNELS`.
+    fn to_raw(samples: &[Self]) -> RawFrame<STD, FMT>;
+    /// Write the samples of a frame, `samples.len()` being `CHANNELS`.
+    fn from_raw(raw: RawFrame<STD, FMT>, samples: &mut [Self]);
+}
+
+macro_rules! impl_interleaved_sample{
+    ($(($type:ty,[$($std:ident),*],$fmt:ident,$channels:tt)),*) => {
+        $(
+            $(
+                impl InterleavedSample<$std, $fmt> for $type {
+                    const CHANNELS: usize = $channels;
+                    fn to_raw(samples: &[Self]) -> RawFrame<$std, $fmt> {
+                        let frame: [$type; $channels] = samples.try_into().unwrap();
+                        impl_interleaved_sample!(@to_raw $std, $fmt, frame, $channels)
+                    }
+                    fn from_raw(raw: RawFrame<$std, $fmt>, samples: &mut [Self]) {
+                        let frame: [$type; $channels] =
+                            impl_interleaved_sample!(@from_raw $std, $fmt, raw, $channels);
+                        samples.copy_from_slice(&frame);
+                    }
+                }
+            )*
+        )*
+    };
+    (@to_raw $std:ident, $fmt:ident, $frame:ident, 2) => {
+        ToRawFrame::<$std, $fmt>::to_raw(&$frame)
+    };
+    (@to_raw $std:ident, $fmt:ident, $frame:ident, 1) => {
+        ToRawFrame::<$std, $fmt>::to_raw(&$frame[0])
+    };
+    (@from_raw $std:ident, $fmt:ident, $raw:ident, 2) => {
+        FromRawFrame::<$std, $fmt>::from_raw($raw)
+    };
+    (@from_raw $std:ident, $fmt:ident, $raw:ident, 1) => {
+        [FromRawFrame::<$std, $fmt>::from_raw($raw)]
+    };
+}
+
+impl_interleaved_sample!(
+    (i16, [Philips, Msb, Lsb], Data16Channel16, 2),
+    (i16, [Philips, Msb, Lsb], Data16Channel32, 2),
+    (i32, [Philips, Msb, Lsb], Data24Channel32, 2),
+    (i32, [Philips, Msb, Lsb], Data32Channel32, 2),
+    (i16, [PcmShortSync, PcmLongSync], Data16Channel16, 1),
+    (i16, [PcmShortSync, PcmLongSync], Data16Channel32, 1),
+    (i32, [PcmShortSync, PcmLongSync], Data24Channel32, 1),
+    (i32, [PcmShortSync, PcmLongSync], Data32Channel32, 1)
+);
+
+/// Frame given as raw words, allowing to transfer frames made from interleaved samples.
+pub(crate) struct Raw<STD, FMT>(pub(crate) RawFrame<STD, FMT>)
+where
+    (STD, FMT): FrameFormat;
+
+impl<STD, FMT> ToRawFrame<STD, FMT> for Raw<STD, FMT>
+where
+    (STD, FMT): FrameFormat,
+{
+    fn to_raw(&self) -> RawFrame<STD, FMT> {
+        self.0
+    }
+}
+
+impl<STD, FMT> FromRawFrame<STD, FMT> for Raw<STD, FMT>
+where
+    (STD, FMT): FrameFormat,
+{
+    fn from_raw(raw: RawFrame<STD, FMT>) -> Self {
+        Raw(raw)
+    }
+}
+
+/// Get the frames of interleaved samples.
+///
+/// # Panics
+///
+/// `samples.len()` must be a multiple of `S::CHANNELS`.
+fn _interleaved_frames<STD, FMT, S>(samples: &[S]) -> impl Iterator<Item = Raw<STD, FMT>> + '_
+where
+    (STD, FMT): FrameFormat,
+    S: InterleavedSample<STD, FMT>,
+{
+    assert!(
+        samples.len() % S::CHANNELS == 0,
+        "interleaved buffers must contain whole frames"
+    );
+    samples
+        .chunks_exact(S::CHANNELS)
+        .map(|samples| Raw(S::to_raw(samples)))
+}
+
+/// Get the frames of an interleaved buffer to fill.
+///
+/// # Panics
+///
+/// `samples.len()` must be a multiple of `S::CHANNELS`.
+fn _interleaved_chunks<STD, FMT, S>(samples: &mut [S]) -> core::slice::ChunksExactMut<'_, S>
+where
+    (STD, FMT): FrameFormat,
+    S: InterleavedSample<STD, FMT>,
+{
+    assert!(
+        samples.len() % S::CHANNELS == 0,
+        "interleaved buffers must contain whole frames"
+    );
+    samples.chunks_exact_mut(S::CHANNELS)
+}
+
+/// `true` when left and right channels can be told apart, that is for non PCM standards.
+pub(crate) fn _has_channels<STD: I2sStandard>() -> bool {
+    !matches!(
+        STD::VALUE,
+        crate::driver::I2sStandard::PcmShortSync | crate::driver::I2sStandard::PcmLongSync
+    )
+}
+
 /// Errors that may require a special handling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum I2sTransferError {
     Overrun,
 }
 
+impl From<Infallible> for I2sTransferError {
+    fn from(error: Infallible) -> Self {
+        match error {}
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 /// [`I2sTransfer`] configuration.
 ///
@@ -292,17 +729,66 @@ where
     ///
     /// # Panics
     ///
-    /// This method panics if an exact frequency is required and that frequency can not be set.
+    /// This method panics if an exact frequency is required, or a frequency is requested within a
+    /// tolerance, and that frequency can not be set.
     pub fn i2s_transfer<I: I2sPeripheral>(
         self,
         i2s_peripheral: I,
     ) -> I2sTransfer<I, MS, DIR, STD, FMT> {
-        let driver = self.driver_config.i2s_driver(i2s_peripheral);
-        I2sTransfer::<I, MS, DIR, STD, FMT> {
+        match self.try_i2s_transfer(i2s_peripheral) {
+            Ok(transfer) => transfer,
+            Err((_, error)) => panic!("{}", error),
+        }
+    }
+
+    /// Create a `I2sTransfer` object around an [`I2sPeripheral`] object, or give it back with an
+    /// error when the sampling frequency can't be set.
+    #[allow(clippy::type_complexity)]
+    pub fn try_i2s_transfer<I: I2sPeripheral>(
+        self,
+        i2s_peripheral: I,
+    ) -> Result<I2sTransfer<I, MS, DIR, STD, FMT>, (I, FrequencyError)> {
+        let driver = self.driver_config.try_i2s_driver(i2s_peripheral)?;
+        Ok(I2sTransfer::<I, MS, DIR, STD, FMT> {
+            driver,
+            frame: Default::default(),
+            transfer_count: 0,
+            sync: false,
+            clocks_only: false,
+            _fmt: PhantomData,
+        })
+    }
+}
+
+impl<DIR, STD, FMT> I2sTransferConfig<Master, DIR, STD, FMT>
+where
+    STD: I2sStandard,
+    FMT: DataFormat,
+    (STD, FMT): FrameFormat,
+{
+    /// Create a `I2sTransfer` like [`i2s_transfer`](Self::i2s_transfer), after reprogramming the
+    /// i2s clock source when this allows to reach the requested or required frequency exactly.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if an exact frequency is required, or a frequency is requested within a
+    /// tolerance, and that frequency can not be set.
+    pub fn i2s_transfer_with_clock_control<I>(
+        self,
+        i2s_peripheral: I,
+    ) -> I2sTransfer<I, Master, DIR, STD, FMT>
+    where
+        I: I2sPeripheral + I2sClockControl,
+    {
+        let driver = self
+            .driver_config
+            .i2s_driver_with_clock_control(i2s_peripheral);
+        I2sTransfer::<I, Master, DIR, STD, FMT> {
             driver,
             frame: Default::default(),
             transfer_count: 0,
             sync: false,
+            clocks_only: false,
             _fmt: PhantomData,
         }
     }
@@ -432,6 +918,17 @@ impl<DIR, STD, FMT> I2sTransferConfig<Master, DIR, STD, FMT> {
             _fmt: PhantomData,
         }
     }
+
+    /// Request an audio sampling frequency, with an error of at most `max_ppm` parts per million.
+    ///
+    /// If the error exceeds `max_ppm`, instantiating a transfer will panic, and the fallible
+    /// instantiation will report the best achievable frequency.
+    pub fn request_frequency_within(self, freq: u32, max_ppm: u32) -> Self {
+        I2sTransferConfig::<Master, DIR, STD, FMT> {
+            driver_config: self.driver_config.request_frequency_within(freq, max_ppm),
+            _fmt: PhantomData,
+        }
+    }
 }
 
 /// Abstraction allowing sending and receiving of I2S data while erasing hardware details.
@@ -443,7 +940,7 @@ impl<DIR, STD, FMT> I2sTransferConfig<Master, DIR, STD, FMT> {
 /// `I2sTransfer` in slave mode never fails when an error is detected. Instead, it tries to recover
 /// although some data may corrupted. This choice has been made because:
 ///  - corrupted data can't produce invalid audio values and therefore can't cause undefined
-///  behavior,
+///    behavior,
 ///  - audio quality is equally degraded by missing or corrupted data,
 ///  - it's easier to use.
 ///
@@ -452,6 +949,19 @@ impl<DIR, STD, FMT> I2sTransferConfig<Master, DIR, STD, FMT> {
 ///
 ///  `I2sTransfer` in master transmit never fails because the hardware can't detect errors in this
 ///  mode.
+///
+/// In master mode, `I2sTransfer` verifies the frame phase before transferring data, so the first
+/// word of each frame is always the first word of a left channel:
+///  - in master transmit, the WS line is polled when the Tx buffer is empty, and padding zeros are
+///    written until a left channel word and the last word of a right channel have been sent. The
+///    CHSIDE flag is not used since it's not reliable in this mode.
+///  - in master receive, words are discarded until a right channel word followed by a left channel
+///    word is received, according to the CHSIDE flag.
+///
+/// This covers `Philips`, `Msb` and `Lsb` standards. Thus, up to two frames may be padded, or one
+/// frame discarded, each time the transfer starts. With PCM standards, there is no channel and
+/// frames are assumed to start when clocks start. That also holds after clock-only mode for 16-bit
+/// channels, but 32-bit frames may be misaligned in this case.
 pub struct I2sTransfer<I, MS, DIR, STD, FMT>
 where
     I: I2sPeripheral,
@@ -461,6 +971,7 @@ where
     frame: RawFrame<STD, FMT>,
     transfer_count: u8, //track part of the frame we transmitting
     sync: bool,
+    clocks_only: bool,
     _fmt: PhantomData<FMT>,
 }
 
@@ -543,6 +1054,30 @@ where
         self.frame = Default::default();
         self.transfer_count = 0;
         self.sync = false;
+        self.clocks_only = false;
+    }
+
+    /// Activate the I2s interface in clock-only mode: clocks are generated but no data is
+    /// transferred until the next read or write, and clocks are not restarted at this point.
+    ///
+    /// This allows to start codecs requiring the master clock before being configured. See
+    /// [`I2sDriver::enable_clocks_only`](crate::driver::I2sDriver::enable_clocks_only) for
+    /// limitations.
+    pub fn begin_clocks_only(&mut self) {
+        self.driver.enable_clocks_only();
+        self.frame = Default::default();
+        self.transfer_count = 0;
+        self.sync = false;
+        self.clocks_only = true;
+    }
+
+    /// Enable the I2s interface, leaving clock-only mode if needed.
+    fn _enable(&mut self) {
+        if self.clocks_only {
+            self.driver.start_data_transfer();
+            self.clocks_only = false;
+        }
+        self.driver.enable();
     }
 }
 
@@ -556,12 +1091,64 @@ where
     }
 }
 
+impl<I, DIR, STD, FMT> I2sTransfer<I, Master, DIR, STD, FMT>
+where
+    I: I2sPeripheral + I2sClockControl,
+    (STD, FMT): FrameFormat,
+{
+    /// Change the sampling frequency, reprogramming the i2s clock source when this allows to reach
+    /// `freq` exactly. Otherwise, the nearest frequency reachable with the current clock source is
+    /// set.
+    ///
+    /// The transfer is ended before the change, it's restarted by the next read or write.
+    pub fn request_frequency(&mut self, freq: u32) {
+        self.end();
+        self.driver.request_frequency(freq);
+    }
+
+    /// Change the sampling frequency to exactly `freq`, reprogramming the i2s clock source if
+    /// needed.
+    ///
+    /// The transfer is ended before the change, even on error. It's restarted by the next read or
+    /// write.
+    pub fn require_frequency(&mut self, freq: u32) -> Result<(), FrequencyError> {
+        self.end();
+        self.driver.require_frequency(freq)
+    }
+}
+
 /// Master Transmit
 impl<I, STD, FMT> I2sTransfer<I, Master, Transmit, STD, FMT>
 where
     I: I2sPeripheral,
+    STD: I2sStandard,
     (STD, FMT): FrameFormat,
 {
+    /// Handle an empty Tx buffer while not synchronized. Return `true` when the next written word
+    /// is the first word of a left channel, otherwise a padding word is written.
+    ///
+    /// While not synchronized, `transfer_count` is 0 until a word is sent on the left channel, then
+    /// it counts words sent on the right channel plus one. WS isn't trusted before that since the
+    /// Tx buffer is already empty when the transfer starts, and with `Philips` WS changes one bit
+    /// before the first word of a channel is sent.
+    fn _tx_sync(&mut self) -> bool {
+        if _has_channels::<STD>() {
+            let words_per_channel = self.frame.as_ref().len() as u8 / 2;
+            if self._ws_is_start() {
+                self.transfer_count = 1;
+            } else if self.transfer_count > 0 {
+                self.transfer_count += 1;
+            }
+            if self.transfer_count <= words_per_channel {
+                self.driver.write_data_register(0);
+                return false;
+            }
+        }
+        self.sync = true;
+        self.transfer_count = 0;
+        true
+    }
+
     /// Transmit (blocking) data from an iterator.
     pub fn write_iter<ITER, T>(&mut self, samples: ITER)
     where
@@ -569,10 +1156,13 @@ where
         ITER: IntoIterator<Item = T>,
     {
         let mut samples = samples.into_iter();
-        self.driver.enable();
+        self._enable();
         loop {
             let status = self.driver.status();
             if status.txe() {
+                if !self.sync && !self._tx_sync() {
+                    continue;
+                }
                 // having this check before give a chance to optimizer to remove bound checking on
                 // array access
                 if self.transfer_count >= self.frame.as_ref().len() as u8 {
@@ -598,9 +1188,12 @@ where
     /// To fully transmit the frame, this function need to be continuously called until the next
     /// frame can be written.
     pub fn write<T: ToRawFrame<STD, FMT>>(&mut self, frame: T) -> nb::Result<(), Infallible> {
-        self.driver.enable();
+        self._enable();
         let status = self.driver.status();
         if status.txe() {
+            if !self.sync && !self._tx_sync() {
+                return Err(WouldBlock);
+            }
             // having this check before give a chance to optimizer to remove bound checking on
             // array access
             if self.transfer_count >= self.frame.as_ref().len() as u8 {
@@ -620,6 +1213,51 @@ where
         }
         Err(WouldBlock)
     }
+
+    /// Transmit (blocking) all frames of a slice.
+    pub fn write_all<T: ToRawFrame<STD, FMT>>(&mut self, frames: &[T]) {
+        self.write_iter(frames)
+    }
+
+    /// Write frames from a slice until it would block, and return how many frames were written.
+    ///
+    /// Like with `write`, this function need to be called again to fully transmit the last frame.
+    pub fn write_some<T: ToRawFrame<STD, FMT>>(&mut self, frames: &[T]) -> usize {
+        let mut count = 0;
+        while count < frames.len() && self.write(&frames[count]).is_ok() {
+            count += 1;
+        }
+        count
+    }
+
+    /// Transmit (blocking) all samples of an interleaved buffer.
+    ///
+    /// # Panics
+    ///
+    /// `samples.len()` must be a multiple of the number of channels.
+    pub fn write_all_interleaved<S: InterleavedSample<STD, FMT>>(&mut self, samples: &[S]) {
+        self.write_iter(_interleaved_frames(samples))
+    }
+
+    /// Write samples from an interleaved buffer until it would block, and return how many
+    /// samples were written.
+    ///
+    /// # Panics
+    ///
+    /// `samples.len()` must be a multiple of the number of channels.
+    pub fn write_some_interleaved<S: InterleavedSample<STD, FMT>>(
+        &mut self,
+        samples: &[S],
+    ) -> usize {
+        let mut count = 0;
+        for frame in _interleaved_frames(samples) {
+            if self.write(frame).is_err() {
+                break;
+            }
+            count += S::CHANNELS;
+        }
+        count
+    }
 }
 
 /// Slave Transmit
@@ -636,10 +1274,16 @@ where
         ITER: IntoIterator<Item = T>,
     {
         let mut frames = frames.into_iter();
+        // set when a resync failed, the frame already taken from the iterator is sent again
+        let mut resend = false;
         loop {
             if self.sync {
                 let status = self.driver.status();
-                if status.txe() {
+                // errors are checked first since reading the status clears them
+                if status.fre() || status.udr() {
+                    self.sync = false;
+                    self.driver.disable();
+                } else if status.txe() {
                     // having this check before give a chance to optimizer to remove bound checking on
                     // array access
                     if self.transfer_count >= self.frame.as_ref().len() as u8 {
@@ -657,24 +1301,23 @@ where
                         .write_data_register(self.frame.as_ref()[self.transfer_count as usize]);
                     self.transfer_count += 1;
                 }
-                if status.fre() || status.udr() {
-                    self.sync = false;
-                    self.driver.disable();
-                }
             } else if !self._ws_is_start() {
+                if !resend {
+                    let frm = frames.next();
+                    //breaking here ensure the last frame is fully transmitted
+                    if frm.is_none() {
+                        break;
+                    }
+                    self.frame = frm.unwrap().to_raw();
+                }
                 // data register may (or not) already contain data, causing uncertainty about next
                 // time txe flag is set. Writing it remove the uncertainty.
-                let frm = frames.next();
-                //breaking here ensure the last frame is fully transmitted
-                if frm.is_none() {
-                    break;
-                }
-                self.frame = frm.unwrap().to_raw();
                 self.driver.write_data_register(self.frame.as_ref()[0]);
                 self.transfer_count = 1;
                 self.driver.enable();
                 // ensure the ws line didn't change during sync process
-                if !self._ws_is_start() {
+                resend = self._ws_is_start();
+                if !resend {
                     self.sync = true;
                 } else {
                     self.driver.disable();
@@ -690,6 +1333,12 @@ where
     pub fn write<T: ToRawFrame<STD, FMT>>(&mut self, frame: T) -> nb::Result<(), Infallible> {
         if self.sync {
             let status = self.driver.status();
+            // errors are checked first since reading the status clears them
+            if status.fre() || status.udr() {
+                self.sync = false;
+                self.driver.disable();
+                return Err(WouldBlock);
+            }
             if status.txe() {
                 // having this check before give a chance to optimizer to remove bound checking on
                 // array access
@@ -708,34 +1357,96 @@ where
                     self.transfer_count += 1;
                 }
             }
-            if status.fre() || status.udr() {
-                self.sync = false;
-                self.driver.disable();
-            }
         } else if !self._ws_is_start() {
             // data register may (or not) already contain data, causing uncertainty about next
             // time txe flag is set. Writing it remove the uncertainty.
+            self.frame = frame.to_raw();
             self.driver.write_data_register(self.frame.as_ref()[0]);
             self.transfer_count = 1;
             self.driver.enable();
             // ensure the ws line didn't change during sync process
             if !self._ws_is_start() {
                 self.sync = true;
-            } else {
-                self.driver.disable();
+                return Ok(());
             }
-            return Ok(());
+            self.driver.disable();
         }
         Err(WouldBlock)
     }
+
+    /// Transmit (blocking) all frames of a slice.
+    pub fn write_all<T: ToRawFrame<STD, FMT>>(&mut self, frames: &[T]) {
+        self.write_iter(frames)
+    }
+
+    /// Write frames from a slice until it would block, and return how many frames were written.
+    ///
+    /// Like with `write`, this function need to be called again to fully transmit the last frame.
+    pub fn write_some<T: ToRawFrame<STD, FMT>>(&mut self, frames: &[T]) -> usize {
+        let mut count = 0;
+        while count < frames.len() && self.write(&frames[count]).is_ok() {
+            count += 1;
+        }
+        count
+    }
+
+    /// Transmit (blocking) all samples of an interleaved buffer.
+    ///
+    /// # Panics
+    ///
+    /// `samples.len()` must be a multiple of the number of channels.
+    pub fn write_all_interleaved<S: InterleavedSample<STD, FMT>>(&mut self, samples: &[S]) {
+        self.write_iter(_interleaved_frames(samples))
+    }
+
+    /// Write samples from an interleaved buffer until it would block, and return how many
+    /// samples were written.
+    ///
+    /// # Panics
+    ///
+    /// `samples.len()` must be a multiple of the number of channels.
+    pub fn write_some_interleaved<S: InterleavedSample<STD, FMT>>(
+        &mut self,
+        samples: &[S],
+    ) -> usize {
+        let mut count = 0;
+        for frame in _interleaved_frames(samples) {
+            if self.write(frame).is_err() {
+                break;
+            }
+            count += S::CHANNELS;
+        }
+        count
+    }
 }
 
 /// Master Receive
 impl<I, STD, FMT> I2sTransfer<I, Master, Receive, STD, FMT>
 where
     I: I2sPeripheral,
+    STD: I2sStandard,
     (STD, FMT): FrameFormat,
 {
+    /// Handle a word received while not synchronized. Return `true` when this word is the first
+    /// word of a left channel, otherwise it should be discarded.
+    ///
+    /// While not synchronized, `transfer_count` is non-zero once a right channel word has been
+    /// received.
+    fn _rx_sync(&mut self, right: bool) -> bool {
+        if _has_channels::<STD>() {
+            if right {
+                self.transfer_count = 1;
+                return false;
+            }
+            if self.transfer_count == 0 {
+                return false;
+            }
+        }
+        self.sync = true;
+        self.transfer_count = 0;
+        true
+    }
+
     /// Read samples while predicate return `true`.
     ///
     /// The given closure must not block, otherwise communication problems may occur.
@@ -744,22 +1455,24 @@ where
         T: FromRawFrame<STD, FMT>,
         F: FnMut(T) -> bool,
     {
-        self.driver.enable();
+        self._enable();
         loop {
             let status = self.driver.status();
             if status.rxne() {
-                if self.transfer_count >= self.frame.as_ref().len() as u8 {
-                    self.transfer_count = 0;
-                }
-                self.frame.as_mut()[self.transfer_count as usize] =
-                    self.driver.read_data_register();
-                self.transfer_count += 1;
+                let data = self.driver.read_data_register();
+                if self.sync || self._rx_sync(status.chside_bit()) {
+                    if self.transfer_count >= self.frame.as_ref().len() as u8 {
+                        self.transfer_count = 0;
+                    }
+                    self.frame.as_mut()[self.transfer_count as usize] = data;
+                    self.transfer_count += 1;
 
-                // note: boolean operators are short-circuiting
-                if self.transfer_count >= self.frame.as_ref().len() as u8
-                    && !predicate(T::from_raw(self.frame))
-                {
-                    return Ok(());
+                    // note: boolean operators are short-circuiting
+                    if self.transfer_count >= self.frame.as_ref().len() as u8
+                        && !predicate(T::from_raw(self.frame))
+                    {
+                        return Ok(());
+                    }
                 }
             }
             if status.ovr() {
@@ -774,17 +1487,20 @@ where
     /// To get the audio frame, this function needs to be continuously called until the frame is
     /// returned
     pub fn read<T: FromRawFrame<STD, FMT>>(&mut self) -> nb::Result<T, I2sTransferError> {
-        self.driver.enable();
+        self._enable();
         let status = self.driver.status();
         if status.rxne() {
-            if self.transfer_count >= self.frame.as_ref().len() as u8 {
-                self.transfer_count = 0;
-            }
-            self.frame.as_mut()[self.transfer_count as usize] = self.driver.read_data_register();
-            self.transfer_count += 1;
+            let data = self.driver.read_data_register();
+            if self.sync || self._rx_sync(status.chside_bit()) {
+                if self.transfer_count >= self.frame.as_ref().len() as u8 {
+                    self.transfer_count = 0;
+                }
+                self.frame.as_mut()[self.transfer_count as usize] = data;
+                self.transfer_count += 1;
 
-            if self.transfer_count >= self.frame.as_ref().len() as u8 {
-                return Ok(T::from_raw(self.frame));
+                if self.transfer_count >= self.frame.as_ref().len() as u8 {
+                    return Ok(T::from_raw(self.frame));
+                }
             }
         }
         if status.ovr() {
@@ -793,6 +1509,118 @@ where
         }
         Err(WouldBlock)
     }
+
+    /// Blocking iterator over received frames.
+    ///
+    /// This is [`AudioSource::frames()`] for a given frame type.
+    pub fn frames<T: FromRawFrame<STD, FMT>>(&mut self) -> Frames<'_, Self, T> {
+        Frames::new(self)
+    }
+
+    /// Receive (blocking) frames until a slice is filled.
+    ///
+    /// On overrun, the transfer is ended and the error is returned with the number of frames read
+    /// before, the slice being partially filled.
+    pub fn read_exact<T: FromRawFrame<STD, FMT>>(
+        &mut self,
+        frames: &mut [T],
+    ) -> Result<(), (usize, I2sTransferError)> {
+        let mut count = 0;
+        let mut frames = frames.iter_mut();
+        let mut next = match frames.next() {
+            Some(next) => next,
+            None => return Ok(()),
+        };
+        self.read_while(|frame| {
+            *next = frame;
+            count += 1;
+            match frames.next() {
+                Some(frame) => {
+                    next = frame;
+                    true
+                }
+                None => false,
+            }
+        })
+        .map_err(|error| (count, error))
+    }
+
+    /// Read frames into a slice until it would block, and return how many frames were read.
+    ///
+    /// On overrun, the transfer is ended and the error is returned with the number of frames read
+    /// before.
+    pub fn read_some<T: FromRawFrame<STD, FMT>>(
+        &mut self,
+        frames: &mut [T],
+    ) -> Result<usize, (usize, I2sTransferError)> {
+        let mut count = 0;
+        for frame in frames.iter_mut() {
+            match self.read() {
+                Ok(read) => *frame = read,
+                Err(WouldBlock) => break,
+                Err(nb::Error::Other(error)) => return Err((count, error)),
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Receive (blocking) samples until an interleaved buffer is filled.
+    ///
+    /// On overrun, the transfer is ended and the error is returned with the number of samples read
+    /// before, the buffer being partially filled.
+    ///
+    /// # Panics
+    ///
+    /// `samples.len()` must be a multiple of the number of channels.
+    pub fn read_exact_interleaved<S: InterleavedSample<STD, FMT>>(
+        &mut self,
+        samples: &mut [S],
+    ) -> Result<(), (usize, I2sTransferError)> {
+        let mut count = 0;
+        let mut chunks = _interleaved_chunks(samples);
+        let mut next = match chunks.next() {
+            Some(next) => next,
+            None => return Ok(()),
+        };
+        self.read_while(|Raw(raw)| {
+            S::from_raw(raw, next);
+            count += S::CHANNELS;
+            match chunks.next() {
+                Some(chunk) => {
+                    next = chunk;
+                    true
+                }
+                None => false,
+            }
+        })
+        .map_err(|error| (count, error))
+    }
+
+    /// Read samples into an interleaved buffer until it would block, and return how many samples
+    /// were read.
+    ///
+    /// On overrun, the transfer is ended and the error is returned with the number of samples read
+    /// before.
+    ///
+    /// # Panics
+    ///
+    /// `samples.len()` must be a multiple of the number of channels.
+    pub fn read_some_interleaved<S: InterleavedSample<STD, FMT>>(
+        &mut self,
+        samples: &mut [S],
+    ) -> Result<usize, (usize, I2sTransferError)> {
+        let mut count = 0;
+        for chunk in _interleaved_chunks(samples) {
+            match self.read() {
+                Ok(Raw(raw)) => S::from_raw(raw, chunk),
+                Err(WouldBlock) => break,
+                Err(nb::Error::Other(error)) => return Err((count, error)),
+            }
+            count += S::CHANNELS;
+        }
+        Ok(count)
+    }
 }
 
 impl<I, STD, FMT> I2sTransfer<I, Slave, Receive, STD, FMT>
@@ -812,7 +1640,11 @@ where
         loop {
             if self.sync {
                 let status = self.driver.status();
-                if status.rxne() {
+                // errors are checked first, the frame being received is corrupted anyway
+                if status.fre() || status.ovr() {
+                    self.sync = false;
+                    self.driver.disable();
+                } else if status.rxne() {
                     if self.transfer_count >= self.frame.as_ref().len() as u8 {
                         self.transfer_count = 0;
                     }
@@ -827,15 +1659,11 @@ where
                         return;
                     }
                 }
-                if status.fre() || status.ovr() {
-                    self.sync = false;
-                    self.driver.read_data_register();
-                    self.driver.status();
-                    self.driver.disable();
-                }
             } else if !self._ws_is_start() {
                 self.transfer_count = 0;
                 self.driver.enable();
+                self.driver.read_data_register();
+                self.driver.status();
                 // ensure the ws line didn't change during sync process
                 if !self._ws_is_start() {
                     self.sync = true;
@@ -857,6 +1685,12 @@ where
         }
         if self.sync {
             let status = self.driver.status();
+            // errors are checked first, the frame being received is corrupted anyway
+            if status.fre() || status.ovr() {
+                self.sync = false;
+                self.driver.disable();
+                return Err(WouldBlock);
+            }
             if status.rxne() {
                 if self.transfer_count >= self.frame.as_ref().len() as u8 {
                     self.transfer_count = 0;
@@ -870,12 +1704,6 @@ where
                     return Ok(T::from_raw(self.frame));
                 }
             }
-            if status.fre() || status.ovr() {
-                self.sync = false;
-                //self.driver.read_data_register();
-                //self.driver.status();
-                self.driver.disable();
-            }
         } else if !self._ws_is_start() {
             self.transfer_count = 0;
             self.driver.enable();
@@ -890,4 +1718,1045 @@ where
         }
         Err(WouldBlock)
     }
+
+    /// Blocking iterator over received frames.
+    ///
+    /// This is [`AudioSource::frames()`] for a given frame type.
+    pub fn frames<T: FromRawFrame<STD, FMT>>(&mut self) -> Frames<'_, Self, T> {
+        Frames::new(self)
+    }
+
+    /// Receive (blocking) frames until a slice is filled.
+    pub fn read_exact<T: FromRawFrame<STD, FMT>>(&mut self, frames: &mut [T]) {
+        let mut frames = frames.iter_mut();
+        let mut next = match frames.next() {
+            Some(next) => next,
+            None => return,
+        };
+        self.read_while(|frame| {
+            *next = frame;
+            match frames.next() {
+                Some(frame) => {
+                    next = frame;
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    /// Read frames into a slice until it would block, and return how many frames were read.
+    pub fn read_some<T: FromRawFrame<STD, FMT>>(&mut self, frames: &mut [T]) -> usize {
+        let mut count = 0;
+        for frame in frames.iter_mut() {
+            match self.read() {
+                Ok(read) => *frame = read,
+                Err(_) => break,
+            }
+            count += 1;
+        }
+        count
+    }
+
+    /// Receive (blocking) samples until an interleaved buffer is filled.
+    ///
+    /// # Panics
+    ///
+    /// `samples.len()` must be a multiple of the number of channels.
+    pub fn read_exact_interleaved<S: InterleavedSample<STD, FMT>>(&mut self, samples: &mut [S]) {
+        let mut chunks = _interleaved_chunks(samples);
+        let mut next = match chunks.next() {
+            Some(next) => next,
+            None => return,
+        };
+        self.read_while(|Raw(raw)| {
+            S::from_raw(raw, next);
+            match chunks.next() {
+                Some(chunk) => {
+                    next = chunk;
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    /// Read samples into an interleaved buffer until it would block, and return how many samples
+    /// were read.
+    ///
+    /// # Panics
+    ///
+    /// `samples.len()` must be a multiple of the number of channels.
+    pub fn read_some_interleaved<S: InterleavedSample<STD, FMT>>(
+        &mut self,
+        samples: &mut [S],
+    ) -> usize {
+        let mut count = 0;
+        for chunk in _interleaved_chunks(samples) {
+            match self.read() {
+                Ok(Raw(raw)) => S::from_raw(raw, chunk),
+                Err(_) => break,
+            }
+            count += S::CHANNELS;
+        }
+        count
+    }
+}
+
+impl<I, STD, FMT, T> AudioSink<T> for I2sTransfer<I, Master, Transmit, STD, FMT>
+where
+    I: I2sPeripheral,
+    STD: I2sStandard,
+    (STD, FMT): FrameFormat,
+    T: ToRawFrame<STD, FMT>,
+{
+    type Error = Infallible;
+
+    fn write_frame(&mut self, frame: T) -> nb::Result<(), Infallible> {
+        self.write(frame)
+    }
+}
+
+impl<I, STD, FMT, T> AudioSink<T> for I2sTransfer<I, Slave, Transmit, STD, FMT>
+where
+    I: I2sPeripheral,
+    STD: I2sStandard,
+    (STD, FMT): FrameFormat,
+    T: ToRawFrame<STD, FMT>,
+{
+    type Error = Infallible;
+
+    fn write_frame(&mut self, frame: T) -> nb::Result<(), Infallible> {
+        self.write(frame)
+    }
+}
+
+impl<I, STD, FMT, T> AudioSource<T> for I2sTransfer<I, Master, Receive, STD, FMT>
+where
+    I: I2sPeripheral,
+    STD: I2sStandard,
+    (STD, FMT): FrameFormat,
+    T: FromRawFrame<STD, FMT>,
+{
+    type Error = I2sTransferError;
+
+    fn read_frame(&mut self) -> nb::Result<T, I2sTransferError> {
+        self.read()
+    }
+}
+
+/// Slave receive never fails, the error type is the one of master receive so application code
+/// gets the same frames in both modes.
+impl<I, STD, FMT, T> AudioSource<T> for I2sTransfer<I, Slave, Receive, STD, FMT>
+where
+    I: I2sPeripheral,
+    STD: I2sStandard,
+    (STD, FMT): FrameFormat,
+    T: FromRawFrame<STD, FMT>,
+{
+    type Error = I2sTransferError;
+
+    fn read_frame(&mut self) -> nb::Result<T, I2sTransferError> {
+        self.read().map_err(|_| WouldBlock)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{BusConfig, SimI2sA, SimI2sB, VirtualBus};
+    use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+    struct FakeWsPin(bool);
+
+    impl WsPin for FakeWsPin {
+        fn is_high(&self) -> bool {
+            self.0
+        }
+        fn is_low(&self) -> bool {
+            !self.0
+        }
+    }
+
+    /// Define a fake peripheral with its own register block and a WS level given by an expression.
+    macro_rules! fake_peripheral {
+        ($name:ident, $registers:ident, $ws:expr) => {
+            struct $name;
+            static mut $registers: [u32; 9] = [0; 9];
+
+            unsafe impl I2sPeripheral for $name {
+                type WsPin = FakeWsPin;
+                const REGISTERS: *const () = core::ptr::addr_of!($registers) as *const ();
+                fn i2s_freq(&self) -> u32 {
+                    86_000_000
+                }
+                fn ws_pin(&self) -> &Self::WsPin {
+                    match $ws {
+                        true => &FakeWsPin(true),
+                        false => &FakeWsPin(false),
+                    }
+                }
+                fn ws_pin_mut(&mut self) -> &mut Self::WsPin {
+                    unimplemented!()
+                }
+                fn rcc_reset(&mut self) {}
+            }
+        };
+    }
+
+    static SWITCHED_WS: AtomicBool = AtomicBool::new(false);
+    static GOLDEN_WS: AtomicBool = AtomicBool::new(false);
+
+    fake_peripheral!(WsLowPeripheral, REGISTERS_WS_LOW, false);
+    fake_peripheral!(WsHighPeripheral, REGISTERS_WS_HIGH, true);
+    fake_peripheral!(
+        SwitchedWsPeripheral,
+        REGISTERS_SWITCHED_WS,
+        SWITCHED_WS.load(Ordering::Relaxed)
+    );
+    fake_peripheral!(RxPeripheral, REGISTERS_RX, false);
+    fake_peripheral!(
+        GoldenPeripheral,
+        REGISTERS_GOLDEN,
+        GOLDEN_WS.load(Ordering::Relaxed)
+    );
+
+    const SR: usize = 2;
+    const DR: usize = 3;
+    const RXNE: u32 = 1 << 0;
+    const TXE: u32 = 1 << 1;
+    const CHSIDE: u32 = 1 << 2;
+    const UDR: u32 = 1 << 3;
+    const OVR: u32 = 1 << 6;
+    const I2SCFGR: usize = 7;
+    const I2SE: u32 = 1 << 10;
+
+    /// Write `frame` at successive TXE events with the given WS levels. Return the index of the
+    /// event where the first word of the frame is written, only padding being written before.
+    fn transmit_start<STD, FMT, T>(
+        standard: STD,
+        data_format: FMT,
+        frame: T,
+        ws: &[bool],
+    ) -> Option<usize>
+    where
+        STD: I2sStandard,
+        FMT: DataFormat,
+        (STD, FMT): FrameFormat,
+        T: ToRawFrame<STD, FMT> + Copy,
+    {
+        let mut transfer = I2sTransferConfig::new_master()
+            .standard(standard)
+            .data_format(data_format)
+            .i2s_transfer(SwitchedWsPeripheral);
+        unsafe { REGISTERS_SWITCHED_WS[SR] = TXE };
+        for (i, &level) in ws.iter().enumerate() {
+            SWITCHED_WS.store(level, Ordering::Relaxed);
+            if transfer.write(frame).is_ok() {
+                return Some(i);
+            }
+            assert_eq!(unsafe { REGISTERS_SWITCHED_WS[DR] }, 0);
+        }
+        None
+    }
+
+    #[test]
+    fn test_master_transmit_sync() {
+        // Philips: WS low is the left channel. WS isn't trusted at enable, so a left word must be
+        // seen before the right channel.
+        let ws = [true, false, true];
+        assert_eq!(
+            transmit_start(Philips, Data16Channel16, (0x1234, 0), &ws),
+            Some(2)
+        );
+        assert_eq!(unsafe { REGISTERS_SWITCHED_WS[DR] }, 0x1234);
+
+        // Msb: WS high is the left channel. With 32 bit channels, the last word of the right
+        // channel is being sent at the second TXE event with WS low after a left channel word.
+        let ws = [false, true, false, false];
+        let frame = (0x1234_5678, 0);
+        assert_eq!(transmit_start(Msb, Data32Channel32, frame, &ws), Some(3));
+        assert_eq!(unsafe { REGISTERS_SWITCHED_WS[DR] }, 0x1234);
+
+        // Lsb: WS high is the left channel, 16 bit data are sent in one word
+        let ws = [false, true, true, false];
+        assert_eq!(
+            transmit_start(Lsb, Data16Channel32, (0x1234, 0), &ws),
+            Some(3)
+        );
+        assert_eq!(unsafe { REGISTERS_SWITCHED_WS[DR] }, 0x1234);
+
+        // Philips: WS never shows both channels, so only padding is written
+        let mut transfer = I2sTransferConfig::new_master()
+            .data_format(Data16Channel16)
+            .i2s_transfer(WsHighPeripheral);
+        unsafe { REGISTERS_WS_HIGH[SR] = TXE };
+        let mut transfer_low = I2sTransferConfig::new_master()
+            .data_format(Data16Channel16)
+            .i2s_transfer(WsLowPeripheral);
+        unsafe { REGISTERS_WS_LOW[SR] = TXE };
+        for _ in 0..4 {
+            assert!(transfer.write((0x1234, 0)).is_err());
+            assert_eq!(unsafe { REGISTERS_WS_HIGH[DR] }, 0);
+            assert!(transfer_low.write((0x1234, 0)).is_err());
+            assert_eq!(unsafe { REGISTERS_WS_LOW[DR] }, 0);
+        }
+    }
+
+    #[test]
+    fn test_master_receive_sync() {
+        let mut transfer = I2sTransferConfig::new_master()
+            .receive()
+            .data_format(Data16Channel16)
+            .i2s_transfer(RxPeripheral);
+        let mut receive = |chside, data| {
+            unsafe {
+                REGISTERS_RX[SR] = RXNE | chside;
+                REGISTERS_RX[DR] = data;
+            }
+            transfer.read::<(i16, i16)>().ok()
+        };
+        // a left word is discarded until a right word is received
+        assert_eq!(receive(0, 1), None);
+        assert_eq!(receive(CHSIDE, 2), None);
+        assert_eq!(receive(0, 3), None);
+        assert_eq!(receive(CHSIDE, 4), Some((3, 4)));
+        assert_eq!(receive(0, 5), None);
+        assert_eq!(receive(CHSIDE, 6), Some((5, 6)));
+
+        // Msb with 32 bit channels: both words of the right channel are discarded
+        let mut transfer = I2sTransferConfig::new_master()
+            .receive()
+            .standard(Msb)
+            .data_format(Data32Channel32)
+            .i2s_transfer(RxPeripheral);
+        let mut receive = |chside, data| {
+            unsafe {
+                REGISTERS_RX[SR] = RXNE | chside;
+                REGISTERS_RX[DR] = data;
+            }
+            transfer.read::<(i32, i32)>().ok()
+        };
+        for &(chside, data) in [(0, 1), (0, 2), (CHSIDE, 3), (CHSIDE, 4)].iter() {
+            assert_eq!(receive(chside, data), None);
+        }
+        assert_eq!(receive(0, 5), None);
+        assert_eq!(receive(0, 6), None);
+        assert_eq!(receive(CHSIDE, 7), None);
+        assert_eq!(receive(CHSIDE, 8), Some((0x0005_0006, 0x0007_0008)));
+
+        // Lsb: starting on a right word, the next word starts the frame
+        let mut transfer = I2sTransferConfig::new_master()
+            .receive()
+            .standard(Lsb)
+            .data_format(Data16Channel32)
+            .i2s_transfer(RxPeripheral);
+        let mut receive = |chside, data| {
+            unsafe {
+                REGISTERS_RX[SR] = RXNE | chside;
+                REGISTERS_RX[DR] = data;
+            }
+            transfer.read::<(i16, i16)>().ok()
+        };
+        assert_eq!(receive(CHSIDE, 1), None);
+        assert_eq!(receive(0, 2), None);
+        assert_eq!(receive(CHSIDE, 3), Some((2, 3)));
+    }
+
+    fake_peripheral!(OverrunPeripheral, REGISTERS_OVERRUN, false);
+
+    #[test]
+    fn test_read_overrun() {
+        let set_registers = |sr, dr| unsafe {
+            REGISTERS_OVERRUN[SR] = sr;
+            REGISTERS_OVERRUN[DR] = dr;
+        };
+        let mut transfer = I2sTransferConfig::new_master()
+            .receive()
+            .data_format(Data16Channel16)
+            .i2s_transfer(OverrunPeripheral);
+        // synchronize and receive a left word
+        for &(sr, dr) in [(RXNE | CHSIDE, 0), (RXNE, 1)].iter() {
+            set_registers(sr, dr);
+            transfer.read::<(i16, i16)>().unwrap_err();
+        }
+        // the frame is completed, then an overrun happens on the next word
+        set_registers(RXNE | CHSIDE | OVR, 2);
+        let mut frames = [(0i16, 0i16); 4];
+        let res = transfer.read_some(&mut frames);
+        assert_eq!(res, Err((1, I2sTransferError::Overrun)));
+        assert_eq!(frames[0], (1, 2));
+
+        for &(sr, dr) in [(RXNE | CHSIDE, 0), (RXNE, 3)].iter() {
+            set_registers(sr, dr);
+            transfer.read::<(i16, i16)>().unwrap_err();
+        }
+        set_registers(RXNE | CHSIDE | OVR, 4);
+        let mut samples = [0i16; 8];
+        let res = transfer.read_some_interleaved(&mut samples);
+        assert_eq!(res, Err((2, I2sTransferError::Overrun)));
+        assert_eq!(samples[..2], [3, 4]);
+
+        // blocking reads report the count the same way
+        for &(sr, dr) in [(RXNE | CHSIDE, 0), (RXNE, 5)].iter() {
+            set_registers(sr, dr);
+            transfer.read::<(i16, i16)>().unwrap_err();
+        }
+        set_registers(RXNE | CHSIDE | OVR, 6);
+        let res = transfer.read_exact(&mut frames);
+        assert_eq!(res, Err((1, I2sTransferError::Overrun)));
+        assert_eq!(frames[0], (5, 6));
+
+        for &(sr, dr) in [(RXNE | CHSIDE, 0), (RXNE, 7)].iter() {
+            set_registers(sr, dr);
+            transfer.read::<(i16, i16)>().unwrap_err();
+        }
+        set_registers(RXNE | CHSIDE | OVR, 8);
+        let res = transfer.read_exact_interleaved(&mut samples);
+        assert_eq!(res, Err((2, I2sTransferError::Overrun)));
+        assert_eq!(samples[..2], [7, 8]);
+    }
+
+    #[test]
+    fn test_interleaved() {
+        let raw = <i16 as InterleavedSample<Philips, Data16Channel32>>::to_raw(&[1, -1]);
+        assert_eq!(raw, [1, 0xFFFF]);
+        let raw = <i32 as InterleavedSample<Msb, Data32Channel32>>::to_raw(&[0x1234_5678, -2]);
+        assert_eq!(raw, [0x1234, 0x5678, 0xFFFF, 0xFFFE]);
+        let mut samples = [0i32; 1];
+        <i32 as InterleavedSample<PcmLongSync, Data32Channel32>>::from_raw(
+            [0x8000, 1],
+            &mut samples,
+        );
+        assert_eq!(samples, [-0x7FFF_FFFF]);
+
+        let bus = VirtualBus::new(BusConfig::new());
+        bus.install();
+        let mut transmitter = I2sTransferConfig::new_master().i2s_transfer(SimI2sA::new(&bus));
+        let mut receiver = I2sTransferConfig::new_slave()
+            .receive()
+            .i2s_transfer(SimI2sB::new(&bus));
+        let samples: std::vec::Vec<i16> =
+            (1..=64).map(|i| if i % 2 == 0 { -i } else { i }).collect();
+        let mut received = [0i16; 16];
+        let (mut written, mut read) = (0, 0);
+        while read < received.len() {
+            written += transmitter.write_some_interleaved(&samples[written..]);
+            read += receiver.read_some_interleaved(&mut received[read..]);
+        }
+        // the receiver syncs on a later frame than the first one
+        let start = samples.iter().position(|&s| s == received[0]).unwrap();
+        assert_eq!(received, samples[start..start + 16]);
+        assert!(written % 2 == 0);
+    }
+
+    #[test]
+    fn test_write_some() {
+        // Philips with WS low: only padding is written
+        let mut transfer = I2sTransferConfig::new_master()
+            .data_format(Data16Channel16)
+            .i2s_transfer(WsLowPeripheral);
+        unsafe { REGISTERS_WS_LOW[SR] = TXE };
+        assert_eq!(transfer.write_some(&[(1i16, 2i16); 4]), 0);
+        assert_eq!(transfer.write_some::<(i16, i16)>(&[]), 0);
+    }
+
+    #[test]
+    fn test_golden_master_transmit() {
+        use std::string::ToString;
+        let mut transfer = I2sTransferConfig::new_master()
+            .data_format(Data16Channel16)
+            .i2s_transfer(GoldenPeripheral);
+        unsafe { REGISTERS_GOLDEN[SR] = TXE };
+        crate::trace::start_recording();
+        GOLDEN_WS.store(false, Ordering::Relaxed);
+        transfer.write((0x1234, 0x5678)).unwrap_err();
+        GOLDEN_WS.store(true, Ordering::Relaxed);
+        transfer.write((0x1234, 0x5678)).unwrap();
+        transfer.write((0, 0)).unwrap_err();
+        transfer.end();
+        let records: std::vec::Vec<_> = crate::trace::stop_recording()
+            .iter()
+            .map(|record| record.to_string())
+            .collect();
+        let expected = [
+            // enable, then WS low is the left channel, padding is sent
+            "modify I2SCFGR 0x00000a00 -> 0x00000e00 [I2SCFG=2 I2SE I2SMOD]",
+            "read SR 0x00000002 [TXE]",
+            "write DR 0x00000000 []",
+            // WS high is the right channel so the next word is the left one
+            "modify I2SCFGR 0x00000e00 -> 0x00000e00 [I2SCFG=2 I2SE I2SMOD]",
+            "read SR 0x00000002 [TXE]",
+            "write DR 0x00001234 [DR=0x1234]",
+            "modify I2SCFGR 0x00000e00 -> 0x00000e00 [I2SCFG=2 I2SE I2SMOD]",
+            "read SR 0x00000002 [TXE]",
+            "write DR 0x00005678 [DR=0x5678]",
+            // end: disable and reset clocks
+            "modify I2SCFGR 0x00000e00 -> 0x00000a00 [I2SCFG=2 I2SMOD]",
+            "read CR2 0x00000000 []",
+            "read I2SCFGR 0x00000a00 [I2SCFG=2 I2SMOD]",
+            "read I2SPR 0x00000002 [I2SDIV=2]",
+            "write CR2 0x00000000 []",
+            "write I2SPR 0x00000002 [I2SDIV=2]",
+            "write I2SCFGR 0x00000a00 [I2SCFG=2 I2SMOD]",
+        ];
+        assert_eq!(records, expected);
+    }
+
+    fake_peripheral!(GoldenRxPeripheral, REGISTERS_GOLDEN_RX, false);
+
+    #[test]
+    fn test_golden_master_receive() {
+        use std::string::ToString;
+        let mut transfer = I2sTransferConfig::new_master()
+            .receive()
+            .data_format(Data16Channel16)
+            .i2s_transfer(GoldenRxPeripheral);
+        let mut receive = |sr, data| {
+            unsafe {
+                REGISTERS_GOLDEN_RX[SR] = sr;
+                REGISTERS_GOLDEN_RX[DR] = data;
+            }
+            transfer.read::<(i16, i16)>()
+        };
+        crate::trace::start_recording();
+        receive(RXNE | CHSIDE, 1).unwrap_err();
+        receive(RXNE, 0x1234).unwrap_err();
+        assert_eq!(receive(RXNE | CHSIDE, 0x5678), Ok((0x1234, 0x5678)));
+        let res = receive(OVR, 0);
+        assert_eq!(res, Err(nb::Error::Other(I2sTransferError::Overrun)));
+        let records: std::vec::Vec<_> = crate::trace::stop_recording()
+            .iter()
+            .map(|record| record.to_string())
+            .collect();
+        let expected = [
+            // a right word is discarded while not synchronized
+            "modify I2SCFGR 0x00000b00 -> 0x00000f00 [I2SCFG=3 I2SE I2SMOD]",
+            "read SR 0x00000005 [RXNE CHSIDE]",
+            "read DR 0x00000001 [DR=0x0001]",
+            // the next left word starts the frame
+            "modify I2SCFGR 0x00000f00 -> 0x00000f00 [I2SCFG=3 I2SE I2SMOD]",
+            "read SR 0x00000001 [RXNE]",
+            "read DR 0x00001234 [DR=0x1234]",
+            "modify I2SCFGR 0x00000f00 -> 0x00000f00 [I2SCFG=3 I2SE I2SMOD]",
+            "read SR 0x00000005 [RXNE CHSIDE]",
+            "read DR 0x00005678 [DR=0x5678]",
+            // overrun: disable and reset clocks
+            "modify I2SCFGR 0x00000f00 -> 0x00000f00 [I2SCFG=3 I2SE I2SMOD]",
+            "read SR 0x00000040 [OVR]",
+            "modify I2SCFGR 0x00000f00 -> 0x00000b00 [I2SCFG=3 I2SMOD]",
+            "read CR2 0x00000000 []",
+            "read I2SCFGR 0x00000b00 [I2SCFG=3 I2SMOD]",
+            "read I2SPR 0x00000002 [I2SDIV=2]",
+            "write CR2 0x00000000 []",
+            "write I2SPR 0x00000002 [I2SDIV=2]",
+            "write I2SCFGR 0x00000b00 [I2SCFG=3 I2SMOD]",
+        ];
+        assert_eq!(records, expected);
+    }
+
+    static GOLDEN_SLAVE_WS: AtomicBool = AtomicBool::new(false);
+
+    fake_peripheral!(
+        GoldenSlavePeripheral,
+        REGISTERS_GOLDEN_SLAVE,
+        GOLDEN_SLAVE_WS.load(Ordering::Relaxed)
+    );
+
+    #[test]
+    fn test_golden_slave() {
+        use std::string::ToString;
+        let records = || -> std::vec::Vec<_> {
+            crate::trace::stop_recording()
+                .iter()
+                .map(|record| record.to_string())
+                .collect()
+        };
+        let set_sr = |sr| unsafe { REGISTERS_GOLDEN_SLAVE[SR] = sr };
+        let mut transfer = I2sTransferConfig::new_slave()
+            .data_format(Data16Channel16)
+            .i2s_transfer(GoldenSlavePeripheral);
+        crate::trace::start_recording();
+        GOLDEN_SLAVE_WS.store(false, Ordering::Relaxed);
+        transfer.write((0x1234, 0x5678)).unwrap_err();
+        GOLDEN_SLAVE_WS.store(true, Ordering::Relaxed);
+        transfer.write((0x1234, 0x5678)).unwrap();
+        set_sr(TXE);
+        transfer.write((0x4321, 0x5678)).unwrap_err();
+        transfer.write((0x4321, 0x5678)).unwrap();
+        set_sr(TXE | UDR);
+        transfer.write((0, 0)).unwrap_err();
+        let expected = [
+            // WS low is the left channel, nothing is done until WS is high. Then the first word
+            // is written before enabling the peripheral
+            "write DR 0x00001234 [DR=0x1234]",
+            "modify I2SCFGR 0x00000800 -> 0x00000c00 [I2SE I2SMOD]",
+            "read SR 0x00000002 [TXE]",
+            "write DR 0x00005678 [DR=0x5678]",
+            "read SR 0x00000002 [TXE]",
+            "write DR 0x00004321 [DR=0x4321]",
+            // underrun: disable until next synchronization
+            "read SR 0x0000000a [TXE UDR]",
+            "modify I2SCFGR 0x00000c00 -> 0x00000800 [I2SMOD]",
+        ];
+        assert_eq!(records(), expected);
+
+        let mut transfer = I2sTransferConfig::new_slave()
+            .receive()
+            .data_format(Data16Channel16)
+            .i2s_transfer(transfer.release());
+        let mut receive = |sr, data| {
+            unsafe { REGISTERS_GOLDEN_SLAVE[DR] = data };
+            set_sr(sr);
+            transfer.read::<(i16, i16)>()
+        };
+        crate::trace::start_recording();
+        receive(0, 0).unwrap_err();
+        receive(RXNE, 0x1234).unwrap_err();
+        assert_eq!(receive(RXNE | CHSIDE, 0x5678), Ok((0x1234, 0x5678)));
+        receive(OVR, 0).unwrap_err();
+        let expected = [
+            // WS is still high: stale data and flags are cleared at enable
+            "modify I2SCFGR 0x00000900 -> 0x00000900 [I2SCFG=1 I2SMOD]",
+            "modify I2SCFGR 0x00000900 -> 0x00000d00 [I2SCFG=1 I2SE I2SMOD]",
+            "read DR 0x00000000 []",
+            "read SR 0x00000000 []",
+            "read SR 0x00000001 [RXNE]",
+            "read DR 0x00001234 [DR=0x1234]",
+            "read SR 0x00000005 [RXNE CHSIDE]",
+            "read DR 0x00005678 [DR=0x5678]",
+            // overrun: disable until next synchronization
+            "read SR 0x00000040 [OVR]",
+            "modify I2SCFGR 0x00000d00 -> 0x00000900 [I2SCFG=1 I2SMOD]",
+        ];
+        assert_eq!(records(), expected);
+    }
+
+    fake_peripheral!(SlavePeripheral, REGISTERS_SLAVE, true);
+
+    #[test]
+    fn test_slave_errors() {
+        let enabled = || unsafe { REGISTERS_SLAVE[I2SCFGR] } & I2SE != 0;
+        // Philips: WS high is the right channel, so the transmitter synchronizes at once and sends
+        // the given frame
+        let mut transfer = I2sTransferConfig::new_slave()
+            .data_format(Data16Channel16)
+            .i2s_transfer(SlavePeripheral);
+        assert!(transfer.write((1, 2)).is_ok());
+        assert_eq!(unsafe { REGISTERS_SLAVE[DR] }, 1);
+        unsafe { REGISTERS_SLAVE[SR] = TXE };
+        assert!(transfer.write((3, 4)).is_err());
+        assert_eq!(unsafe { REGISTERS_SLAVE[DR] }, 2);
+        // an underrun reported with TXE stops the transfer before accepting a frame
+        unsafe { REGISTERS_SLAVE[SR] = TXE | UDR };
+        assert!(transfer.write((3, 4)).is_err());
+        assert_eq!(unsafe { REGISTERS_SLAVE[DR] }, 2);
+        assert!(!enabled());
+        // then the next frame is sent at resynchronization
+        unsafe { REGISTERS_SLAVE[SR] = 0 };
+        assert!(transfer.write((5, 6)).is_ok());
+        assert_eq!(unsafe { REGISTERS_SLAVE[DR] }, 5);
+        assert!(enabled());
+
+        let mut transfer = I2sTransferConfig::new_slave()
+            .receive()
+            .data_format(Data16Channel16)
+            .i2s_transfer(transfer.release());
+        let mut receive = |sr, data| {
+            unsafe {
+                REGISTERS_SLAVE[SR] = sr;
+                REGISTERS_SLAVE[DR] = data;
+            }
+            transfer.read::<(i16, i16)>().ok()
+        };
+        assert_eq!(receive(0, 0), None);
+        assert_eq!(receive(RXNE, 1), None);
+        assert_eq!(receive(RXNE, 2), Some((1, 2)));
+        // an overrun reported with RXNE drops the frame being received
+        assert_eq!(receive(RXNE, 3), None);
+        assert_eq!(receive(RXNE | OVR, 4), None);
+        assert!(!enabled());
+        assert_eq!(receive(0, 0), None);
+        assert!(enabled());
+        assert_eq!(receive(RXNE, 5), None);
+        assert_eq!(receive(RXNE, 6), Some((5, 6)));
+    }
+
+    /// Backend giving scripted status and data register contents to the driver, so blocking
+    /// methods can be driven through errors.
+    struct Script {
+        sr: core::slice::Iter<'static, u32>,
+        dr: core::slice::Iter<'static, u32>,
+    }
+
+    impl crate::trace::Backend for Script {
+        fn read(&mut self, _: usize, register: crate::trace::Register, value: u32) -> u32 {
+            use crate::trace::Register::*;
+            match register {
+                Sr => *self.sr.next().expect("status script exhausted"),
+                Dr => self.dr.next().copied().unwrap_or(value),
+                _ => value,
+            }
+        }
+    }
+
+    static SCRIPT_WS_READS: AtomicU32 = AtomicU32::new(0);
+
+    // WS is low, the start of a Philips frame, only at the second read
+    fake_peripheral!(
+        ScriptPeripheral,
+        REGISTERS_SCRIPT,
+        SCRIPT_WS_READS.fetch_add(1, Ordering::Relaxed) != 1
+    );
+
+    #[test]
+    fn test_slave_blocking_errors() {
+        use std::string::ToString;
+        let run = |sr: &'static [u32], dr: &'static [u32], f: &mut dyn FnMut()| {
+            crate::trace::set_backend(Some(std::boxed::Box::new(Script {
+                sr: sr.iter(),
+                dr: dr.iter(),
+            })));
+            crate::trace::start_recording();
+            f();
+            crate::trace::set_backend(None);
+            crate::trace::stop_recording()
+                .iter()
+                .map(|record| record.to_string())
+                .collect::<std::vec::Vec<_>>()
+        };
+        let mut transfer = I2sTransferConfig::new_slave()
+            .data_format(Data16Channel16)
+            .i2s_transfer(ScriptPeripheral);
+        let records = run(&[TXE, TXE, TXE, TXE], &[], &mut || {
+            transfer.write_iter([(1, 2), (3, 4)].iter())
+        });
+        let expected = [
+            // WS changed while enabling, the frame is kept for the next synchronization
+            "write DR 0x00000001 [DR=0x0001]",
+            "modify I2SCFGR 0x00000800 -> 0x00000c00 [I2SE I2SMOD]",
+            "modify I2SCFGR 0x00000c00 -> 0x00000800 [I2SMOD]",
+            "write DR 0x00000001 [DR=0x0001]",
+            "modify I2SCFGR 0x00000800 -> 0x00000c00 [I2SE I2SMOD]",
+            "read SR 0x00000002 [TXE]",
+            "write DR 0x00000002 [DR=0x0002]",
+            "read SR 0x00000002 [TXE]",
+            "write DR 0x00000003 [DR=0x0003]",
+            "read SR 0x00000002 [TXE]",
+            "write DR 0x00000004 [DR=0x0004]",
+            "read SR 0x00000002 [TXE]",
+        ];
+        assert_eq!(records, expected);
+        let records = run(&[TXE | UDR, TXE, TXE, TXE, TXE], &[], &mut || {
+            transfer.write_iter([(5, 6), (7, 8)].iter())
+        });
+        let expected = [
+            // the underrun is handled before taking a frame from the iterator
+            "read SR 0x0000000a [TXE UDR]",
+            "modify I2SCFGR 0x00000c00 -> 0x00000800 [I2SMOD]",
+            "write DR 0x00000005 [DR=0x0005]",
+            "modify I2SCFGR 0x00000800 -> 0x00000c00 [I2SE I2SMOD]",
+            "read SR 0x00000002 [TXE]",
+            "write DR 0x00000006 [DR=0x0006]",
+            "read SR 0x00000002 [TXE]",
+            "write DR 0x00000007 [DR=0x0007]",
+            "read SR 0x00000002 [TXE]",
+            "write DR 0x00000008 [DR=0x0008]",
+            "read SR 0x00000002 [TXE]",
+        ];
+        assert_eq!(records, expected);
+        run(&[TXE | UDR], &[], &mut || {
+            assert!(transfer.write((9, 10)).is_err())
+        });
+        // a synchronization failing because of WS doesn't accept the frame
+        SCRIPT_WS_READS.store(0, Ordering::Relaxed);
+        run(&[], &[], &mut || {
+            assert!(transfer.write((9, 10)).is_err());
+            assert!(transfer.write((9, 10)).is_ok());
+        });
+
+        let mut transfer = I2sTransferConfig::new_slave()
+            .receive()
+            .data_format(Data16Channel16)
+            .i2s_transfer(transfer.release());
+        let mut frames = std::vec::Vec::new();
+        let records = run(
+            &[0, RXNE, RXNE | OVR, 0, RXNE, RXNE],
+            &[0, 1, 0, 3, 4],
+            &mut || {
+                transfer.read_while(|frame: (i16, i16)| {
+                    frames.push(frame);
+                    false
+                })
+            },
+        );
+        let expected = [
+            // stale data and flags are cleared at enable
+            "modify I2SCFGR 0x00000900 -> 0x00000d00 [I2SCFG=1 I2SE I2SMOD]",
+            "read DR 0x00000000 []",
+            "read SR 0x00000000 []",
+            "read SR 0x00000001 [RXNE]",
+            "read DR 0x00000001 [DR=0x0001]",
+            // the overrun is handled before reading the data completing the frame
+            "read SR 0x00000041 [RXNE OVR]",
+            "modify I2SCFGR 0x00000d00 -> 0x00000900 [I2SCFG=1 I2SMOD]",
+            "modify I2SCFGR 0x00000900 -> 0x00000d00 [I2SCFG=1 I2SE I2SMOD]",
+            "read DR 0x00000000 []",
+            "read SR 0x00000000 []",
+            "read SR 0x00000001 [RXNE]",
+            "read DR 0x00000003 [DR=0x0003]",
+            "read SR 0x00000001 [RXNE]",
+            "read DR 0x00000004 [DR=0x0004]",
+        ];
+        assert_eq!(records, expected);
+        assert_eq!(frames, [(3, 4)]);
+    }
+
+    /// Frames sent as `(n, -n)` in simulated tests.
+    trait TestFrame: Copy {
+        fn new(n: i32) -> Self;
+        /// `true` when both channels come from the same frame.
+        fn is_whole(&self) -> bool;
+    }
+
+    impl TestFrame for (i16, i16) {
+        fn new(n: i32) -> Self {
+            (n as i16, -n as i16)
+        }
+        fn is_whole(&self) -> bool {
+            self.0 == -self.1
+        }
+    }
+
+    impl TestFrame for (i32, i32) {
+        fn new(n: i32) -> Self {
+            (n, -n)
+        }
+        fn is_whole(&self) -> bool {
+            self.0 == -self.1
+        }
+    }
+
+    /// Check master transfers against slave transfers, at any phase of the master clock.
+    fn check_master_sync<STD, FMT, T>(standard: STD, data_format: FMT)
+    where
+        STD: I2sStandard + Copy,
+        FMT: DataFormat + Copy,
+        (STD, FMT): FrameFormat,
+        T: TestFrame + ToRawFrame<STD, FMT> + FromRawFrame<STD, FMT> + core::fmt::Debug,
+    {
+        let master = I2sTransferConfig::new_master()
+            .standard(standard)
+            .data_format(data_format);
+        let slave = I2sTransferConfig::new_slave()
+            .standard(standard)
+            .data_format(data_format);
+        for offset in 0..64 {
+            let bus = VirtualBus::new(BusConfig::new().start_offset(offset));
+            bus.install();
+            let mut transmitter = master.i2s_transfer(SimI2sA::new(&bus));
+            let mut receiver = slave.receive().i2s_transfer(SimI2sB::new(&bus));
+            let mut next = 1;
+            let mut received = 0;
+            while received < 4 {
+                if transmitter.write(T::new(next)).is_ok() {
+                    next += 1;
+                }
+                if let Ok(frame) = receiver.read::<T>() {
+                    assert!(frame.is_whole(), "transmit {}: {:?}", offset, frame);
+                    received += 1;
+                }
+            }
+
+            let bus = VirtualBus::new(BusConfig::new().start_offset(offset));
+            bus.install();
+            let mut receiver = master.receive().i2s_transfer(SimI2sA::new(&bus));
+            let mut transmitter = slave.i2s_transfer(SimI2sB::new(&bus));
+            let mut next = 1;
+            let mut received = 0;
+            while received < 4 {
+                if transmitter.write(T::new(next)).is_ok() {
+                    next += 1;
+                }
+                if let Ok(frame) = receiver.read::<T>() {
+                    assert!(frame.is_whole(), "receive {}: {:?}", offset, frame);
+                    received += 1;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_master_sync_phases() {
+        check_master_sync::<_, _, (i16, i16)>(Philips, Data16Channel16);
+        check_master_sync::<_, _, (i32, i32)>(Philips, Data32Channel32);
+        check_master_sync::<_, _, (i16, i16)>(Msb, Data16Channel32);
+        check_master_sync::<_, _, (i32, i32)>(Msb, Data32Channel32);
+        check_master_sync::<_, _, (i16, i16)>(Lsb, Data16Channel16);
+        check_master_sync::<_, _, (i32, i32)>(Lsb, Data32Channel32);
+    }
+
+    /// Xorshift pseudo-random generator, so a failing fuzz case can be replayed from its seed.
+    struct Rng(u32);
+
+    impl Rng {
+        fn next(&mut self) -> u32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 17;
+            self.0 ^= self.0 << 5;
+            self.0
+        }
+
+        /// `true` with a probability of 1 / `n`.
+        fn one_in(&mut self, n: u32) -> bool {
+            self.next() % n == 0
+        }
+    }
+
+    /// Check invariants on received frames, sent as `(n, -n)` with `n` increasing from 1.
+    #[derive(Default)]
+    struct FrameChecker {
+        seed: u32,
+        /// Last valid frame.
+        last: i32,
+        /// Errors counted by the bus before the last valid frame. An error detected while receiving
+        /// a frame may cause losses after it.
+        errors: u32,
+        /// Errors counted by the bus when the last valid frame was received.
+        last_errors: u32,
+        valid: usize,
+    }
+
+    impl FrameChecker {
+        fn check(&mut self, (left, right): (i16, i16), errors: u32) {
+            let seed = self.seed;
+            let (left, right) = (left as i32, right as i32);
+            let valid = left != 0 && left == -right;
+            if self.last == 0 {
+                // before the first valid frame, the transfer may not be synchronized yet
+            } else if valid && left == self.last + 1 {
+                assert!(errors >= self.errors);
+            } else {
+                // lost, corrupted or misaligned frames only after a counted error
+                assert!(
+                    errors > self.errors,
+                    "seed {}: frame ({}, {}) after {} without error",
+                    seed,
+                    left,
+                    right,
+                    self.last
+                );
+                assert!(!valid || left > self.last, "seed {}: frame replayed", seed);
+            }
+            if valid {
+                self.last = left;
+                self.errors = self.last_errors;
+                self.last_errors = errors;
+                self.valid += 1;
+            }
+        }
+    }
+
+    const FUZZ_BITS: u64 = 20_000;
+
+    fn fuzz_bus(rng: &mut Rng) -> VirtualBus {
+        let config = BusConfig::new()
+            .clock_ratio(2 + rng.next() % 4)
+            .start_offset(rng.next() % 64);
+        let bus = VirtualBus::new(config);
+        bus.install();
+        bus
+    }
+
+    fn fuzz_slave_receive(seed: u32) {
+        let mut rng = Rng(seed);
+        let bus = fuzz_bus(&mut rng);
+        let mut transmitter = I2sTransferConfig::new_master().i2s_transfer(SimI2sA::new(&bus));
+        let mut receiver = I2sTransferConfig::new_slave()
+            .receive()
+            .i2s_transfer(SimI2sB::new(&bus));
+        let mut checker = FrameChecker {
+            seed,
+            ..Default::default()
+        };
+        let mut next = 1;
+        let mut missed_reads = 0;
+        while bus.bits() < FUZZ_BITS {
+            if transmitter.write((next, -next)).is_ok() {
+                next += 1;
+            }
+            if rng.one_in(400) {
+                bus.glitch_ws();
+            }
+            if missed_reads > 0 {
+                missed_reads -= 1;
+                continue;
+            }
+            if rng.one_in(300) {
+                missed_reads = rng.next() % 40;
+            }
+            if let Ok(frame) = receiver.read::<(i16, i16)>() {
+                checker.check(frame, bus.errors().total());
+            }
+            assert!(receiver.transfer_count as usize <= receiver.frame.len());
+        }
+        assert!(
+            checker.valid > 100,
+            "seed {}: {} frames",
+            seed,
+            checker.valid
+        );
+    }
+
+    fn fuzz_slave_transmit(seed: u32) {
+        let mut rng = Rng(seed);
+        let bus = fuzz_bus(&mut rng);
+        let mut receiver = I2sTransferConfig::new_master()
+            .receive()
+            .i2s_transfer(SimI2sA::new(&bus));
+        let mut transmitter = I2sTransferConfig::new_slave().i2s_transfer(SimI2sB::new(&bus));
+        let mut checker = FrameChecker {
+            seed,
+            ..Default::default()
+        };
+        let mut next = 1;
+        let mut missed_writes = 0;
+        while bus.bits() < FUZZ_BITS {
+            if rng.one_in(400) {
+                bus.glitch_ws();
+            }
+            if missed_writes > 0 {
+                missed_writes -= 1;
+            } else {
+                if rng.one_in(300) {
+                    missed_writes = rng.next() % 40;
+                }
+                if transmitter.write((next, -next)).is_ok() {
+                    next += 1;
+                }
+                assert!(transmitter.transfer_count as usize <= transmitter.frame.len());
+            }
+            // missed reads cause overruns, ending the transfer until the next read
+            if !rng.one_in(50) {
+                if let Ok(frame) = receiver.read() {
+                    checker.check(frame, bus.errors().total());
+                }
+            }
+            assert!(receiver.transfer_count as usize <= receiver.frame.len());
+        }
+        assert!(
+            checker.valid > 100,
+            "seed {}: {} frames",
+            seed,
+            checker.valid
+        );
+    }
+
+    #[test]
+    fn test_fuzz_slave() {
+        for seed in 1..=20 {
+            fuzz_slave_receive(seed);
+            fuzz_slave_transmit(seed);
+        }
+    }
 }
@@ -0,0 +1,784 @@
+//! Streaming WAV files.
+//!
+//! [`WavWriter`] records frames of an `I2sTransfer` into a WAV file, and [`WavReader`] parses a
+//! WAV file to play it back. Both stream through the minimal [`ByteWrite`] and [`ByteRead`]
+//! traits, so they work with any storage without `std`, like a file of an SD card library.
+//!
+//! Mono files correspond to PCM standards and stereo files to `Philips`, `Msb` and `Lsb`
+//! standards. 16 bit samples are transferred with `Data16Channel16` or `Data16Channel32`, 24 bit
+//! samples with `Data24Channel32` or `Data32Channel32`, and 32 bit samples with `Data32Channel32`,
+//! 24 bit samples being left-justified in 32 bit words.
+//!
+//! ```
+//! # use stm32_i2s_v12x::wav::*;
+//! # use stm32_i2s_v12x::marker::*;
+//! let mut buffer = [0; 64];
+//! let mut writer =
+//!     WavWriter::<_, Philips, Data16Channel16>::new(SliceWriter::new(&mut buffer), 48_000, 16)
+//!         .unwrap();
+//! // frames would come from an I2sTransfer
+//! writer.write_frame((1i16, -1i16)).unwrap();
+//! let file = writer.finish().unwrap();
+//!
+//! let reader = WavReader::new(file.written()).unwrap();
+//! let spec = reader.spec();
+//! assert_eq!(spec.sample_rate, 48_000);
+//! // the transfer to configure
+//! assert_eq!(spec.standard(), stm32_i2s_v12x::driver::I2sStandard::Philips);
+//! assert_eq!(
+//!     spec.data_format(),
+//!     stm32_i2s_v12x::driver::DataFormat::Data16Channel16
+//! );
+//! let mut frames = reader.into_frames::<Philips, Data16Channel16>().unwrap();
+//! assert_eq!(frames.read_frame::<(i16, i16)>(), Ok((1, -1)));
+//! assert_eq!(frames.read_frame::<(i16, i16)>(), Err(WavError::EndOfData));
+//! ```
+use core::convert::Infallible;
+use core::fmt;
+use core::marker::PhantomData;
+
+use crate::audio::{AudioSink, AudioSource, EndOfBuffer};
+use crate::driver::{DataFormat, I2sStandard};
+use crate::dynamic::DynFrame;
+use crate::marker;
+use crate::transfer::{FrameFormat, FromRawFrame, RawFrame, ToRawFrame};
+
+/// Minimal byte output.
+pub trait ByteWrite {
+    /// Error of the output.
+    type Error;
+
+    /// Write all `bytes` at the current position.
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Move to an absolute position, used to patch sizes once all data has been written.
+    fn seek(&mut self, position: u32) -> Result<(), Self::Error>;
+}
+
+/// Minimal byte input.
+pub trait ByteRead {
+    /// Error of the input.
+    type Error;
+
+    /// Read up to `buffer.len()` bytes, returning how many bytes were read, 0 meaning the end of
+    /// the input.
+    fn read_bytes(&mut self, buffer: &mut [u8]) -> Result<usize, Self::Error>;
+}
+
+impl ByteRead for &[u8] {
+    type Error = Infallible;
+
+    fn read_bytes(&mut self, buffer: &mut [u8]) -> Result<usize, Infallible> {
+        let count = buffer.len().min(self.len());
+        let (read, rest) = self.split_at(count);
+        buffer[..count].copy_from_slice(read);
+        *self = rest;
+        Ok(count)
+    }
+}
+
+/// A [`ByteWrite`] output to a slice.
+#[derive(Debug)]
+pub struct SliceWriter<'a> {
+    buffer: &'a mut [u8],
+    position: usize,
+    len: usize,
+}
+
+impl<'a> SliceWriter<'a> {
+    /// Create an output to `buffer`.
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Self {
+            buffer,
+            position: 0,
+            len: 0,
+        }
+    }
+
+    /// Bytes written, up to the furthest position.
+    pub fn written(&self) -> &[u8] {
+        &self.buffer[..self.len]
+    }
+}
+
+impl<'a> ByteWrite for SliceWriter<'a> {
+    type Error = EndOfBuffer;
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), EndOfBuffer> {
+        let end = self.position + bytes.len();
+        self.buffer
+            .get_mut(self.position..end)
+            .ok_or(EndOfBuffer)?
+            .copy_from_slice(bytes);
+        self.position = end;
+        self.len = self.len.max(end);
+        Ok(())
+    }
+
+    fn seek(&mut self, position: u32) -> Result<(), EndOfBuffer> {
+        match position as usize <= self.buffer.len() {
+            true => self.position = position as usize,
+            false => return Err(EndOfBuffer),
+        }
+        Ok(())
+    }
+}
+
+/// Error of WAV reading and writing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WavError<E> {
+    /// Error of the byte input or output.
+    Io(E),
+    /// The input is not a WAV file, or its header is truncated.
+    NotWav,
+    /// The file isn't 16, 24 or 32 bit PCM with one or two channels, or its byte rate overflows.
+    Unsupported,
+    /// The file or the sample size doesn't match the standard and the data format of the transfer.
+    Incompatible,
+    /// All frames were read.
+    EndOfData,
+    /// The data doesn't fit in a WAV file.
+    TooLong,
+}
+
+impl<E: fmt::Debug> fmt::Display for WavError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WavError::Io(error) => write!(f, "I/O error: {:?}", error),
+            WavError::NotWav => write!(f, "not a WAV file"),
+            WavError::Unsupported => write!(f, "unsupported WAV format"),
+            WavError::Incompatible => write!(f, "WAV format incompatible with the transfer"),
+            WavError::EndOfData => write!(f, "end of WAV data"),
+            WavError::TooLong => write!(f, "WAV data too long"),
+        }
+    }
+}
+
+/// Format of a WAV file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WavSpec {
+    /// Number of channels, 1 or 2.
+    pub channels: u16,
+    /// Sampling frequency, in Hz.
+    pub sample_rate: u32,
+    /// Sample size, 16, 24 or 32 bits.
+    pub bits_per_sample: u16,
+}
+
+impl WavSpec {
+    /// Format for a transfer standard and data format, if `bits_per_sample` is compatible.
+    pub fn for_transfer<STD, FMT>(sample_rate: u32, bits_per_sample: u16) -> Option<Self>
+    where
+        STD: marker::I2sStandard,
+        FMT: marker::DataFormat,
+    {
+        let spec = WavSpec {
+            channels: match _is_pcm(STD::VALUE) {
+                true => 1,
+                false => 2,
+            },
+            sample_rate,
+            bits_per_sample,
+        };
+        Some(spec).filter(|spec| spec.is_compatible::<STD, FMT>())
+    }
+
+    /// Standard for a transfer: `Philips` for stereo files, and `PcmShortSync` for mono files.
+    ///
+    /// Other standards with the same number of channels can also be used.
+    pub fn standard(&self) -> I2sStandard {
+        match self.channels {
+            1 => I2sStandard::PcmShortSync,
+            _ => I2sStandard::Philips,
+        }
+    }
+
+    /// Data format for a transfer: `Data16Channel16` for 16 bit samples, and `Data32Channel32`
+    /// for 24 and 32 bit samples.
+    ///
+    /// `Data16Channel32` can also be used for 16 bit samples, and `Data24Channel32` for 24 bit
+    /// samples.
+    pub fn data_format(&self) -> DataFormat {
+        match self.bits_per_sample {
+            16 => DataFormat::Data16Channel16,
+            _ => DataFormat::Data32Channel32,
+        }
+    }
+
+    /// `true` if frames of this format can be transferred with `STD` and `FMT`.
+    pub fn is_compatible<STD, FMT>(&self) -> bool
+    where
+        STD: marker::I2sStandard,
+        FMT: marker::DataFormat,
+    {
+        let channels = match _is_pcm(STD::VALUE) {
+            true => 1,
+            false => 2,
+        };
+        let bits_ok = match FMT::VALUE {
+            DataFormat::Data16Channel16 | DataFormat::Data16Channel32 => self.bits_per_sample == 16,
+            DataFormat::Data24Channel32 => self.bits_per_sample == 24,
+            DataFormat::Data32Channel32 => matches!(self.bits_per_sample, 24 | 32),
+        };
+        self.channels == channels && bits_ok
+    }
+
+    fn is_supported(&self) -> bool {
+        matches!(self.channels, 1 | 2) && matches!(self.bits_per_sample, 16 | 24 | 32)
+    }
+
+    /// Size of a frame in bytes.
+    fn block_align(&self) -> u16 {
+        self.channels * self.bits_per_sample / 8
+    }
+}
+
+fn _is_pcm(standard: I2sStandard) -> bool {
+    matches!(
+        standard,
+        I2sStandard::PcmShortSync | I2sStandard::PcmLongSync
+    )
+}
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+const HEADER_LEN: u32 = 44;
+const RIFF_SIZE_POSITION: u32 = 4;
+const DATA_SIZE_POSITION: u32 = 40;
+
+/// Call `f` with the samples of a raw frame, as left-justified 32 bit values.
+fn _raw_samples(raw: &[u16], channels: usize, mut f: impl FnMut(u32)) {
+    let words = raw.len() / channels;
+    for sample in raw.chunks_exact(words) {
+        match sample {
+            [word] => f((*word as u32) << 16),
+            _ => f((sample[0] as u32) << 16 | sample[1] as u32),
+        }
+    }
+}
+
+/// Streaming WAV writer of frames transferred with `STD` and `FMT`.
+///
+/// The header is written at creation with zero sizes, patched by [`finish()`](Self::finish).
+#[derive(Debug)]
+pub struct WavWriter<W, STD, FMT> {
+    writer: W,
+    spec: WavSpec,
+    data_size: u32,
+    _format: PhantomData<(STD, FMT)>,
+}
+
+impl<W, STD, FMT> WavWriter<W, STD, FMT>
+where
+    W: ByteWrite,
+    STD: marker::I2sStandard,
+    FMT: marker::DataFormat,
+    (STD, FMT): FrameFormat,
+{
+    /// Start a WAV file of `bits_per_sample` samples.
+    ///
+    /// `bits_per_sample` must be 16 with `Data16Channel16` and `Data16Channel32`, 24 with
+    /// `Data24Channel32`, and 24 or 32 with `Data32Channel32`. [`WavError::Unsupported`] is
+    /// returned if the byte rate doesn't fit the header.
+    pub fn new(
+        mut writer: W,
+        sample_rate: u32,
+        bits_per_sample: u16,
+    ) -> Result<Self, WavError<W::Error>> {
+        let spec = WavSpec::for_transfer::<STD, FMT>(sample_rate, bits_per_sample)
+            .ok_or(WavError::Incompatible)?;
+        let block_align = spec.block_align();
+        let byte_rate = sample_rate
+            .checked_mul(block_align as u32)
+            .ok_or(WavError::Unsupported)?;
+        let mut header = [0; HEADER_LEN as usize];
+        header[0..4].copy_from_slice(b"RIFF");
+        header[8..16].copy_from_slice(b"WAVEfmt ");
+        header[16..20].copy_from_slice(&16u32.to_le_bytes());
+        header[20..22].copy_from_slice(&FORMAT_PCM.to_le_bytes());
+        header[22..24].copy_from_slice(&spec.channels.to_le_bytes());
+        header[24..28].copy_from_slice(&sample_rate.to_le_bytes());
+        header[28..32].copy_from_slice(&byte_rate.to_le_bytes());
+        header[32..34].copy_from_slice(&block_align.to_le_bytes());
+        header[34..36].copy_from_slice(&bits_per_sample.to_le_bytes());
+        header[36..40].copy_from_slice(b"data");
+        writer.write_bytes(&header).map_err(WavError::Io)?;
+        Ok(Self {
+            writer,
+            spec,
+            data_size: 0,
+            _format: PhantomData,
+        })
+    }
+
+    /// Format of the file.
+    pub fn spec(&self) -> WavSpec {
+        self.spec
+    }
+
+    /// Number of frames written.
+    pub fn frames_written(&self) -> u32 {
+        self.data_size / self.spec.block_align() as u32
+    }
+
+    /// Write a frame.
+    pub fn write_frame<T: ToRawFrame<STD, FMT>>(
+        &mut self,
+        frame: T,
+    ) -> Result<(), WavError<W::Error>> {
+        let block_align = self.spec.block_align() as u32;
+        // keep room for the header and a padding byte
+        let data_size = self
+            .data_size
+            .checked_add(block_align)
+            .filter(|size| *size < u32::MAX - HEADER_LEN)
+            .ok_or(WavError::TooLong)?;
+        let mut bytes = [0; 8];
+        let mut len = 0;
+        let sample_len = self.spec.bits_per_sample as usize / 8;
+        let raw = frame.to_raw();
+        _raw_samples(raw.as_ref(), self.spec.channels as usize, |sample| {
+            bytes[len..len + sample_len].copy_from_slice(&sample.to_le_bytes()[4 - sample_len..]);
+            len += sample_len;
+        });
+        self.writer
+            .write_bytes(&bytes[..len])
+            .map_err(WavError::Io)?;
+        self.data_size = data_size;
+        Ok(())
+    }
+
+    /// Pad the data and patch sizes in the header, giving back the output positioned at the end
+    /// of the file.
+    pub fn finish(mut self) -> Result<W, WavError<W::Error>> {
+        let mut end = HEADER_LEN + self.data_size;
+        if self.data_size % 2 != 0 {
+            self.writer.write_bytes(&[0]).map_err(WavError::Io)?;
+            end += 1;
+        }
+        let patches = [
+            (RIFF_SIZE_POSITION, end - 8),
+            (DATA_SIZE_POSITION, self.data_size),
+        ];
+        for (position, size) in patches.iter() {
+            self.writer.seek(*position).map_err(WavError::Io)?;
+            self.writer
+                .write_bytes(&size.to_le_bytes())
+                .map_err(WavError::Io)?;
+        }
+        self.writer.seek(end).map_err(WavError::Io)?;
+        Ok(self.writer)
+    }
+}
+
+impl<W, STD, FMT, T> AudioSink<T> for WavWriter<W, STD, FMT>
+where
+    W: ByteWrite,
+    STD: marker::I2sStandard,
+    FMT: marker::DataFormat,
+    (STD, FMT): FrameFormat,
+    T: ToRawFrame<STD, FMT>,
+{
+    type Error = WavError<W::Error>;
+
+    fn write_frame(&mut self, frame: T) -> nb::Result<(), Self::Error> {
+        Ok(WavWriter::write_frame(self, frame)?)
+    }
+}
+
+/// Read exactly `buffer.len()` bytes, `Ok(false)` meaning the input ended before.
+fn _read_exact<R: ByteRead>(reader: &mut R, buffer: &mut [u8]) -> Result<bool, R::Error> {
+    let mut position = 0;
+    while position < buffer.len() {
+        match reader.read_bytes(&mut buffer[position..])? {
+            0 => return Ok(false),
+            count => position += count,
+        }
+    }
+    Ok(true)
+}
+
+/// Streaming WAV reader, parsing the header to select a transfer configuration.
+#[derive(Debug)]
+pub struct WavReader<R> {
+    reader: R,
+    spec: WavSpec,
+    remaining: u32,
+}
+
+impl<R: ByteRead> WavReader<R> {
+    /// Parse the header of a WAV file, up to the start of the data.
+    ///
+    /// Chunks other than the format and the data are skipped. Files being recorded, with a
+    /// `0xFFFFFFFF` data size, or zero RIFF and data sizes like an unfinished [`WavWriter`], are
+    /// read up to the end of the input.
+    pub fn new(mut reader: R) -> Result<Self, WavError<R::Error>> {
+        let read = |reader: &mut R, buffer: &mut [u8]| match _read_exact(reader, buffer) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(WavError::NotWav),
+            Err(error) => Err(WavError::Io(error)),
+        };
+        let mut header = [0; 12];
+        read(&mut reader, &mut header)?;
+        if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+            return Err(WavError::NotWav);
+        }
+        let riff_size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let mut spec = None;
+        loop {
+            let mut chunk = [0; 8];
+            read(&mut reader, &mut chunk)?;
+            let mut size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+            match (&chunk[0..4], spec) {
+                (b"data", Some(spec)) => {
+                    let remaining = match (riff_size, size) {
+                        (0, 0) => u32::MAX,
+                        (_, size) => size,
+                    };
+                    return Ok(Self {
+                        reader,
+                        spec,
+                        remaining,
+                    });
+                }
+                (b"data", None) => return Err(WavError::NotWav),
+                (b"fmt ", _) => {
+                    let mut fmt = [0; 40];
+                    let len = (size as usize).min(fmt.len());
+                    if len < 16 {
+                        return Err(WavError::NotWav);
+                    }
+                    read(&mut reader, &mut fmt[..len])?;
+                    size -= len as u32;
+                    let field = |i: usize| u16::from_le_bytes([fmt[i], fmt[i + 1]]);
+                    let format = match field(0) {
+                        FORMAT_EXTENSIBLE if len >= 26 => field(24),
+                        format => format,
+                    };
+                    let parsed = WavSpec {
+                        channels: field(2),
+                        sample_rate: u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]),
+                        bits_per_sample: field(14),
+                    };
+                    if format != FORMAT_PCM
+                        || !parsed.is_supported()
+                        || field(12) != parsed.block_align()
+                    {
+                        return Err(WavError::Unsupported);
+                    }
+                    spec = Some(parsed);
+                }
+                _ => {}
+            }
+            // skip the rest of the chunk, and its padding byte
+            let mut skip = size as u64 + (size & 1) as u64;
+            let mut scratch = [0; 16];
+            while skip > 0 {
+                let len = skip.min(scratch.len() as u64) as usize;
+                read(&mut reader, &mut scratch[..len])?;
+                skip -= len as u64;
+            }
+        }
+    }
+
+    /// Format of the file.
+    pub fn spec(&self) -> WavSpec {
+        self.spec
+    }
+
+    /// Read the next frame, whatever the format of the file, failing with
+    /// [`WavError::EndOfData`] at the end of the data.
+    ///
+    /// 24 bit samples are read as left-justified 32 bit samples, like with `Data32Channel32`.
+    pub fn read_dyn_frame(&mut self) -> Result<DynFrame, WavError<R::Error>> {
+        let [left, right] = self.read_samples()?;
+        Ok(match (self.spec.channels, self.spec.bits_per_sample) {
+            (1, 16) => DynFrame::Mono16((left >> 16) as i16),
+            (1, _) => DynFrame::Mono32(left as i32),
+            (_, 16) => DynFrame::Stereo16((left >> 16) as i16, (right >> 16) as i16),
+            _ => DynFrame::Stereo32(left as i32, right as i32),
+        })
+    }
+
+    /// Read the samples of a frame, as left-justified 32 bit values.
+    fn read_samples(&mut self) -> Result<[u32; 2], WavError<R::Error>> {
+        let block_align = self.spec.block_align() as u32;
+        if self.remaining < block_align {
+            return Err(WavError::EndOfData);
+        }
+        let mut bytes = [0; 8];
+        let bytes = &mut bytes[..block_align as usize];
+        match _read_exact(&mut self.reader, bytes) {
+            Ok(true) => {}
+            Ok(false) => return Err(WavError::EndOfData),
+            Err(error) => return Err(WavError::Io(error)),
+        }
+        if self.remaining != u32::MAX {
+            self.remaining -= block_align;
+        }
+        let sample_len = self.spec.bits_per_sample as usize / 8;
+        let mut samples = [0; 2];
+        for (sample, bytes) in samples.iter_mut().zip(bytes.chunks_exact(sample_len)) {
+            let mut value = [0; 4];
+            value[4 - sample_len..].copy_from_slice(bytes);
+            *sample = u32::from_le_bytes(value);
+        }
+        Ok(samples)
+    }
+
+    /// Read frames of the file with a transfer standard and data format.
+    ///
+    /// This fails with [`WavError::Incompatible`] if the file format doesn't match them.
+    pub fn into_frames<STD, FMT>(self) -> Result<WavFrames<R, STD, FMT>, WavError<R::Error>>
+    where
+        STD: marker::I2sStandard,
+        FMT: marker::DataFormat,
+        (STD, FMT): FrameFormat,
+    {
+        match self.spec.is_compatible::<STD, FMT>() {
+            true => Ok(WavFrames {
+                reader: self,
+                _format: PhantomData,
+            }),
+            false => Err(WavError::Incompatible),
+        }
+    }
+}
+
+/// Frames of a WAV file, read for a transfer standard and data format.
+///
+/// This `struct` is created by [`WavReader::into_frames()`].
+#[derive(Debug)]
+pub struct WavFrames<R, STD, FMT> {
+    reader: WavReader<R>,
+    _format: PhantomData<(STD, FMT)>,
+}
+
+impl<R, STD, FMT> WavFrames<R, STD, FMT>
+where
+    R: ByteRead,
+    (STD, FMT): FrameFormat,
+{
+    /// Format of the file.
+    pub fn spec(&self) -> WavSpec {
+        self.reader.spec
+    }
+
+    /// Read the next frame, failing with [`WavError::EndOfData`] at the end of the data.
+    pub fn read_frame<T: FromRawFrame<STD, FMT>>(&mut self) -> Result<T, WavError<R::Error>> {
+        let samples = self.reader.read_samples()?;
+        let mut raw = RawFrame::<STD, FMT>::default();
+        let words = raw.as_ref().len() / self.reader.spec.channels as usize;
+        for (sample, raw) in samples.iter().zip(raw.as_mut().chunks_exact_mut(words)) {
+            match raw {
+                [word] => *word = (sample >> 16) as u16,
+                _ => {
+                    raw[0] = (sample >> 16) as u16;
+                    raw[1] = *sample as u16;
+                }
+            }
+        }
+        Ok(T::from_raw(raw))
+    }
+}
+
+impl<R, STD, FMT, T> AudioSource<T> for WavFrames<R, STD, FMT>
+where
+    R: ByteRead,
+    (STD, FMT): FrameFormat,
+    T: FromRawFrame<STD, FMT>,
+{
+    type Error = WavError<R::Error>;
+
+    fn read_frame(&mut self) -> nb::Result<T, Self::Error> {
+        Ok(WavFrames::read_frame(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{AudioSource, BufferSource};
+    use crate::marker::{
+        Data16Channel16, Data16Channel32, Data24Channel32, Data32Channel32, Lsb, Msb, PcmLongSync,
+        PcmShortSync, Philips,
+    };
+    use std::vec::Vec;
+
+    /// Reference file, built field by field.
+    fn reference(channels: u16, rate: u32, bits: u16, data: &[u8]) -> Vec<u8> {
+        let block_align = channels * bits / 8;
+        let mut file = Vec::new();
+        file.extend_from_slice(b"RIFF");
+        let padded = data.len() + data.len() % 2;
+        file.extend_from_slice(&(36 + padded as u32).to_le_bytes());
+        file.extend_from_slice(b"WAVEfmt ");
+        file.extend_from_slice(&[16, 0, 0, 0, 1, 0]);
+        file.extend_from_slice(&channels.to_le_bytes());
+        file.extend_from_slice(&rate.to_le_bytes());
+        file.extend_from_slice(&(rate * block_align as u32).to_le_bytes());
+        file.extend_from_slice(&block_align.to_le_bytes());
+        file.extend_from_slice(&bits.to_le_bytes());
+        file.extend_from_slice(b"data");
+        file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        file.extend_from_slice(data);
+        file.resize(44 + padded, 0);
+        file
+    }
+
+    #[test]
+    fn test_writer() {
+        let mut buffer = [0; 128];
+        let mut writer =
+            WavWriter::<_, Msb, Data16Channel32>::new(SliceWriter::new(&mut buffer), 8000, 16)
+                .unwrap();
+        writer.write_frame((0x1234, -2)).unwrap();
+        writer.write_frame([-0x8000i16, 1]).unwrap();
+        assert_eq!(writer.frames_written(), 2);
+        let data = [0x34, 0x12, 0xFE, 0xFF, 0x00, 0x80, 0x01, 0x00];
+        let file = writer.finish().unwrap();
+        assert_eq!(file.written(), &reference(2, 8000, 16, &data)[..]);
+
+        // 24 bit mono, padded
+        let mut buffer = [0; 128];
+        let mut writer = WavWriter::<_, PcmLongSync, Data32Channel32>::new(
+            SliceWriter::new(&mut buffer),
+            44_100,
+            24,
+        )
+        .unwrap();
+        let mut source = BufferSource::new(&[0x1234_5678, -0x100, 0x7FFF_FFFF]);
+        while let Ok(sample) = AudioSource::<i32>::read_frame(&mut source) {
+            writer.write_frame(sample).unwrap();
+        }
+        let data = [0x56, 0x34, 0x12, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F];
+        let file = writer.finish().unwrap();
+        assert_eq!(file.written(), &reference(1, 44_100, 24, &data)[..]);
+
+        // errors
+        let mut buffer = [0; 50];
+        let result =
+            WavWriter::<_, Philips, Data16Channel16>::new(SliceWriter::new(&mut buffer), 8000, 24);
+        assert_eq!(result.err(), Some(WavError::Incompatible));
+        let mut writer =
+            WavWriter::<_, Philips, Data32Channel32>::new(SliceWriter::new(&mut buffer), 8000, 32)
+                .unwrap();
+        assert_eq!(writer.write_frame((1, 2)), Err(WavError::Io(EndOfBuffer)));
+        let result = WavWriter::<_, Philips, Data32Channel32>::new(
+            SliceWriter::new(&mut buffer),
+            1_000_000_000,
+            32,
+        );
+        assert_eq!(result.err(), Some(WavError::Unsupported));
+    }
+
+    #[test]
+    fn test_reader() {
+        let data = [0x34, 0x12, 0xFE, 0xFF, 0x00, 0x80];
+        let file = reference(1, 16_000, 16, &data);
+        let reader = WavReader::new(&file[..]).unwrap();
+        let spec = reader.spec();
+        assert_eq!(
+            spec,
+            WavSpec {
+                channels: 1,
+                sample_rate: 16_000,
+                bits_per_sample: 16
+            }
+        );
+        assert_eq!(spec.standard(), I2sStandard::PcmShortSync);
+        assert_eq!(spec.data_format(), DataFormat::Data16Channel16);
+        assert!(!spec.is_compatible::<Philips, Data16Channel16>());
+        let mut frames = reader
+            .into_frames::<PcmShortSync, Data16Channel32>()
+            .unwrap();
+        let samples: Vec<_> = AudioSource::<i16>::frames(&mut frames).collect();
+        assert_eq!(
+            samples,
+            [Ok(0x1234), Ok(-2), Ok(-0x8000), Err(WavError::EndOfData)]
+        );
+
+        // extensible format, 32 bit stereo, with a list chunk, and an unknown data size
+        let mut file = Vec::new();
+        file.extend_from_slice(b"RIFF\0\0\0\0WAVELIST\x03\0\0\0abc\0fmt \x28\0\0\0");
+        file.extend_from_slice(&[0xFE, 0xFF, 2, 0, 0x80, 0xBB, 0, 0]);
+        file.extend_from_slice(&[0, 0xDC, 5, 0, 8, 0, 32, 0, 22, 0, 32, 0, 3, 0, 0, 0]);
+        file.extend_from_slice(&[
+            1, 0, 0, 0, 0, 0, 0x10, 0, 0x80, 0, 0, 0xAA, 0, 0x38, 0x9B, 0x71,
+        ]);
+        file.extend_from_slice(b"data\xFF\xFF\xFF\xFF");
+        file.extend_from_slice(&[0x78, 0x56, 0x34, 0x12, 0xFF, 0xFF, 0xFF, 0xFF, 0]);
+        let reader = WavReader::new(&file[..]).unwrap();
+        assert_eq!(reader.spec().sample_rate, 48_000);
+        assert_eq!(reader.spec().data_format(), DataFormat::Data32Channel32);
+        assert_eq!(
+            reader.into_frames::<Msb, Data16Channel16>().err().unwrap(),
+            WavError::Incompatible
+        );
+        let reader = WavReader::new(&file[..]).unwrap();
+        let mut frames = reader.into_frames::<Lsb, Data32Channel32>().unwrap();
+        assert_eq!(frames.read_frame(), Ok((0x1234_5678, -1)));
+        let mut reader = WavReader::new(&file[..]).unwrap();
+        assert_eq!(
+            reader.read_dyn_frame(),
+            Ok(DynFrame::Stereo32(0x1234_5678, -1))
+        );
+        let file = reference(1, 8000, 24, &[0x56, 0x34, 0x12]);
+        let mut reader = WavReader::new(&file[..]).unwrap();
+        assert_eq!(reader.read_dyn_frame(), Ok(DynFrame::Mono32(0x1234_5600)));
+        assert_eq!(reader.read_dyn_frame(), Err(WavError::EndOfData));
+        let reader = WavReader::new(&file[..]).unwrap();
+        let mut mono = reader
+            .into_frames::<PcmLongSync, Data24Channel32>()
+            .unwrap();
+        assert_eq!(mono.read_frame(), Ok(0x1234_5600));
+        // the last byte is an incomplete frame
+        assert_eq!(frames.read_frame::<(i32, i32)>(), Err(WavError::EndOfData));
+
+        // empty data followed by another chunk
+        let mut file = reference(1, 8000, 16, &[]);
+        file.extend_from_slice(b"LIST\x02\0\0\0ab");
+        let mut reader = WavReader::new(&file[..]).unwrap();
+        assert_eq!(reader.read_dyn_frame(), Err(WavError::EndOfData));
+        // unfinished header, as written before `finish()`
+        let mut file = reference(1, 8000, 16, &[]);
+        file[4..8].copy_from_slice(&[0; 4]);
+        file.extend_from_slice(&[0x34, 0x12]);
+        let mut reader = WavReader::new(&file[..]).unwrap();
+        assert_eq!(reader.read_dyn_frame(), Ok(DynFrame::Mono16(0x1234)));
+        assert_eq!(reader.read_dyn_frame(), Err(WavError::EndOfData));
+
+        // errors
+        assert_eq!(WavReader::new(&b"RIFF"[..]).err(), Some(WavError::NotWav));
+        let file = reference(2, 8000, 8, &[]);
+        assert_eq!(WavReader::new(&file[..]).err(), Some(WavError::Unsupported));
+        let mut file = reference(2, 8000, 16, &[]);
+        file[20] = 3;
+        assert_eq!(WavReader::new(&file[..]).err(), Some(WavError::Unsupported));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut buffer = [0; 1024];
+        let mut writer = WavWriter::<_, Philips, Data32Channel32>::new(
+            SliceWriter::new(&mut buffer),
+            96_000,
+            24,
+        )
+        .unwrap();
+        let frames: Vec<(i32, i32)> = (0..100).map(|i| (i << 20, -i << 12)).collect();
+        for frame in frames.iter() {
+            writer.write_frame(frame).unwrap();
+        }
+        let file = writer.finish().unwrap();
+        let mut read = WavReader::new(file.written())
+            .unwrap()
+            .into_frames::<Msb, Data32Channel32>()
+            .unwrap();
+        for frame in frames.iter() {
+            assert_eq!(read.read_frame::<(i32, i32)>(), Ok(*frame));
+        }
+        assert_eq!(read.read_frame::<(i32, i32)>(), Err(WavError::EndOfData));
+    }
+}
@@ -0,0 +1,623 @@
+//! Bit-level model of I2S waveforms.
+//!
+//! This module turns audio frames into the sequence of levels seen on CK, WS and SD lines for a
+//! given standard, data format and clock polarity, and decodes such sequences back into frames.
+//! This allows checking codecs and logic analyzer captures against a driver configuration.
+//!
+//! The model follows STM32 reference manuals:
+//!  - data is transmitted MSB first,
+//!  - `Philips`: WS is low for the left channel and changes one bit before the MSB,
+//!  - `Msb`: WS is high for the left channel and changes with the MSB,
+//!  - `Lsb`: like `Msb` but data is right aligned in 32-bit channels,
+//!  - `PcmShortSync`: frames are mono, WS is high during the bit preceding the MSB,
+//!  - `PcmLongSync`: frames are mono, WS is high during the first 13 bits of the frame.
+//!
+//! With 32-bit channels, 16-bit and 24-bit data are padded with zeros, after the data or before
+//! it with `Lsb`. Data changes on the clock edge going to the idle level and is sampled on the
+//! edge leaving it.
+//!
+//! ```
+//! # use stm32_i2s_v12x::waveform::*;
+//! # use stm32_i2s_v12x::driver::*;
+//! let config = I2sDriverConfig::new_master().standard(Philips);
+//! let waveform = Waveform::from_config(&config);
+//! let frames = [Frame { left: 1, right: -1 }];
+//! let lines = waveform.encode(frames.iter().copied());
+//! let decoded: Vec<Frame> = waveform.decode(lines).collect();
+//! assert_eq!(decoded, frames);
+//! ```
+use crate::clock;
+use crate::driver::{ClockPolarity, DataFormat, I2sDriverConfig, I2sStandard};
+use crate::marker;
+use crate::transfer::{FrameFormat, FromRawFrame, ToRawFrame};
+
+/// Levels of CK, WS and SD lines at a given time, `true` meaning high level.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineState {
+    /// Serial clock.
+    pub ck: bool,
+    /// Word select.
+    pub ws: bool,
+    /// Serial data.
+    pub sd: bool,
+}
+
+/// Audio data of a frame.
+///
+/// Values are signed integers of the data length, e.g. in the range of `i16` for 16-bit data.
+/// With PCM standards, frames are mono: only `left` is used, and `right` is decoded as 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Frame {
+    /// Left channel data, or mono data.
+    pub left: i32,
+    /// Right channel data.
+    pub right: i32,
+}
+
+fn is_mono<STD: marker::I2sStandard>() -> bool {
+    matches!(
+        STD::VALUE,
+        I2sStandard::PcmShortSync | I2sStandard::PcmLongSync
+    )
+}
+
+impl Frame {
+    /// Convert a frame type written to an `I2sTransfer`.
+    pub fn from_transfer<STD, FMT, T>(frame: &T) -> Self
+    where
+        STD: marker::I2sStandard,
+        (STD, FMT): FrameFormat,
+        T: ToRawFrame<STD, FMT>,
+    {
+        let raw = frame.to_raw();
+        let words = raw.as_ref();
+        let mono = is_mono::<STD>();
+        let words_per_channel = if mono { words.len() } else { words.len() / 2 };
+        // one word for 16-bit data, two for 32-bit data
+        let value = |words: &[u16]| match words_per_channel {
+            1 => words[0] as i16 as i32,
+            _ => ((words[0] as u32) << 16 | words[1] as u32) as i32,
+        };
+        Frame {
+            left: value(&words[..words_per_channel]),
+            right: if mono {
+                0
+            } else {
+                value(&words[words_per_channel..])
+            },
+        }
+    }
+
+    /// Convert into a frame type read from an `I2sTransfer`.
+    pub fn to_transfer<STD, FMT, T>(self) -> T
+    where
+        STD: marker::I2sStandard,
+        (STD, FMT): FrameFormat,
+        T: FromRawFrame<STD, FMT>,
+    {
+        let mut raw: <(STD, FMT) as FrameFormat>::RawFrame = Default::default();
+        let words = raw.as_mut();
+        let mono = is_mono::<STD>();
+        let words_per_channel = if mono { words.len() } else { words.len() / 2 };
+        let mut set = |offset: usize, value: i32| match words_per_channel {
+            1 => words[offset] = value as u16,
+            _ => {
+                words[offset] = (value as u32 >> 16) as u16;
+                words[offset + 1] = value as u16;
+            }
+        };
+        set(0, self.left);
+        if !mono {
+            set(words_per_channel, self.right);
+        }
+        T::from_raw(raw)
+    }
+}
+
+/// Length of the WS pulse with `PcmLongSync`, in bits.
+const LONG_SYNC_LENGTH: u32 = 13;
+
+/// Waveform model for a standard, a data format and a clock polarity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Waveform {
+    standard: I2sStandard,
+    data_format: DataFormat,
+    clock_polarity: ClockPolarity,
+}
+
+impl Waveform {
+    /// Create a waveform model.
+    pub fn new(
+        standard: I2sStandard,
+        data_format: DataFormat,
+        clock_polarity: ClockPolarity,
+    ) -> Self {
+        Self {
+            standard,
+            data_format,
+            clock_polarity,
+        }
+    }
+
+    /// Create the waveform model matching a driver configuration.
+    pub fn from_config<MS, DIR, STD>(config: &I2sDriverConfig<MS, DIR, STD>) -> Self {
+        Self::new(
+            config.get_standard(),
+            config.get_data_format(),
+            config.get_clock_polarity(),
+        )
+    }
+
+    /// Create the waveform model matching `I2sTransfer` markers, e.g. to decode captures into
+    /// transfer frame types.
+    pub fn for_transfer<STD, FMT>(clock_polarity: ClockPolarity) -> Self
+    where
+        STD: marker::I2sStandard,
+        FMT: marker::DataFormat,
+    {
+        Self::new(STD::VALUE, FMT::VALUE, clock_polarity)
+    }
+
+    /// Get the I2S standard.
+    pub fn standard(&self) -> I2sStandard {
+        self.standard
+    }
+
+    /// Get the data format.
+    pub fn data_format(&self) -> DataFormat {
+        self.data_format
+    }
+
+    /// Get the clock polarity.
+    pub fn clock_polarity(&self) -> ClockPolarity {
+        self.clock_polarity
+    }
+
+    /// `true` for mono frames, that is with PCM standards.
+    pub fn is_mono(&self) -> bool {
+        matches!(
+            self.standard,
+            I2sStandard::PcmShortSync | I2sStandard::PcmLongSync
+        )
+    }
+
+    /// Number of bits (SCK cycles) in a frame.
+    pub fn bits_per_frame(&self) -> u32 {
+        clock::bits_per_frame(self.standard, self.data_format)
+    }
+
+    pub(crate) fn channel_length(&self) -> u32 {
+        match self.data_format {
+            DataFormat::Data16Channel16 => 16,
+            _ => 32,
+        }
+    }
+
+    pub(crate) fn data_length(&self) -> u32 {
+        match self.data_format {
+            DataFormat::Data16Channel16 | DataFormat::Data16Channel32 => 16,
+            DataFormat::Data24Channel32 => 24,
+            DataFormat::Data32Channel32 => 32,
+        }
+    }
+
+    /// Number of bits between the start of a frame on WS and its first data bit.
+    pub(crate) fn delay(&self) -> u32 {
+        match self.standard {
+            I2sStandard::Philips | I2sStandard::PcmShortSync => 1,
+            _ => 0,
+        }
+    }
+
+    /// WS level at the start of a frame.
+    pub(crate) fn start_level(&self) -> bool {
+        !matches!(self.standard, I2sStandard::Philips)
+    }
+
+    /// WS level for bit `index` of a frame, not taking into account the delay.
+    pub(crate) fn ws(&self, index: u32) -> bool {
+        match self.standard {
+            I2sStandard::PcmShortSync => index == 0,
+            I2sStandard::PcmLongSync => index < LONG_SYNC_LENGTH,
+            _ => (index < self.channel_length()) == self.start_level(),
+        }
+    }
+
+    /// Serialize a channel value into its bits, MSB first in the `channel_length` low bits.
+    fn channel_bits(&self, value: i32) -> u64 {
+        let data_length = self.data_length();
+        let data = (value as u32 as u64) & ((1 << data_length) - 1);
+        match self.standard {
+            I2sStandard::Lsb => data,
+            _ => data << (self.channel_length() - data_length),
+        }
+    }
+
+    /// Deserialize a channel value from its bits.
+    fn channel_value(&self, bits: u64) -> i32 {
+        let data_length = self.data_length();
+        let data = match self.standard {
+            I2sStandard::Lsb => bits,
+            _ => bits >> (self.channel_length() - data_length),
+        };
+        // sign extension
+        ((data as u32) << (32 - data_length)) as i32 >> (32 - data_length)
+    }
+
+    fn frame_bits(&self, frame: Frame) -> u64 {
+        let left = self.channel_bits(frame.left);
+        match self.is_mono() {
+            true => left,
+            false => left << self.channel_length() | self.channel_bits(frame.right),
+        }
+    }
+
+    fn frame_value(&self, bits: u64) -> Frame {
+        let channel_length = self.channel_length();
+        match self.is_mono() {
+            true => Frame {
+                left: self.channel_value(bits),
+                right: 0,
+            },
+            false => Frame {
+                left: self.channel_value(bits >> channel_length),
+                right: self.channel_value(bits & ((1 << channel_length) - 1)),
+            },
+        }
+    }
+
+    /// Get the line states generated when transmitting `frames`.
+    ///
+    /// Each bit produces two states, CK being at idle level then at active level. With `Philips`
+    /// and `PcmShortSync`, an extra bit is produced at the end to send the last data bit.
+    pub fn encode<I>(&self, frames: I) -> Encoder<I::IntoIter>
+    where
+        I: IntoIterator<Item = Frame>,
+    {
+        Encoder {
+            waveform: *self,
+            frames: frames.into_iter(),
+            bits: 0,
+            index: self.bits_per_frame(),
+            previous: false,
+            done: false,
+            second_half: false,
+            current: LineState::default(),
+        }
+    }
+
+    /// Decode frames from line states, for example from a logic analyzer capture.
+    ///
+    /// Bits are sampled on CK edges leaving the idle level, so line states may be oversampled.
+    /// Frames are synchronized on WS. The first line state is considered as a frame start if WS
+    /// has the right level, so captures should begin at a frame boundary or before a WS
+    /// transition. Incomplete frames are dropped.
+    pub fn decode<I>(&self, lines: I) -> Decoder<I::IntoIter>
+    where
+        I: IntoIterator<Item = LineState>,
+    {
+        Decoder {
+            waveform: *self,
+            lines: lines.into_iter(),
+            ck: None,
+            ws: None,
+            frame: None,
+        }
+    }
+}
+
+/// Iterator over line states of encoded frames, created by [`Waveform::encode`].
+#[derive(Debug, Clone)]
+pub struct Encoder<I> {
+    waveform: Waveform,
+    frames: I,
+    /// Bits of the current frame.
+    bits: u64,
+    /// Index of the next bit in the current frame.
+    index: u32,
+    /// Previous data bit, sent with delayed standards.
+    previous: bool,
+    done: bool,
+    second_half: bool,
+    current: LineState,
+}
+
+impl<I: Iterator<Item = Frame>> Encoder<I> {
+    /// Compute the line levels of the next bit.
+    fn next_bit(&mut self) -> Option<(bool, bool)> {
+        let waveform = self.waveform;
+        let bits_per_frame = waveform.bits_per_frame();
+        if self.done {
+            return None;
+        }
+        if self.index >= bits_per_frame {
+            match self.frames.next() {
+                Some(frame) => {
+                    self.bits = waveform.frame_bits(frame);
+                    self.index = 0;
+                }
+                None => {
+                    self.done = true;
+                    // a delayed standard sends the last bit during the next frame start
+                    return match waveform.delay() {
+                        0 => None,
+                        _ => Some((waveform.ws(0), self.previous)),
+                    };
+                }
+            }
+        }
+        let bit = (self.bits >> (bits_per_frame - 1 - self.index)) & 1 != 0;
+        let ws = waveform.ws(self.index);
+        self.index += 1;
+        let sd = match waveform.delay() {
+            0 => bit,
+            _ => core::mem::replace(&mut self.previous, bit),
+        };
+        Some((ws, sd))
+    }
+}
+
+impl<I: Iterator<Item = Frame>> Iterator for Encoder<I> {
+    type Item = LineState;
+
+    fn next(&mut self) -> Option<LineState> {
+        let idle = self.waveform.clock_polarity == ClockPolarity::IdleHigh;
+        if self.second_half {
+            self.second_half = false;
+            self.current.ck = !idle;
+            return Some(self.current);
+        }
+        let (ws, sd) = self.next_bit()?;
+        self.second_half = true;
+        self.current = LineState { ck: idle, ws, sd };
+        Some(self.current)
+    }
+}
+
+/// Iterator over frames decoded from line states, created by [`Waveform::decode`].
+#[derive(Debug, Clone)]
+pub struct Decoder<I> {
+    waveform: Waveform,
+    lines: I,
+    /// Previous CK level.
+    ck: Option<bool>,
+    /// Previous sampled WS level.
+    ws: Option<bool>,
+    /// Bits of the frame being received, and their count.
+    frame: Option<(u64, u32)>,
+}
+
+impl<I: Iterator<Item = LineState>> Decoder<I> {
+    /// Handle a sampled bit, return a frame when complete.
+    fn bit(&mut self, ws: bool, sd: bool) -> Option<Frame> {
+        let waveform = self.waveform;
+        let start_level = waveform.start_level();
+        let start = ws == start_level && self.ws != Some(start_level);
+        self.ws = Some(ws);
+        let mut decoded = None;
+        if let Some((bits, count)) = self.frame.as_mut() {
+            *bits = *bits << 1 | sd as u64;
+            *count += 1;
+            if *count == waveform.bits_per_frame() {
+                decoded = Some(waveform.frame_value(*bits));
+                self.frame = None;
+            }
+        }
+        if start {
+            // drop an incomplete frame, and with delayed standards this bit isn't data
+            self.frame = match waveform.delay() {
+                0 => Some((sd as u64, 1)),
+                _ => Some((0, 0)),
+            };
+        }
+        decoded
+    }
+}
+
+impl<I: Iterator<Item = LineState>> Iterator for Decoder<I> {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        let idle = self.waveform.clock_polarity == ClockPolarity::IdleHigh;
+        loop {
+            let line = self.lines.next()?;
+            let previous = self.ck.replace(line.ck);
+            if line.ck != idle && previous == Some(idle) {
+                if let Some(frame) = self.bit(line.ws, line.sd) {
+                    return Some(frame);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    const STANDARDS: [I2sStandard; 5] = [
+        I2sStandard::Philips,
+        I2sStandard::Msb,
+        I2sStandard::Lsb,
+        I2sStandard::PcmShortSync,
+        I2sStandard::PcmLongSync,
+    ];
+
+    const FORMATS: [DataFormat; 4] = [
+        DataFormat::Data16Channel16,
+        DataFormat::Data16Channel32,
+        DataFormat::Data24Channel32,
+        DataFormat::Data32Channel32,
+    ];
+
+    /// Get (ws, sd) of each bit.
+    fn bit_levels(waveform: &Waveform, frames: &[Frame]) -> Vec<(bool, bool)> {
+        let lines: Vec<_> = waveform.encode(frames.iter().copied()).collect();
+        lines.chunks(2).map(|bit| (bit[1].ws, bit[1].sd)).collect()
+    }
+
+    fn levels(bits: &[(bool, bool)], ws: bool) -> Vec<u8> {
+        bits.iter()
+            .map(|b| if ws { b.0 } else { b.1 } as u8)
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let polarities = [ClockPolarity::IdleLow, ClockPolarity::IdleHigh];
+        for &std in STANDARDS.iter() {
+            for &fmt in FORMATS.iter() {
+                for &polarity in polarities.iter() {
+                    let waveform = Waveform::new(std, fmt, polarity);
+                    let max = match fmt {
+                        DataFormat::Data24Channel32 => (1 << 23) - 1,
+                        DataFormat::Data32Channel32 => i32::MAX,
+                        _ => i16::MAX as i32,
+                    };
+                    let frames: Vec<_> = [(max, -1), (-max - 1, 0), (0x55, -0x55)]
+                        .iter()
+                        .map(|&(left, right)| Frame {
+                            left,
+                            right: if waveform.is_mono() { 0 } else { right },
+                        })
+                        .collect();
+                    let lines: Vec<_> = waveform.encode(frames.iter().copied()).collect();
+                    let extra = waveform.delay() as usize;
+                    let bits_per_frame = waveform.bits_per_frame() as usize;
+                    assert_eq!(lines.len(), 2 * (3 * bits_per_frame + extra));
+                    // oversampled capture
+                    let oversampled = lines.iter().flat_map(|&l| [l, l, l]);
+                    let decoded: Vec<_> = waveform.decode(oversampled).collect();
+                    assert_eq!(decoded, frames, "{:?} {:?} {:?}", std, fmt, polarity);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_clock() {
+        let waveform = Waveform::new(
+            I2sStandard::Msb,
+            DataFormat::Data16Channel16,
+            ClockPolarity::IdleHigh,
+        );
+        let lines: Vec<_> = waveform.encode([Frame::default()]).collect();
+        assert!(lines[0].ck && !lines[1].ck && lines[2].ck);
+    }
+
+    #[test]
+    fn test_philips_delay() {
+        let waveform = Waveform::new(
+            I2sStandard::Philips,
+            DataFormat::Data16Channel16,
+            ClockPolarity::IdleLow,
+        );
+        let bits = bit_levels(&waveform, &[Frame { left: -1, right: 1 }]);
+        assert_eq!(bits.len(), 33);
+        // WS low for left, the MSB comes one bit later
+        let mut ws = [0; 33];
+        ws[16..32].copy_from_slice(&[1; 16]);
+        assert_eq!(levels(&bits, true), ws);
+        let mut sd = [0; 33];
+        sd[1..17].copy_from_slice(&[1; 16]);
+        sd[32] = 1;
+        assert_eq!(levels(&bits, false), sd);
+    }
+
+    #[test]
+    fn test_lsb_justified() {
+        let frame = Frame {
+            left: 0x800001,
+            right: 0,
+        };
+        let waveform = Waveform::new(
+            I2sStandard::Lsb,
+            DataFormat::Data24Channel32,
+            ClockPolarity::IdleLow,
+        );
+        let bits = bit_levels(&waveform, &[frame]);
+        let mut sd = [0; 64];
+        sd[8] = 1;
+        sd[31] = 1;
+        assert_eq!(levels(&bits, false), sd);
+        assert_eq!(levels(&bits, true)[..32], [1; 32]);
+        // MSB justified for comparison
+        let waveform = Waveform::new(
+            I2sStandard::Msb,
+            DataFormat::Data24Channel32,
+            ClockPolarity::IdleLow,
+        );
+        let bits = bit_levels(&waveform, &[frame]);
+        let mut sd = [0; 64];
+        sd[0] = 1;
+        sd[23] = 1;
+        assert_eq!(levels(&bits, false), sd);
+    }
+
+    #[test]
+    fn test_pcm_sync() {
+        let frames = [Frame { left: -1, right: 0 }; 2];
+        let short = Waveform::new(
+            I2sStandard::PcmShortSync,
+            DataFormat::Data16Channel16,
+            ClockPolarity::IdleLow,
+        );
+        let bits = bit_levels(&short, &frames);
+        let mut ws = [0; 33];
+        ws[0] = 1;
+        ws[16] = 1;
+        ws[32] = 1;
+        assert_eq!(levels(&bits, true), ws);
+        let mut sd = [1; 33];
+        sd[0] = 0;
+        assert_eq!(levels(&bits, false), sd);
+        let long = Waveform::new(
+            I2sStandard::PcmLongSync,
+            DataFormat::Data16Channel16,
+            ClockPolarity::IdleLow,
+        );
+        let bits = bit_levels(&long, &frames);
+        let mut ws = [0; 32];
+        ws[..13].copy_from_slice(&[1; 13]);
+        ws[16..29].copy_from_slice(&[1; 13]);
+        assert_eq!(levels(&bits, true), ws);
+        assert_eq!(levels(&bits, false), [1; 32]);
+    }
+
+    #[test]
+    fn test_decode_resync() {
+        let waveform = Waveform::new(
+            I2sStandard::Msb,
+            DataFormat::Data16Channel16,
+            ClockPolarity::IdleLow,
+        );
+        let frames = [Frame { left: 1, right: 2 }, Frame { left: 3, right: 4 }];
+        // capture starting in the middle of the first frame
+        let lines = waveform.encode(frames.iter().copied()).skip(10);
+        let decoded: Vec<_> = waveform.decode(lines).collect();
+        assert_eq!(decoded, frames[1..]);
+    }
+
+    #[test]
+    fn test_transfer_frames() {
+        use crate::marker::{Data16Channel16, Data32Channel32, Msb, PcmLongSync};
+        let frame = Frame::from_transfer::<Msb, Data32Channel32, _>(&(-2i32, 0x1234_5678i32));
+        assert_eq!(
+            frame,
+            Frame {
+                left: -2,
+                right: 0x1234_5678
+            }
+        );
+        let value: (i32, i32) = frame.to_transfer::<Msb, Data32Channel32, _>();
+        assert_eq!(value, (-2, 0x1234_5678));
+        let frame = Frame::from_transfer::<PcmLongSync, Data32Channel32, _>(&-3i32);
+        assert_eq!(frame, Frame { left: -3, right: 0 });
+        let frame = Frame { left: -1, right: 7 };
+        let value: (i16, i16) = frame.to_transfer::<Msb, Data16Channel16, _>();
+        assert_eq!(value, (-1, 7));
+        let waveform = Waveform::for_transfer::<Msb, Data16Channel16>(ClockPolarity::IdleLow);
+        assert_eq!(waveform.standard(), I2sStandard::Msb);
+    }
+}